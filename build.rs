@@ -1,6 +1,13 @@
+#[cfg(feature = "directx")]
 use hlsl_compiler::{compile_from_file_to_file, CompileFlags, CompileFromFileToFileError, EffectCompileFlags};
 
 fn main() {
+	#[cfg(feature = "directx")]
+	compile_shaders();
+}
+
+#[cfg(feature = "directx")]
+fn compile_shaders() {
 	println!("Compiling Pixel Shader");
 	let (result, messages) = compile_from_file_to_file(
 		"shaders/pixel_shader.hlsl".to_string(),
@@ -28,6 +35,7 @@ fn main() {
 	println!("cargo:rerun-if-changed=shaders/")
 }
 
+#[cfg(feature = "directx")]
 fn check_compile_result(result: Result<(), CompileFromFileToFileError>, messages: Option<Vec<u8>>) {
 	if let Some(message_blob) = messages {
 		print_messages(message_blob)
@@ -36,6 +44,7 @@ fn check_compile_result(result: Result<(), CompileFromFileToFileError>, messages
 	result.unwrap();
 }
 
+#[cfg(feature = "directx")]
 fn print_messages(message_blob: Vec<u8>) {
 	let message_string = String::from_utf8(message_blob).unwrap();
 	let messages = message_string.lines();
@@ -0,0 +1,73 @@
+use std::env;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+use raytrayce_r::{Camera, Color, CPURaytracer, Dielectric, GenerationMode, Lambertian, Metal, Raytracer, RaytracerSettings, RaytracerState, RaytracingScene, save_texture_to_path, Sphere, Vec3};
+
+const WIDTH: u32 = 400;
+const HEIGHT: u32 = 225;
+
+fn main() {
+	let output = env::args().nth(1).unwrap_or_else(|| "spheres.png".to_string());
+
+	let mut scene = RaytracingScene::new();
+
+	scene.camera = Camera::create(
+		Vec3::create(0.0, 1.0, 5.0),
+		Vec3::create(0.0, 0.5, 0.0),
+		Vec3::create(0.0, 1.0, 0.0),
+		40.0,
+		WIDTH as f64 / HEIGHT as f64,
+		5.0,
+		0.0
+	);
+
+	let ground = Arc::new(Lambertian::create(Color::create(0.5, 0.6, 0.3, 1.0)));
+	let diffuse = Arc::new(Lambertian::create(Color::create(0.7, 0.2, 0.2, 1.0)));
+	let metal = Arc::new(Metal::create(Color::create(0.8, 0.8, 0.8, 1.0), 0.05));
+	let glass = Arc::new(Dielectric::create(1.5));
+
+	scene.add_hittable(Arc::new(Sphere::create(Vec3::create(0.0, -1000.0, 0.0), 1000.0, ground)));
+	scene.add_hittable(Arc::new(Sphere::create(Vec3::create(-1.1, 0.5, 0.0), 0.5, diffuse)));
+	scene.add_hittable(Arc::new(Sphere::create(Vec3::create(0.0, 0.5, 0.0), 0.5, glass)));
+	scene.add_hittable(Arc::new(Sphere::create(Vec3::create(1.1, 0.5, 0.0), 0.5, metal)));
+
+	let settings = RaytracerSettings {
+		width: WIDTH,
+		height: HEIGHT,
+		samples_per_pixel: 32,
+		max_bounces: 8,
+		generation_mode: "tile,32x32/line".parse::<GenerationMode>().unwrap(),
+		..RaytracerSettings::default()
+	};
+
+	let mut raytracer = CPURaytracer::new(settings, Arc::new(scene));
+
+	// Step by step, Raytracer::render_to_completion does the same in one call
+	raytracer.start_rendering();
+
+	let mut reported_percentage = 0;
+
+	loop {
+		match raytracer.step() {
+			RaytracerState::Running { commissioned, completed } => {
+				let percentage = completed * 100 / commissioned;
+
+				if percentage >= reported_percentage + 10 {
+					println!("{}%", percentage);
+					reported_percentage = percentage;
+				}
+
+				sleep(Duration::from_millis(10));
+			}
+			RaytracerState::Finished { .. } => break,
+			state => panic!("render ended in state {:?}", state)
+		}
+	}
+
+	let texture = raytracer.get_current_texture();
+
+	save_texture_to_path(&output, texture).unwrap();
+
+	println!("Saved {}x{} image to {}", texture.get_width(), texture.get_height(), output);
+}
\ No newline at end of file
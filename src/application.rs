@@ -6,12 +6,13 @@ use windows::Win32::UI::WindowsAndMessaging::{DispatchMessageW, MSG, PeekMessage
 use std::mem::MaybeUninit;
 use crate::directx::DirectX;
 use crate::gui::{GUI};
-use crate::image::save_texture_to_path;
-use crate::raytracing::raytracer::cpu_raytracer::CPURaytracer;
-use crate::raytracing::raytracer::{Raytracer, RaytracerSettings};
-use crate::raytracing::raytracer::RaytracerState::Running;
-use crate::raytracing::scene::RaytracingScene;
-use crate::raytracing::work::generator::{GenerationMode};
+use raytrayce_r::image::{ExportSettings, save_render, save_texture_to_path};
+use raytrayce_r::presenter::Presenter;
+use raytrayce_r::raytracing::aov::Aov;
+use raytrayce_r::raytracing::raytracer::cpu_raytracer::CPURaytracer;
+use raytrayce_r::raytracing::raytracer::{Raytracer, RaytracerSettings};
+use raytrayce_r::raytracing::scene::RaytracingScene;
+use raytrayce_r::raytracing::scene_description::SceneDescription;
 use crate::rendering::win32::dxgidebug::dump_debug_messages;
 use crate::window::Window;
 
@@ -52,24 +53,41 @@ impl RaytracingApplication {
 		}
 	}
 
-	pub fn run(self) -> ! {
+	pub fn run(mut self) -> ! {
 		let aspect_ratio = self.width as f64 / self.height as f64;
 
-		let scene = Arc::new(RaytracingScene::create_scene(aspect_ratio));
-
 		let mut last_frame = Instant::now();
 		let mut gui = GUI::new(&self.window, &self.directx.device);
 
-		let generation_mode = GenerationMode::from_gui_mode_tree(&gui.state().mode_tree);
+		let scene = match gui.state().scene_path.as_str() {
+			"" => None,
+			path => load_scene(path, aspect_ratio)
+		};
+
+		let mut scene = Arc::new(scene.unwrap_or_else(|| RaytracingScene::create_scene(aspect_ratio)));
+
+		let generation_mode = gui.state().mode_tree.to_generation_mode();
 
 		let settings = RaytracerSettings {
 			width: self.width,
 			height: self.height,
 			samples_per_pixel: gui.state().samples_per_pixel,
 			max_bounces: gui.state().max_bounces,
-			generation_mode
+			russian_roulette_depth: gui.state().russian_roulette_depth,
+			integrator: gui.state().integrator,
+			generation_mode,
+			sampler: gui.state().sampler,
+			filter: gui.state().filter,
+			display: gui.state().display_settings(),
+			seed: gui.state().seed,
+			progressive: gui.state().progressive_settings(),
+			adaptive: gui.state().adaptive_settings(),
+			aovs: gui.state().aovs,
+			denoiser: gui.state().denoiser()
 		};
 
+		let mut display_settings = settings.display;
+		let mut denoiser = settings.denoiser;
 		let mut raytracer: Box<dyn Raytracer> = Box::new(CPURaytracer::new(settings, scene.clone()));
 		gui.update_raytracer_state(&raytracer);
 
@@ -97,27 +115,55 @@ impl RaytracingApplication {
 				}
 			}
 
-			self.directx.start_frame(raytracer.get_current_texture());
+			self.directx.start_frame(raytracer.get_current_texture(), raytracer.get_state());
 
 			gui.update_raytracer_state(&raytracer);
 
 			let imgui_result = gui.draw();
 
+			if gui.state().display_settings() != display_settings {
+				display_settings = gui.state().display_settings();
+				raytracer.set_display_settings(display_settings);
+			}
+
+			if gui.state().denoiser() != denoiser {
+				denoiser = gui.state().denoiser();
+				raytracer.set_denoiser(denoiser);
+			}
+
 			if imgui_result.render_start_button_clicked {
 				let samples_per_pixel = gui.state().samples_per_pixel;
 				let max_bounces = gui.state().max_bounces;
+				let russian_roulette_depth = gui.state().russian_roulette_depth;
+				let integrator = gui.state().integrator;
+				let sampler = gui.state().sampler;
+				let filter = gui.state().filter;
+				let seed = gui.state().seed;
+				let progressive = gui.state().progressive_settings();
+				let adaptive = gui.state().adaptive_settings();
+				let aovs = gui.state().aovs;
 
 				let gui_state_mut = gui.state_mut();
 				let mode_tree = &mut gui_state_mut.mode_tree;
 
-				let generation_mode = GenerationMode::from_gui_mode_tree(mode_tree);
+				let generation_mode = mode_tree.to_generation_mode();
 
 				let settings = RaytracerSettings {
 					width: self.width,
 					height: self.height,
 					samples_per_pixel,
 					max_bounces,
-					generation_mode
+					russian_roulette_depth,
+					integrator,
+					generation_mode,
+					sampler,
+					filter,
+					display: display_settings,
+					seed,
+					progressive,
+					adaptive,
+					aovs,
+					denoiser
 				};
 
 				raytracer.change_settings(settings);
@@ -125,23 +171,85 @@ impl RaytracingApplication {
 				raytracer.start_rendering();
 			}
 
+			if imgui_result.load_scene_button_clicked {
+				if let Some(new_scene) = load_scene(&gui.state().scene_path, aspect_ratio) {
+					if let Some(description) = new_scene.description() {
+						let gui_state_mut = gui.state_mut();
+						gui_state_mut.samples_per_pixel = description.render.samples_per_pixel.max(1);
+						gui_state_mut.max_bounces = description.render.max_bounces.max(1);
+						gui_state_mut.russian_roulette_depth = description.render.russian_roulette_depth;
+						gui_state_mut.integrator = description.render.integrator;
+						gui_state_mut.sampler = description.render.sampler;
+						gui_state_mut.filter = description.render.filter;
+						gui_state_mut.seed = description.render.seed;
+						gui_state_mut.exposure = description.render.exposure;
+						gui_state_mut.tone_mapping = description.render.tone_mapping;
+						gui_state_mut.white_point = description.render.white_point;
+					}
+
+					raytracer.stop_rendering();
+					scene = Arc::new(new_scene);
+					raytracer.set_scene(scene.clone());
+				}
+			}
+
 			if imgui_result.render_stop_button_clicked {
 				raytracer.stop_rendering();
 			}
 
-			if imgui_result.export_button_clicked {
-				save_texture_to_path(&gui.state().image_path, raytracer.get_current_texture()).unwrap();
+			if imgui_result.render_pause_button_clicked {
+				raytracer.pause_rendering();
 			}
 
-			if let Running { .. } = raytracer.get_state() {
-				raytracer.update();
+			if imgui_result.render_resume_button_clicked {
+				raytracer.continue_rendering();
 			}
 
+			if imgui_result.export_button_clicked {
+				let image_path = &gui.state().image_path;
+
+				// Writes whatever AOVs the last render produced, raytracers without a film can't write HDR files
+				let result = match raytracer.get_film() {
+					Some(film) => {
+						let export_settings = ExportSettings {
+							denoiser,
+							..ExportSettings::new()
+						};
+
+						save_render(image_path, raytracer.get_current_texture(), film, &display_settings, &Aov::ALL, &export_settings).map(|_| ())
+					}
+					None => save_texture_to_path(image_path, raytracer.get_current_texture())
+				};
+
+				if let Err(error) = result {
+					eprintln!("Error saving image to {}: {}", image_path, error);
+				}
+			}
+
+			// Also called after the render is done, the denoiser may still be working on it
+			raytracer.update();
+
 			self.directx.end_frame();
 		});
 	}
 }
 
+// The image size is fixed by the window, so the scene camera always uses the window aspect ratio
+fn load_scene(path: &str, aspect_ratio: f64) -> Option<RaytracingScene> {
+	let scene = SceneDescription::load(path).and_then(|mut description| {
+		description.camera.aspect_ratio = Some(aspect_ratio);
+		description.build()
+	});
+
+	match scene {
+		Ok(scene) => Some(scene),
+		Err(error) => {
+			eprintln!("Error loading scene {}: {}", path, error);
+			None
+		}
+	}
+}
+
 fn handle_messages() -> bool {
     loop {
         let message = peek_message();
@@ -0,0 +1,306 @@
+use std::env;
+use std::process::exit;
+use std::str::FromStr;
+use std::sync::Arc;
+use raytrayce_r::{Aov, ConsolePresenter, CPURaytracer, Denoiser, ExportSettings, ExrPrecision, Filter, GenerationMode, Integrator, PngBitDepth, Raytracer, RaytracerState, SamplerType, SceneDescription, SceneError, save_linear_texture, save_render, ToneMapping};
+
+const USAGE: &str = "\
+Usage: raytrayce_cli [OPTIONS] --output <PATH>
+
+Options:
+  -o, --output <PATH>          Image file to write, the format follows the extension, .exr and .hdr files are linear
+      --exr-precision <TYPE>   Channel type of OpenEXR output, half or float [default: half]
+      --png-bit-depth <BITS>   Bits per channel of PNG output, 8 or 16 [default: 8]
+      --aovs <LIST>            Also write these render passes, comma separated or all: depth, normal, albedo, object_id,
+                               material_id, position, direct, indirect. OpenEXR output stores them as layers, other
+                               formats as files named like render.depth.png
+      --separate-aovs          Write AOVs to separate files for OpenEXR output too
+      --denoise                Denoise the image before writing it, guided by the albedo, normal and depth AOVs
+  -s, --scene <SCENE>          Scene file, or one of the built-in scenes random-spheres, cornell-box [default: random-spheres]
+      --save-scene <PATH>      Write the scene, including the options below, to a scene file
+  -w, --width <PIXELS>         Image width [default: from scene]
+  -h, --height <PIXELS>        Image height [default: from scene]
+  -n, --samples <COUNT>        Samples per pixel [default: from scene]
+  -b, --bounces <COUNT>        Safety cap on the path length [default: from scene]
+      --roulette-depth <COUNT> Bounces before Russian roulette can end paths [default: from scene]
+  -i, --integrator <NAME>      path_tracer, or a debug view: normal, depth with an optional distance for white,
+                               albedo, uv, bounce_count, material_id, bvh_cost with an optional node count for red,
+                               ambient_occlusion with an optional radius, e.g. ambient_occlusion,0.5
+                               [default: from scene]
+  -m, --mode <MODE>            Generation mode [default: from scene]
+      --sampler <SAMPLER>      Sampler, one of independent, stratified, halton, sobol [default: from scene]
+      --filter <FILTER>        Reconstruction filter, one of box, tent, gaussian, mitchell, lanczos with an optional radius, e.g. mitchell,2 [default: from scene]
+      --exposure <EV>          Exposure in stops applied before tone mapping [default: from scene]
+      --tone-mapping <OP>      Tone mapping, one of clamp, reinhard, extended_reinhard, aces_fitted, agx [default: from scene]
+      --white-point <VALUE>    Radiance that extended_reinhard maps to white [default: from scene]
+      --seed <SEED>            Seed for the random numbers, equal settings give identical images [default: from scene]
+  -p, --progressive <N>        Render the whole image in passes of N samples per pixel [default: from scene]
+  -a, --adaptive <ERROR>       Stop sampling pixels once their relative error drops below ERROR, --samples is the maximum [default: from scene]
+      --min-samples <COUNT>    Minimum samples per pixel when sampling adaptively [default: from scene, or 16]
+      --sample-counts <PATH>   Write a heatmap of the samples taken per pixel
+      --help                   Print this help
+
+Generation modes:
+  pixel
+  line[,reverse][,random][,transparent]
+  stripe[,random][,reverse][,transparent]
+  tile,<W>x<H>[,line-first|stripe-first|random][,reverse-x|random-x][,reverse-y|random-y][,transparent][/<MODE>]";
+
+struct CommandLineSettings {
+	output: Option<String>,
+	export_settings: ExportSettings,
+	aovs: Vec<Aov>,
+	scene: String,
+	save_scene: Option<String>,
+	width: Option<u32>,
+	height: Option<u32>,
+	samples_per_pixel: Option<usize>,
+	max_bounces: Option<usize>,
+	russian_roulette_depth: Option<usize>,
+	integrator: Option<Integrator>,
+	generation_mode: Option<String>,
+	sampler: Option<SamplerType>,
+	filter: Option<Filter>,
+	exposure: Option<f64>,
+	tone_mapping: Option<ToneMapping>,
+	white_point: Option<f64>,
+	seed: Option<u64>,
+	samples_per_pass: Option<usize>,
+	error_threshold: Option<f64>,
+	min_samples_per_pixel: Option<usize>,
+	sample_counts: Option<String>
+}
+
+impl CommandLineSettings {
+	fn parse<I: Iterator<Item = String>>(mut arguments: I) -> Result<Option<Self>, String> {
+		let mut settings = Self {
+			output: None,
+			export_settings: ExportSettings::new(),
+			aovs: Vec::new(),
+			scene: "random-spheres".to_string(),
+			save_scene: None,
+			width: None,
+			height: None,
+			samples_per_pixel: None,
+			max_bounces: None,
+			russian_roulette_depth: None,
+			integrator: None,
+			generation_mode: None,
+			sampler: None,
+			filter: None,
+			exposure: None,
+			tone_mapping: None,
+			white_point: None,
+			seed: None,
+			samples_per_pass: None,
+			error_threshold: None,
+			min_samples_per_pixel: None,
+			sample_counts: None
+		};
+
+		while let Some(argument) = arguments.next() {
+			if argument == "--help" {
+				return Ok(None);
+			}
+
+			let mut value = || arguments.next().ok_or_else(|| format!("missing value for '{}'", argument));
+
+			match argument.as_str() {
+				"-o" | "--output" => settings.output = Some(value()?),
+				"--exr-precision" => {
+					settings.export_settings.exr_precision = match value()?.as_str() {
+						"half" => ExrPrecision::Half,
+						"float" => ExrPrecision::Float,
+						precision => return Err(format!("invalid value '{}' for '{}'", precision, argument))
+					};
+				}
+				"--png-bit-depth" => {
+					settings.export_settings.png_bit_depth = match value()?.as_str() {
+						"8" => PngBitDepth::Eight,
+						"16" => PngBitDepth::Sixteen,
+						bit_depth => return Err(format!("invalid value '{}' for '{}'", bit_depth, argument))
+					};
+				}
+				"--aovs" => settings.aovs = Aov::parse_list(&value()?)?,
+				"--separate-aovs" => settings.export_settings.separate_aov_files = true,
+				"--denoise" => settings.export_settings.denoiser = Some(Denoiser::new()),
+				"-s" | "--scene" => settings.scene = value()?,
+				"--save-scene" => settings.save_scene = Some(value()?),
+				"-w" | "--width" => settings.width = Some(parse_value(&argument, &value()?)?),
+				"-h" | "--height" => settings.height = Some(parse_value(&argument, &value()?)?),
+				"-n" | "--samples" => settings.samples_per_pixel = Some(parse_value(&argument, &value()?)?),
+				"-b" | "--bounces" => settings.max_bounces = Some(parse_value(&argument, &value()?)?),
+				"--roulette-depth" => settings.russian_roulette_depth = Some(parse_value(&argument, &value()?)?),
+				"-i" | "--integrator" => settings.integrator = Some(Integrator::from_str(&value()?)?),
+				"-m" | "--mode" => {
+					let generation_mode = value()?;
+					GenerationMode::from_str(&generation_mode)?;
+					settings.generation_mode = Some(generation_mode);
+				}
+				"--sampler" => settings.sampler = Some(SamplerType::from_str(&value()?)?),
+				"--filter" => settings.filter = Some(Filter::from_str(&value()?)?),
+				"--exposure" => settings.exposure = Some(parse_value(&argument, &value()?)?),
+				"--tone-mapping" => settings.tone_mapping = Some(ToneMapping::from_str(&value()?)?),
+				"--white-point" => settings.white_point = Some(parse_value(&argument, &value()?)?),
+				"--seed" => settings.seed = Some(parse_value(&argument, &value()?)?),
+				"-p" | "--progressive" => settings.samples_per_pass = Some(parse_value(&argument, &value()?)?),
+				"-a" | "--adaptive" => settings.error_threshold = Some(parse_value(&argument, &value()?)?),
+				"--min-samples" => settings.min_samples_per_pixel = Some(parse_value(&argument, &value()?)?),
+				"--sample-counts" => settings.sample_counts = Some(value()?),
+				_ => return Err(format!("unknown argument '{}'", argument))
+			}
+		}
+
+		if settings.output.is_none() && settings.save_scene.is_none() {
+			return Err("no output path given".to_string());
+		}
+		if settings.width == Some(0) || settings.height == Some(0) {
+			return Err("width and height must be at least 1".to_string());
+		}
+		if settings.samples_per_pixel == Some(0) || settings.max_bounces == Some(0) || settings.samples_per_pass == Some(0) {
+			return Err("samples and bounces must be at least 1".to_string());
+		}
+		if let Some(error_threshold) = settings.error_threshold {
+			if error_threshold.is_nan() || error_threshold <= 0.0 {
+				return Err("the adaptive error threshold must be greater than 0".to_string());
+			}
+		}
+
+		Ok(Some(settings))
+	}
+
+	fn load_scene_description(&self) -> Result<SceneDescription, SceneError> {
+		let mut description = match SceneDescription::built_in(&self.scene) {
+			Some(description) => description,
+			None => SceneDescription::load(&self.scene)?
+		};
+
+		let render = &mut description.render;
+
+		if let Some(width) = self.width {
+			render.width = width;
+		}
+		if let Some(height) = self.height {
+			render.height = height;
+		}
+		if let Some(samples_per_pixel) = self.samples_per_pixel {
+			render.samples_per_pixel = samples_per_pixel;
+		}
+		if let Some(max_bounces) = self.max_bounces {
+			render.max_bounces = max_bounces;
+		}
+		if let Some(russian_roulette_depth) = self.russian_roulette_depth {
+			render.russian_roulette_depth = russian_roulette_depth;
+		}
+		if let Some(integrator) = self.integrator {
+			render.integrator = integrator;
+		}
+		if let Some(generation_mode) = &self.generation_mode {
+			render.generation_mode = generation_mode.clone();
+		}
+		if let Some(sampler) = self.sampler {
+			render.sampler = sampler;
+		}
+		if let Some(filter) = self.filter {
+			render.filter = filter;
+		}
+		if let Some(exposure) = self.exposure {
+			render.exposure = exposure;
+		}
+		if let Some(tone_mapping) = self.tone_mapping {
+			render.tone_mapping = tone_mapping;
+		}
+		if let Some(white_point) = self.white_point {
+			render.white_point = white_point;
+		}
+		if let Some(seed) = self.seed {
+			render.seed = seed;
+		}
+		if let Some(samples_per_pass) = self.samples_per_pass {
+			render.samples_per_pass = Some(samples_per_pass);
+		}
+		if let Some(error_threshold) = self.error_threshold {
+			render.error_threshold = Some(error_threshold);
+		}
+		if let Some(min_samples_per_pixel) = self.min_samples_per_pixel {
+			render.min_samples_per_pixel = Some(min_samples_per_pixel);
+		}
+
+		Ok(description)
+	}
+}
+
+fn parse_value<T: FromStr>(argument: &str, value: &str) -> Result<T, String> {
+	value.parse::<T>().map_err(|_| format!("invalid value '{}' for '{}'", value, argument))
+}
+
+fn exit_with_error<E: std::fmt::Display>(error: E) -> ! {
+	eprintln!("Error: {}", error);
+	exit(1);
+}
+
+fn main() {
+	let settings = match CommandLineSettings::parse(env::args().skip(1)) {
+		Ok(Some(settings)) => settings,
+		Ok(None) => {
+			println!("{}", USAGE);
+			return;
+		}
+		Err(error) => {
+			eprintln!("Error: {}\n\n{}", error, USAGE);
+			exit(2);
+		}
+	};
+
+	let description = settings.load_scene_description().unwrap_or_else(|error| exit_with_error(error));
+
+	if let Some(path) = &settings.save_scene {
+		description.save(path).unwrap_or_else(|error| exit_with_error(error));
+		eprintln!("Saved scene to {}", path);
+	}
+
+	let output = match &settings.output {
+		None => return,
+		Some(output) => output
+	};
+
+	let mut raytracer_settings = description.render.to_settings().unwrap_or_else(|error| exit_with_error(error));
+	// The denoiser needs the AOVs as guides even when none get written
+	raytracer_settings.aovs = !settings.aovs.is_empty() || settings.export_settings.denoiser.is_some();
+
+	let display = raytracer_settings.display;
+	let scene = description.build().unwrap_or_else(|error| exit_with_error(error));
+
+	let mut raytracer = CPURaytracer::new(raytracer_settings, Arc::new(scene));
+
+	let mut presenter = ConsolePresenter::new();
+
+	match raytracer.present_to_completion(&mut presenter) {
+		RaytracerState::Finished { .. } => {}
+		state => exit_with_error(format!("raytracer stopped unexpectedly in state {:?}", state))
+	}
+
+	match save_render(output, raytracer.get_current_texture(), raytracer.film(), &display, &settings.aovs, &settings.export_settings) {
+		Ok(paths) => {
+			for path in paths {
+				eprintln!("Saved image to {}", path.display());
+			}
+		}
+		Err(error) => {
+			eprintln!("Error saving image to {}: {}", output, error);
+			exit(1);
+		}
+	}
+
+	if let Some(path) = &settings.sample_counts {
+		match save_linear_texture(path, &raytracer.film().sample_count_texture(), &settings.export_settings) {
+			Ok(_) => {
+				eprintln!("Saved sample counts to {}", path);
+			}
+			Err(error) => {
+				eprintln!("Error saving sample counts to {}: {}", path, error);
+				exit(1);
+			}
+		}
+	}
+}
\ No newline at end of file
@@ -12,8 +12,10 @@ use windows::Win32::Graphics::Dxgi::Common::{DXGI_FORMAT_R32G32_FLOAT, DXGI_FORM
 use std::intrinsics::copy;
 use std::io::Read;
 use std::str::FromStr;
-use crate::raytracing::color::Color;
-use crate::raytracing::texture::Texture;
+use raytrayce_r::raytracing::color::Color;
+use raytrayce_r::raytracing::texture::Texture;
+use raytrayce_r::raytracing::raytracer::RaytracerState;
+use raytrayce_r::presenter::Presenter;
 use crate::rendering::{bind_background_texture, bind_index_buffer, bind_input_layout, bind_pixel_shader, bind_render_target_view, bind_vertex_buffer, bind_vertex_shader, clear_render_target_view, create_back_buffer, create_background_texture, create_background_texture_resource_view, create_index_buffer, create_input_layout, create_pixel_shader, create_render_target_view, create_vertex_buffer, create_vertex_shader, draw_indexed, map_background_texture, present, set_primitive_topology, set_viewport, setup_directx_device_and_swapchain, unmap_background_texture};
 use crate::rendering::win32::dxgidebug::dump_debug_messages;
 use crate::rendering::win32::errhandlingapi::get_last_error;
@@ -105,11 +107,14 @@ impl DirectX {
 		}
 	}
 
-	pub fn start_frame(&self, texture: &Texture) {
+}
+
+impl Presenter for DirectX {
+	fn start_frame(&mut self, texture: &Texture, _state: RaytracerState) {
 		draw_background_texture(&texture, &self.background_texture_resource, &self.swap_chain, &self.device, &self.device_context);
 	}
 
-	pub fn end_frame(&self) {
+	fn end_frame(&mut self) {
 		present(1,0, &self.swap_chain).unwrap();
 	}
 }
@@ -8,7 +8,13 @@ use imgui_winit_support::{HiDpiMode, WinitPlatform};
 use windows::Win32::Graphics::Direct3D11::ID3D11Device;
 use winit::event::Event;
 use serde_derive::{Serialize, Deserialize};
-use crate::raytracing::raytracer::{Raytracer, RaytracerState};
+use raytrayce_r::raytracing::raytracer::{AdaptiveSettings, ProgressiveSettings, Raytracer, RaytracerState};
+use raytrayce_r::raytracing::denoiser::Denoiser;
+use raytrayce_r::raytracing::filter::Filter;
+use raytrayce_r::raytracing::integrator::Integrator;
+use raytrayce_r::raytracing::sampler::SamplerType;
+use raytrayce_r::raytracing::tone_mapping::{DisplaySettings, ToneMapping};
+use raytrayce_r::raytracing::work::generator::{GenerationMode, TileAxisOrder, TileDimensionOrder};
 use crate::window::Window;
 
 pub const SETTINGS_PATH: &'static str = "./settings.json";
@@ -20,10 +26,102 @@ pub struct GUIState {
     pub selected: usize,
     pub samples_per_pixel: usize,
     pub max_bounces: usize,
-    pub mode_tree: GUIModeTree
+    pub mode_tree: GUIModeTree,
+    #[serde(default)]
+    pub scene_path: String,
+    #[serde(default)]
+    pub sampler: SamplerType,
+    #[serde(default)]
+    pub filter: Filter,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub progressive: bool,
+    #[serde(default = "default_samples_per_pass")]
+    pub samples_per_pass: usize,
+    #[serde(default)]
+    pub adaptive: bool,
+    #[serde(default = "default_min_samples_per_pixel")]
+    pub min_samples_per_pixel: usize,
+    #[serde(default = "default_error_threshold")]
+    pub error_threshold: f64,
+    #[serde(default)]
+    pub exposure: f64,
+    #[serde(default)]
+    pub tone_mapping: ToneMapping,
+    #[serde(default = "default_white_point")]
+    pub white_point: f64,
+    #[serde(default)]
+    pub aovs: bool,
+    #[serde(default)]
+    pub denoise: bool,
+    #[serde(default = "default_russian_roulette_depth")]
+    pub russian_roulette_depth: usize,
+    #[serde(default)]
+    pub integrator: Integrator,
+    // The current film has no albedo, normal and depth guides, so the denoiser can only blur it
+    #[serde(skip)]
+    pub denoiser_guides_missing: bool
+}
+
+fn default_russian_roulette_depth() -> usize {
+    3
+}
+
+fn default_samples_per_pass() -> usize {
+    1
+}
+
+fn default_min_samples_per_pixel() -> usize {
+    AdaptiveSettings::new().min_samples_per_pixel
+}
+
+fn default_error_threshold() -> f64 {
+    AdaptiveSettings::new().error_threshold
+}
+
+fn default_white_point() -> f64 {
+    DisplaySettings::new().white_point
 }
 
 impl GUIState {
+    pub fn progressive_settings(&self) -> Option<ProgressiveSettings> {
+        if !self.progressive {
+            return None;
+        }
+
+        Some(ProgressiveSettings {
+            samples_per_pass: self.samples_per_pass
+        })
+    }
+
+    pub fn adaptive_settings(&self) -> Option<AdaptiveSettings> {
+        if !self.adaptive {
+            return None;
+        }
+
+        Some(AdaptiveSettings {
+            min_samples_per_pixel: self.min_samples_per_pixel,
+            error_threshold: self.error_threshold
+        })
+    }
+
+    pub fn display_settings(&self) -> DisplaySettings {
+        DisplaySettings {
+            exposure: self.exposure,
+            tone_mapping: self.tone_mapping,
+            white_point: self.white_point
+        }
+    }
+
+    pub fn denoiser(&self) -> Option<Denoiser> {
+        if !self.denoise {
+            return None;
+        }
+
+        Some(Denoiser::new())
+    }
+
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Self {
         let file = File::open(path);
 
@@ -78,8 +176,25 @@ impl Default for GUIState {
             image_path: "".to_string(),
             selected: 0,
             samples_per_pixel: 100,
-            max_bounces: 10,
-            mode_tree: initial_mode_tree
+            max_bounces: 64,
+            mode_tree: initial_mode_tree,
+            scene_path: "".to_string(),
+            sampler: SamplerType::Independent,
+            filter: Filter::new(),
+            seed: 0,
+            progressive: false,
+            samples_per_pass: default_samples_per_pass(),
+            adaptive: false,
+            min_samples_per_pixel: default_min_samples_per_pixel(),
+            error_threshold: default_error_threshold(),
+            exposure: 0.0,
+            tone_mapping: ToneMapping::Clamp,
+            white_point: default_white_point(),
+            aovs: false,
+            denoise: false,
+            russian_roulette_depth: default_russian_roulette_depth(),
+            integrator: Integrator::PathTracer,
+            denoiser_guides_missing: false
         };
 
         imgui_state
@@ -89,7 +204,10 @@ impl Default for GUIState {
 pub struct GUIResult {
     pub render_start_button_clicked: bool,
     pub render_stop_button_clicked: bool,
+    pub render_pause_button_clicked: bool,
+    pub render_resume_button_clicked: bool,
     pub export_button_clicked: bool,
+    pub load_scene_button_clicked: bool,
 }
 
 pub struct GUI {
@@ -107,6 +225,48 @@ pub struct GUIModeTree {
     pub sub_tree: Option<Box<GUIModeTree>>
 }
 
+impl GUIModeTree {
+    pub fn to_generation_mode(&self) -> GenerationMode {
+        match &self.settings {
+            GUIModeSettings::PixelRandom => {
+                GenerationMode::PixelRandom
+            }
+            GUIModeSettings::Line { reverse_order_horizontal, random_order_vertical, transparent } => {
+                GenerationMode::Line {
+                    reverse_order_horizontal: *reverse_order_horizontal,
+                    random_order_vertical: *random_order_vertical,
+                    transparent: *transparent
+                }
+            }
+            GUIModeSettings::Stripe { random_order_horizontal, reverse_order_vertical, transparent } => {
+                GenerationMode::Stripe {
+                    random_order_horizontal: *random_order_horizontal,
+                    reverse_order_vertical: *reverse_order_vertical,
+                    transparent: *transparent
+                }
+            }
+            GUIModeSettings::Tile { tile_width, tile_height, dimension_order, horizontal_order, vertical_order, transparent } => {
+                let dimension_order = indices_to_tile_dimension_order(*horizontal_order, *vertical_order, *dimension_order);
+
+                let sub_tree = match &self.sub_tree {
+                    None => panic!(),
+                    Some(sub_tree) => sub_tree
+                };
+
+                let sub_generation_mode = sub_tree.to_generation_mode();
+
+                GenerationMode::Tile {
+                    tile_width: *tile_width,
+                    tile_height: *tile_height,
+                    dimension_order,
+                    transparent: *transparent,
+                    sub_generation_mode: Box::new(sub_generation_mode)
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum GUIModeSettings {
     PixelRandom,
@@ -158,6 +318,8 @@ impl GUI {
         let state = raytracer.get_state();
 
         self.state.raytracer_state = state;
+        self.state.denoiser_guides_missing = !matches!(state, RaytracerState::Created)
+            && raytracer.get_film().is_some_and(|film| !film.has_aovs());
     }
 
     pub fn handle_event(&mut self, event: Event<()>) {
@@ -176,8 +338,10 @@ impl GUI {
         self.platform.prepare_frame(self.context.io_mut(), &self.window).unwrap();
         let ui = self.context.frame();
 
-        let (render_start_button_clicked, render_stop_button_clicked) = Self::draw_render_window(&mut self.state, &ui);
+        let (render_start_button_clicked, render_stop_button_clicked, render_pause_button_clicked, render_resume_button_clicked) = Self::draw_render_window(&mut self.state, &ui);
         let export_button_clicked = Self::draw_export_window(&mut self.state, &ui);
+        let load_scene_button_clicked = Self::draw_scene_window(&mut self.state, &ui);
+        Self::draw_display_window(&mut self.state, &ui);
         Self::draw_order_window(&mut self.state, &ui);
 
         self.platform.prepare_render(&ui, &self.window);
@@ -186,24 +350,31 @@ impl GUI {
         GUIResult {
             render_start_button_clicked,
             render_stop_button_clicked,
-            export_button_clicked
+            render_pause_button_clicked,
+            render_resume_button_clicked,
+            export_button_clicked,
+            load_scene_button_clicked
         }
     }
 
-    fn draw_render_window(state: &mut GUIState, ui: &Ui) -> (bool, bool) {
+    fn draw_render_window(state: &mut GUIState, ui: &Ui) -> (bool, bool, bool, bool) {
         let mut render_start_button_clicked: bool = false;
         let mut render_stop_button_clicked: bool = false;
+        let mut render_pause_button_clicked: bool = false;
+        let mut render_resume_button_clicked: bool = false;
 
         imgui::Window::new("Render")
             .size([300.0, 100.0], Condition::FirstUseEver)
             .build(&ui, || {
                 let mut samples_per_pixel: i32 = state.samples_per_pixel as i32;
                 let mut max_bounces: i32 = state.max_bounces as i32;
+                let mut russian_roulette_depth: i32 = state.russian_roulette_depth as i32;
 
                 let [text_width, _] = ui.calc_text_size("Samples per Pixel");
                 ui.push_item_width(-(text_width + 5.0));
                 ui.input_int("Samples per Pixel", &mut samples_per_pixel).build();
                 ui.input_int("Max Bounces", &mut max_bounces).build();
+                ui.input_int("Roulette Depth", &mut russian_roulette_depth).build();
 
                 if samples_per_pixel < 1 {
                     samples_per_pixel = 1;
@@ -211,13 +382,115 @@ impl GUI {
                 if max_bounces < 1 {
                     max_bounces = 1;
                 }
+                if russian_roulette_depth < 0 {
+                    russian_roulette_depth = 0;
+                }
 
                 state.samples_per_pixel = samples_per_pixel as usize;
                 state.max_bounces = max_bounces as usize;
+                state.russian_roulette_depth = russian_roulette_depth as usize;
+
+                let mut sampler = sampler_to_index(state.sampler);
+                ui.combo_simple_string("Sampler", &mut sampler, &["Independent", "Stratified", "Halton", "Sobol"]);
+                state.sampler = index_to_sampler(sampler);
+
+                let mut filter = filter_to_index(&state.filter);
+                ui.combo_simple_string("Filter", &mut filter, &["Box", "Tent", "Gaussian", "Mitchell", "Lanczos"]);
+
+                if filter != filter_to_index(&state.filter) {
+                    state.filter = index_to_filter(filter);
+                }
+
+                let mut filter_radius: f32 = state.filter.radius() as f32;
+                ui.input_float("Filter Radius", &mut filter_radius).step(0.5).build();
+
+                if filter_radius < 0.5 {
+                    filter_radius = 0.5;
+                }
+                state.filter = state.filter.with_radius(filter_radius as f64);
+
+                let mut seed: i32 = state.seed as i32;
+                ui.input_int("Seed", &mut seed).build();
+
+                if seed < 0 {
+                    seed = 0;
+                }
+                state.seed = seed as u64;
+
+                let mut integrator = integrator_to_index(&state.integrator);
+                ui.combo_simple_string("Integrator", &mut integrator, &["Path Tracer", "Normal", "Depth", "Albedo", "UV", "Bounce Count", "Material ID", "BVH Cost", "Ambient Occlusion"]);
+
+                if integrator != integrator_to_index(&state.integrator) {
+                    state.integrator = index_to_integrator(integrator);
+                }
+
+                match &mut state.integrator {
+                    Integrator::Depth { max_depth } => {
+                        let mut depth: f32 = *max_depth as f32;
+                        ui.input_float("Max Depth", &mut depth).step(1.0).build();
+
+                        *max_depth = depth.max(0.001) as f64;
+                    }
+                    Integrator::BvhCost { max_nodes } => {
+                        let mut nodes: i32 = *max_nodes as i32;
+                        ui.input_int("Max Nodes", &mut nodes).build();
+
+                        *max_nodes = nodes.max(1) as usize;
+                    }
+                    Integrator::AmbientOcclusion { radius } => {
+                        let mut occlusion_radius: f32 = *radius as f32;
+                        ui.input_float("Occlusion Radius", &mut occlusion_radius).step(0.1).build();
+
+                        *radius = occlusion_radius.max(0.001) as f64;
+                    }
+                    _ => {}
+                }
+
+                ui.checkbox("Progressive", &mut state.progressive);
+
+                if state.progressive {
+                    let mut samples_per_pass: i32 = state.samples_per_pass as i32;
+                    ui.input_int("Samples per Pass", &mut samples_per_pass).build();
+
+                    if samples_per_pass < 1 {
+                        samples_per_pass = 1;
+                    }
+                    state.samples_per_pass = samples_per_pass as usize;
+                }
+
+                ui.checkbox("Adaptive", &mut state.adaptive);
+
+                if state.adaptive {
+                    let mut min_samples_per_pixel: i32 = state.min_samples_per_pixel as i32;
+                    let mut error_threshold: f32 = state.error_threshold as f32;
+                    ui.input_int("Min Samples", &mut min_samples_per_pixel).build();
+                    ui.input_float("Error Threshold", &mut error_threshold).step(0.001).build();
+
+                    if min_samples_per_pixel < 1 {
+                        min_samples_per_pixel = 1;
+                    }
+                    if error_threshold < 0.0001 {
+                        error_threshold = 0.0001;
+                    }
+                    state.min_samples_per_pixel = min_samples_per_pixel as usize;
+                    state.error_threshold = error_threshold as f64;
+                }
+
+                ui.checkbox("AOVs", &mut state.aovs);
 
                 render_start_button_clicked = ui.button("Start Render");
                 render_stop_button_clicked = ui.button("Stop Render");
 
+                match state.raytracer_state {
+                    RaytracerState::Running { .. } => {
+                        render_pause_button_clicked = ui.button("Pause Render");
+                    }
+                    RaytracerState::Paused { .. } => {
+                        render_resume_button_clicked = ui.button("Resume Render");
+                    }
+                    _ => {}
+                }
+
                 ui.text("Rendering:");
                 match state.raytracer_state {
                     RaytracerState::Created => {
@@ -229,8 +502,11 @@ impl GUI {
 
                         ui.text(text);
                     }
-                    RaytracerState::Paused { .. } => {
-                        ui.text("Paused...");
+                    RaytracerState::Paused { commissioned, completed } => {
+                        let percentage = (completed as f32 / commissioned as f32) * 100.0;
+                        let text = format!("Paused at {}/{} : {}%", completed, commissioned, percentage);
+
+                        ui.text(text);
                     }
                     RaytracerState::Finished { .. } => {
                         ui.text("Done!");
@@ -241,7 +517,7 @@ impl GUI {
                 }
             });
 
-        (render_start_button_clicked, render_stop_button_clicked)
+        (render_start_button_clicked, render_stop_button_clicked, render_pause_button_clicked, render_resume_button_clicked)
     }
 
     fn draw_export_window(state: &mut GUIState, ui: &Ui) -> bool {
@@ -260,6 +536,56 @@ impl GUI {
         export_button_clicked
     }
 
+    fn draw_scene_window(state: &mut GUIState, ui: &Ui) -> bool {
+        let mut load_scene_button_clicked: bool = false;
+
+        imgui::Window::new("Scene")
+            .size([300.0, 100.0], Condition::FirstUseEver)
+            .build(&ui, || {
+                let [text_width, _] = ui.calc_text_size("Scene Path");
+                ui.push_item_width(-(text_width + 5.0));
+                imgui::InputText::new(&ui, "Scene Path", &mut state.scene_path)
+                    .build();
+                load_scene_button_clicked = ui.button("Load Scene");
+            });
+
+        load_scene_button_clicked
+    }
+
+    fn draw_display_window(state: &mut GUIState, ui: &Ui) {
+        imgui::Window::new("Display")
+            .size([300.0, 100.0], Condition::FirstUseEver)
+            .build(&ui, || {
+                let [text_width, _] = ui.calc_text_size("Tone Mapping");
+                ui.push_item_width(-(text_width + 5.0));
+
+                let mut exposure: f32 = state.exposure as f32;
+                imgui::Slider::new("Exposure", -10.0, 10.0).build(&ui, &mut exposure);
+                state.exposure = exposure as f64;
+
+                let mut tone_mapping = tone_mapping_to_index(state.tone_mapping);
+                ui.combo_simple_string("Tone Mapping", &mut tone_mapping, &["Clamp", "Reinhard", "Extended Reinhard", "ACES Fitted", "AgX"]);
+                state.tone_mapping = index_to_tone_mapping(tone_mapping);
+
+                if state.tone_mapping == ToneMapping::ExtendedReinhard {
+                    let mut white_point: f32 = state.white_point as f32;
+                    ui.input_float("White Point", &mut white_point).step(0.5).build();
+
+                    if white_point < 0.1 {
+                        white_point = 0.1;
+                    }
+                    state.white_point = white_point as f64;
+                }
+
+                // Passes started after turning it on collect the guides, earlier ones have to be rendered again
+                ui.checkbox("Denoise", &mut state.denoise);
+
+                if state.denoise && state.denoiser_guides_missing {
+                    ui.text("This render has no guides, render it again to keep the edges sharp");
+                }
+            });
+    }
+
     fn draw_order_window(state: &mut GUIState, ui: &Ui) {
         imgui::Window::new("Draw Order")
             .size([300.0, 100.0], Condition::FirstUseEver)
@@ -416,4 +742,127 @@ fn index_to_sub_tree(index: usize) -> Option<Box<GUIModeTree>> {
         }
         _ => None
     }
+}
+
+fn indices_to_tile_dimension_order(horizontal_order: usize, vertical_order: usize, dimension_order: usize) -> TileDimensionOrder {
+    let horizontal_order = index_to_tile_axis_order(horizontal_order);
+    let vertical_order = index_to_tile_axis_order(vertical_order);
+
+    match dimension_order {
+        0 => {
+            TileDimensionOrder::LineFirst {
+                horizontal_order,
+                vertical_order
+            }
+        }
+        1 => {
+            TileDimensionOrder::StripeFirst {
+                horizontal_order,
+                vertical_order
+            }
+        }
+        _ => {
+            TileDimensionOrder::Random
+        }
+    }
+}
+
+fn sampler_to_index(sampler: SamplerType) -> usize {
+    match sampler {
+        SamplerType::Independent => 0,
+        SamplerType::Stratified => 1,
+        SamplerType::Halton => 2,
+        SamplerType::Sobol => 3
+    }
+}
+
+fn index_to_sampler(index: usize) -> SamplerType {
+    match index {
+        1 => SamplerType::Stratified,
+        2 => SamplerType::Halton,
+        3 => SamplerType::Sobol,
+        _ => SamplerType::Independent
+    }
+}
+
+fn filter_to_index(filter: &Filter) -> usize {
+    match filter {
+        Filter::Box { .. } => 0,
+        Filter::Tent { .. } => 1,
+        Filter::Gaussian { .. } => 2,
+        Filter::Mitchell { .. } => 3,
+        Filter::Lanczos { .. } => 4
+    }
+}
+
+// Every filter starts out with its usual radius
+fn index_to_filter(index: usize) -> Filter {
+    let name = match index {
+        1 => "tent",
+        2 => "gaussian",
+        3 => "mitchell",
+        4 => "lanczos",
+        _ => "box"
+    };
+
+    name.parse::<Filter>().unwrap_or_default()
+}
+
+fn integrator_to_index(integrator: &Integrator) -> usize {
+    match integrator {
+        Integrator::PathTracer => 0,
+        Integrator::Normal => 1,
+        Integrator::Depth { .. } => 2,
+        Integrator::Albedo => 3,
+        Integrator::Uv => 4,
+        Integrator::BounceCount => 5,
+        Integrator::MaterialId => 6,
+        Integrator::BvhCost { .. } => 7,
+        Integrator::AmbientOcclusion { .. } => 8
+    }
+}
+
+// Integrators with a parameter start out with their usual one
+fn index_to_integrator(index: usize) -> Integrator {
+    let name = match index {
+        1 => "normal",
+        2 => "depth",
+        3 => "albedo",
+        4 => "uv",
+        5 => "bounce_count",
+        6 => "material_id",
+        7 => "bvh_cost",
+        8 => "ambient_occlusion",
+        _ => "path_tracer"
+    };
+
+    name.parse::<Integrator>().unwrap_or_default()
+}
+
+fn tone_mapping_to_index(tone_mapping: ToneMapping) -> usize {
+    match tone_mapping {
+        ToneMapping::Clamp => 0,
+        ToneMapping::Reinhard => 1,
+        ToneMapping::ExtendedReinhard => 2,
+        ToneMapping::AcesFitted => 3,
+        ToneMapping::Agx => 4
+    }
+}
+
+fn index_to_tone_mapping(index: usize) -> ToneMapping {
+    match index {
+        1 => ToneMapping::Reinhard,
+        2 => ToneMapping::ExtendedReinhard,
+        3 => ToneMapping::AcesFitted,
+        4 => ToneMapping::Agx,
+        _ => ToneMapping::Clamp
+    }
+}
+
+fn index_to_tile_axis_order(index: usize) -> TileAxisOrder {
+    match index {
+        1 => TileAxisOrder::Reverse,
+        2 => TileAxisOrder::Random,
+        _ => TileAxisOrder::Forward
+    }
 }
\ No newline at end of file
@@ -1,8 +1,290 @@
-use std::path::Path;
-use image::{ImageResult, Rgba, RgbaImage};
-use crate::raytracing::texture::Texture;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use exr::prelude::{AnyChannel, AnyChannels, Encoding, f16, FlatSamples, Image, Layer, LayerAttributes, read_first_rgba_layer_from_file, WritableImage};
+use image::{ImageBuffer, ImageError, ImageFormat, ImageResult, Rgb, Rgba, RgbaImage};
+use image::codecs::hdr::{HdrDecoder, HdrEncoder};
+use image::error::{DecodingError, EncodingError, ImageFormatHint, ParameterError, ParameterErrorKind};
+use crate::raytracing::aov::Aov;
+use crate::raytracing::color::Color;
+use crate::raytracing::denoiser::Denoiser;
+use crate::raytracing::film::Film;
+use crate::raytracing::texture::{Texture, TextureWrapMode};
+use crate::raytracing::tone_mapping::{DisplaySettings, srgb_decode};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExrPrecision {
+	Half,
+	Float
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PngBitDepth {
+	Eight,
+	Sixteen
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ExportSettings {
+	pub exr_precision: ExrPrecision,
+	pub png_bit_depth: PngBitDepth,
+	// Writes AOVs to their own files even when the image is an OpenEXR file that could hold them as layers
+	pub separate_aov_files: bool,
+	// Denoises the beauty image before writing it, AOVs stay as rendered
+	pub denoiser: Option<Denoiser>
+}
+
+impl ExportSettings {
+	pub fn new() -> Self {
+		Self {
+			exr_precision: ExrPrecision::Half,
+			png_bit_depth: PngBitDepth::Eight,
+			separate_aov_files: false,
+			denoiser: None
+		}
+	}
+}
+
+impl Default for ExportSettings {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// OpenEXR and Radiance files store unclamped linear values, everything else is clamped to [0, 1]
+pub fn is_hdr_path<P: AsRef<Path>>(path: P) -> bool {
+	matches!(extension(path.as_ref()).as_str(), "exr" | "hdr")
+}
+
+// Linear colors for rendering, 8 and 16 bit formats are taken to be sRGB encoded
+pub fn load_texture<P: AsRef<Path>>(path: P, wrap_mode: TextureWrapMode) -> ImageResult<Texture> {
+	let path = path.as_ref();
+
+	match extension(path).as_str() {
+		"exr" => {
+			let image = read_first_rgba_layer_from_file(
+				path,
+				|resolution, _| (vec![[0.0f32; 4]; resolution.width() * resolution.height()], resolution.width()),
+				|(pixels, width), position, (r, g, b, a): (f32, f32, f32, f32)| {
+					pixels[position.y() * *width + position.x()] = [r, g, b, a];
+				}
+			).map_err(exr_read_error)?;
+
+			let size = image.layer_data.size;
+			let (pixels, _) = image.layer_data.channel_data.pixels;
+
+			texture_from_pixels(size.width() as u32, size.height() as u32, wrap_mode, pixels.into_iter())
+		}
+		"hdr" => {
+			let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+			let metadata = decoder.metadata();
+			let pixels = decoder.read_image_hdr()?;
+
+			texture_from_pixels(metadata.width, metadata.height, wrap_mode, pixels.into_iter().map(|Rgb([r, g, b])| [r, g, b, 1.0]))
+		}
+		_ => {
+			let image = image::open(path)?.to_rgba32f();
+			let (width, height) = image.dimensions();
+
+			let pixels = image.pixels().map(|Rgba([r, g, b, a])| [srgb_decode(*r), srgb_decode(*g), srgb_decode(*b), *a]);
+
+			texture_from_pixels(width, height, wrap_mode, pixels)
+		}
+	}
+}
+
+fn texture_from_pixels<I: Iterator<Item = [f32; 4]>>(width: u32, height: u32, wrap_mode: TextureWrapMode, pixels: I) -> ImageResult<Texture> {
+	let size = NonZeroU32::new(width).zip(NonZeroU32::new(height));
+
+	let (width, height) = match size {
+		None => return Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::DimensionMismatch))),
+		Some(size) => size
+	};
+
+	let mut texture = Texture::new(width, height, wrap_mode, Color::black());
+
+	for (target, pixel) in texture.get_raw_mut().chunks_exact_mut(4).zip(pixels) {
+		target.copy_from_slice(&pixel);
+	}
+
+	Ok(texture)
+}
 
 pub fn save_texture_to_path<P: AsRef<Path>>(path: P, texture: &Texture) -> ImageResult<()> {
+	save_texture_with_settings(path, texture, &ExportSettings::new())
+}
+
+// For displayed textures, the format follows the extension of `path`.
+// OpenEXR and Radiance files are refused, they would store display encoded colors as linear ones, `save_render` writes the film to them.
+pub fn save_texture_with_settings<P: AsRef<Path>>(path: P, texture: &Texture, settings: &ExportSettings) -> ImageResult<()> {
+	let path = path.as_ref();
+
+	if is_hdr_path(path) {
+		return Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(
+			format!("{} is an HDR format for linear colors, save the render's film with save_render", path.display())
+		))));
+	}
+
+	save_linear_texture(path, texture, settings)
+}
+
+// Writes the values of `texture` as they are, for linear data like the film or sample counts.
+// 8 and 16 bit formats clamp them to [0, 1] without encoding them.
+pub fn save_linear_texture<P: AsRef<Path>>(path: P, texture: &Texture, settings: &ExportSettings) -> ImageResult<()> {
+	let path = path.as_ref();
+
+	match extension(path).as_str() {
+		"exr" => save_exr(path, texture, settings.exr_precision),
+		"hdr" => save_hdr(path, texture),
+		"png" if settings.png_bit_depth == PngBitDepth::Sixteen => save_png_16(path, texture),
+		_ => save_8_bit(path, texture)
+	}
+}
+
+// Channels stored next to the RGBA ones of an OpenEXR file, named `<name>.<channel>`
+pub struct ExrLayer<'a> {
+	pub name: &'a str,
+	// Taken from the texture's red, green, blue and alpha in this order
+	pub channels: &'a [&'a str],
+	pub texture: &'a Texture,
+	// Stored as 32 bit floats no matter the export precision
+	pub full_precision: bool
+}
+
+// Saves `texture`, or the linear film for HDR formats, and the rendered AOVs in `aovs`.
+// OpenEXR files get the AOVs as layers, other formats as separate files named after the image. Returns all written paths.
+pub fn save_render<P: AsRef<Path>>(path: P, texture: &Texture, film: &Film, display: &DisplaySettings, aovs: &[Aov], settings: &ExportSettings) -> ImageResult<Vec<PathBuf>> {
+	let path = path.as_ref();
+	let denoised = settings.denoiser.map(|denoiser| denoiser.denoise(film));
+
+	let beauty = match denoised {
+		Some(denoised) if is_hdr_path(path) => denoised,
+		Some(denoised) => display_texture(&denoised, display),
+		None if is_hdr_path(path) => film.to_texture(),
+		None => texture.clone()
+	};
+
+	let aov_textures: Vec<(Aov, Texture)> = aovs.iter()
+		.filter_map(|aov| film.aov_texture(*aov).map(|texture| (*aov, texture)))
+		.collect();
+
+	if extension(path) == "exr" && !settings.separate_aov_files {
+		let layers: Vec<ExrLayer> = aov_textures.iter()
+			.map(|(aov, texture)| ExrLayer {
+				name: aov.name(),
+				channels: aov.channel_names(),
+				texture,
+				full_precision: aov.needs_full_precision()
+			})
+			.collect();
+
+		save_exr_with_layers(path, &beauty, &layers, settings.exr_precision)?;
+
+		return Ok(vec![path.to_path_buf()]);
+	}
+
+	save_linear_texture(path, &beauty, settings)?;
+
+	let mut written = vec![path.to_path_buf()];
+
+	for (aov, aov_texture) in &aov_textures {
+		let aov_path = aov_path(path, *aov);
+
+		if is_hdr_path(&aov_path) {
+			save_linear_texture(&aov_path, aov_texture, settings)?;
+		}
+		else {
+			save_linear_texture(&aov_path, &aov.preview_texture(aov_texture, display), settings)?;
+		}
+
+		written.push(aov_path);
+	}
+
+	Ok(written)
+}
+
+fn display_texture(texture: &Texture, display: &DisplaySettings) -> Texture {
+	let mut result = texture.clone();
+
+	for y in 0..texture.get_height().get() {
+		for x in 0..texture.get_width().get() {
+			let [r, g, b, _] = texture.get_pixel(x, y).unwrap().to_rgba().0;
+			result.set_pixel(x, y, display.apply(Color::create(r, g, b, 1.0)));
+		}
+	}
+
+	result
+}
+
+// "render.png" becomes "render.depth.png"
+pub fn aov_path<P: AsRef<Path>>(path: P, aov: Aov) -> PathBuf {
+	let path = path.as_ref();
+	let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+
+	let file_name = match path.extension().and_then(|extension| extension.to_str()) {
+		None => format!("{}.{}", stem, aov.name()),
+		Some(extension) => format!("{}.{}.{}", stem, aov.name(), extension)
+	};
+
+	path.with_file_name(file_name)
+}
+
+pub fn save_exr_with_layers<P: AsRef<Path>>(path: P, texture: &Texture, layers: &[ExrLayer], precision: ExrPrecision) -> ImageResult<()> {
+	let mut channels = Vec::new();
+
+	for (index, name) in ["R", "G", "B", "A"].iter().enumerate() {
+		channels.push(AnyChannel::new(*name, texture_channel(texture, index, precision)));
+	}
+
+	for layer in layers {
+		let precision = if layer.full_precision {
+			ExrPrecision::Float
+		}
+		else {
+			precision
+		};
+
+		for (index, channel) in layer.channels.iter().enumerate() {
+			let name = format!("{}.{}", layer.name, channel);
+			channels.push(AnyChannel::new(name.as_str(), texture_channel(layer.texture, index, precision)));
+		}
+	}
+
+	let size = (texture.get_width().get() as usize, texture.get_height().get() as usize);
+	let layer = Layer::new(size, LayerAttributes::default(), Encoding::FAST_LOSSLESS, AnyChannels::sort(channels.into()));
+
+	Image::from_layer(layer).write().to_file(path.as_ref()).map_err(exr_error)
+}
+
+fn texture_channel(texture: &Texture, channel: usize, precision: ExrPrecision) -> FlatSamples {
+	let width = texture.get_width().get();
+	let height = texture.get_height().get();
+
+	let values = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)))
+		.map(|(x, y)| texture.get_pixel(x, y).unwrap().to_rgba().0[channel]);
+
+	match precision {
+		ExrPrecision::Half => FlatSamples::F16(values.map(f16::from_f32).collect()),
+		ExrPrecision::Float => FlatSamples::F32(values.collect())
+	}
+}
+
+fn exr_error(error: exr::error::Error) -> ImageError {
+	match error {
+		exr::error::Error::Io(error) => ImageError::IoError(error),
+		error => ImageError::Encoding(EncodingError::new(ImageFormatHint::Exact(ImageFormat::OpenExr), error))
+	}
+}
+
+fn exr_read_error(error: exr::error::Error) -> ImageError {
+	match error {
+		exr::error::Error::Io(error) => ImageError::IoError(error),
+		error => ImageError::Decoding(DecodingError::new(ImageFormatHint::Exact(ImageFormat::OpenExr), error))
+	}
+}
+
+fn save_8_bit(path: &Path, texture: &Texture) -> ImageResult<()> {
 	let image = RgbaImage::from_fn(texture.get_width().get(), texture.get_height().get(), |x,y| {
 		let pixel = texture.get_pixel(x,y).unwrap();
 		let rgba_float = pixel.to_rgba();
@@ -19,9 +301,152 @@ pub fn save_texture_to_path<P: AsRef<Path>>(path: P, texture: &Texture) -> Image
 		let a = (a * 255.0).round() as u8;
 
 		let rgba_uint_values = [r,g,b,a];
-		let rgba_uint = Rgba::<u8>::from(rgba_uint_values);
-		rgba_uint
+		Rgba::<u8>::from(rgba_uint_values)
 	});
 
 	image.save(path)
-}
\ No newline at end of file
+}
+
+fn save_png_16(path: &Path, texture: &Texture) -> ImageResult<()> {
+	let image = ImageBuffer::<Rgba<u16>, Vec<u16>>::from_fn(texture.get_width().get(), texture.get_height().get(), |x,y| {
+		let [r, g, b, a] = texture.get_pixel(x,y).unwrap().to_rgba().0;
+
+		let to_u16 = |value: f32| (value.clamp(0.0, 1.0) * 65535.0).round() as u16;
+
+		Rgba::<u16>::from([to_u16(r), to_u16(g), to_u16(b), to_u16(a)])
+	});
+
+	image.save_with_format(path, ImageFormat::Png)
+}
+
+fn save_exr(path: &Path, texture: &Texture, precision: ExrPrecision) -> ImageResult<()> {
+	let width = texture.get_width().get() as usize;
+	let height = texture.get_height().get() as usize;
+
+	let pixel = |x: usize, y: usize| texture.get_pixel(x as u32, y as u32).unwrap().to_rgba().0;
+
+	let result = match precision {
+		ExrPrecision::Half => exr::prelude::write_rgba_file(path, width, height, |x, y| {
+			let [r, g, b, a] = pixel(x, y);
+			(f16::from_f32(r), f16::from_f32(g), f16::from_f32(b), f16::from_f32(a))
+		}),
+		ExrPrecision::Float => exr::prelude::write_rgba_file(path, width, height, |x, y| {
+			let [r, g, b, a] = pixel(x, y);
+			(r, g, b, a)
+		})
+	};
+
+	result.map_err(exr_error)
+}
+
+// Radiance RGBE has no alpha channel
+fn save_hdr(path: &Path, texture: &Texture) -> ImageResult<()> {
+	let width = texture.get_width().get();
+	let height = texture.get_height().get();
+
+	let mut pixels = Vec::with_capacity(width as usize * height as usize);
+
+	for y in 0..height {
+		for x in 0..width {
+			let [r, g, b, _] = texture.get_pixel(x, y).unwrap().to_rgba().0;
+			pixels.push(Rgb::<f32>::from([r.max(0.0), g.max(0.0), b.max(0.0)]));
+		}
+	}
+
+	let file = BufWriter::new(File::create(path)?);
+
+	HdrEncoder::new(file).encode(&pixels, width as usize, height as usize)
+}
+
+fn extension(path: &Path) -> String {
+	path.extension()
+		.and_then(|extension| extension.to_str())
+		.unwrap_or_default()
+		.to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hdr_texture() -> Texture {
+		let mut texture = Texture::new(NonZeroU32::new(5).unwrap(), NonZeroU32::new(3).unwrap(), TextureWrapMode::Clamp, Color::black());
+
+		for y in 0..3 {
+			for x in 0..5 {
+				let value = (x + 5 * y) as f32;
+				texture.set_pixel(x, y, Color::create(value * 2.5, 0.001 * (value + 1.0), 1.0 / (value + 1.0), 1.0));
+			}
+		}
+
+		texture
+	}
+
+	fn temporary_path(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("raytrayce_{}_{}", std::process::id(), name))
+	}
+
+	fn assert_round_trips(name: &str, settings: &ExportSettings, tolerance: f32) {
+		let path = temporary_path(name);
+		let texture = hdr_texture();
+
+		save_linear_texture(&path, &texture, settings).unwrap();
+		let loaded = load_texture(&path, TextureWrapMode::Clamp);
+		std::fs::remove_file(&path).unwrap();
+		let loaded = loaded.unwrap();
+
+		assert_eq!((loaded.get_width(), loaded.get_height()), (texture.get_width(), texture.get_height()));
+
+		for y in 0..3 {
+			for x in 0..5 {
+				let expected = texture.get_pixel(x, y).unwrap().to_rgba().0;
+				let actual = loaded.get_pixel(x, y).unwrap().to_rgba().0;
+
+				for channel in 0..4 {
+					let error = (actual[channel] - expected[channel]).abs();
+					assert!(error <= tolerance * expected[channel].abs(), "{}: {:?} != {:?} at {}, {}", name, actual, expected, x, y);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn exr_files_round_trip() {
+		assert_round_trips("float.exr", &ExportSettings { exr_precision: ExrPrecision::Float, ..ExportSettings::new() }, 0.0);
+		assert_round_trips("half.exr", &ExportSettings { exr_precision: ExrPrecision::Half, ..ExportSettings::new() }, 1e-3);
+	}
+
+	#[test]
+	fn hdr_files_round_trip() {
+		// RGBE shares one exponent between the channels, the error is relative to the brightest one
+		let path = temporary_path("gray.hdr");
+		let mut texture = Texture::new(NonZeroU32::new(3).unwrap(), NonZeroU32::new(1).unwrap(), TextureWrapMode::Clamp, Color::black());
+		for (x, value) in [0.01, 1.0, 250.0].into_iter().enumerate() {
+			texture.set_pixel(x as u32, 0, Color::create(value, value * 0.5, value * 0.25, 1.0));
+		}
+
+		save_linear_texture(&path, &texture, &ExportSettings::new()).unwrap();
+		let loaded = load_texture(&path, TextureWrapMode::Clamp);
+		std::fs::remove_file(&path).unwrap();
+		let loaded = loaded.unwrap();
+
+		for x in 0..3 {
+			let expected = texture.get_pixel(x, 0).unwrap().to_rgba().0;
+			let actual = loaded.get_pixel(x, 0).unwrap().to_rgba().0;
+
+			for channel in 0..4 {
+				assert!((actual[channel] - expected[channel]).abs() <= 0.01 * expected[0], "{:?} != {:?}", actual, expected);
+			}
+		}
+	}
+
+	#[test]
+	fn display_textures_are_not_written_to_hdr_files() {
+		for name in ["refused.exr", "refused.HDR"] {
+			let path = temporary_path(name);
+
+			assert!(save_texture_to_path(&path, &hdr_texture()).is_err());
+			assert!(!path.exists());
+		}
+	}
+}
@@ -0,0 +1,48 @@
+//! Raytracing library behind the `raytrayce_r` viewer and the `raytrayce_cli` renderer.
+//!
+//! Build a [`RaytracingScene`], describe the render with [`RaytracerSettings`] and run it on a
+//! [`CPURaytracer`], either in one go with [`Raytracer::render_to_completion`] or by calling
+//! [`Raytracer::step`] until it reports [`RaytracerState::Finished`].
+//! The result is a [`Texture`] that can be written to disk with [`save_texture_to_path`], the linear [`Film`] behind it
+//! can be exported to OpenEXR or Radiance HDR files with [`save_render`].
+
+pub mod raytracing;
+pub mod image;
+pub mod presenter;
+
+pub use crate::image::{aov_path, ExportSettings, ExrLayer, ExrPrecision, is_hdr_path, load_texture, PngBitDepth, save_exr_with_layers, save_linear_texture, save_render, save_texture_to_path, save_texture_with_settings};
+pub use crate::presenter::{ConsolePresenter, Presenter};
+pub use crate::raytracing::aov::Aov;
+pub use crate::raytracing::background::Background;
+pub use crate::raytracing::bokeh::{ApertureImage, Bokeh};
+pub use crate::raytracing::camera::{Camera, Lens};
+pub use crate::raytracing::color::Color;
+pub use crate::raytracing::denoiser::Denoiser;
+pub use crate::raytracing::environment::EnvironmentMap;
+pub use crate::raytracing::film::Film;
+pub use crate::raytracing::filter::Filter;
+pub use crate::raytracing::hittable::Hittable;
+pub use crate::raytracing::hittable::sphere::Sphere;
+pub use crate::raytracing::hittable::triangle::Triangle;
+pub use crate::raytracing::hittable::triangle_mesh::{MeshTriangle, TriangleMesh};
+pub use crate::raytracing::integrator::Integrator;
+pub use crate::raytracing::materials::Material;
+pub use crate::raytracing::materials::albedo::Albedo;
+pub use crate::raytracing::materials::dielectric::Dielectric;
+pub use crate::raytracing::materials::diffuse_light::DiffuseLight;
+pub use crate::raytracing::materials::lambertian::Lambertian;
+pub use crate::raytracing::materials::metal::Metal;
+pub use crate::raytracing::raytracer::{AdaptiveSettings, ProgressiveSettings, Raytracer, RaytracerSettings, RaytracerState};
+pub use crate::raytracing::raytracer::cpu_raytracer::CPURaytracer;
+pub use crate::raytracing::sampler::{Sampler, SamplerType};
+pub use crate::raytracing::scene::RaytracingScene;
+pub use crate::raytracing::scene_description::{SceneDescription, SceneError};
+pub use crate::raytracing::sky::PhysicalSky;
+pub use crate::raytracing::texture::{Texture, TextureWrapMode};
+pub use crate::raytracing::texture_sampling::{MipMap, TextureFilter, TextureSampling};
+pub use crate::raytracing::tone_mapping::{DisplaySettings, ToneMapping};
+pub use crate::raytracing::vector_2d::Vec2;
+pub use crate::raytracing::vector_3d::Vec3;
+pub use crate::raytracing::wavefront::WavefrontError;
+pub use crate::raytracing::wavefront::obj::load_obj;
+pub use crate::raytracing::work::generator::GenerationMode;
\ No newline at end of file
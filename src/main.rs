@@ -3,8 +3,6 @@
 use crate::application::{RaytracingApplication, RaytracingApplicationSettings};
 
 mod rendering;
-mod image;
-mod raytracing;
 mod gui;
 mod application;
 mod window;
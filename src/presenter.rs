@@ -0,0 +1,75 @@
+use std::io::{stderr, Write};
+use std::time::{Duration, Instant};
+use crate::raytracing::raytracer::RaytracerState;
+use crate::raytracing::texture::Texture;
+
+// Shows the raytracer output to the user.
+// Anything drawn between start_frame and end_frame, like the GUI, ends up on top of the texture.
+pub trait Presenter {
+	fn start_frame(&mut self, texture: &Texture, state: RaytracerState);
+	fn end_frame(&mut self);
+}
+
+pub struct ConsolePresenter {
+	started: Instant,
+	last_report: Option<Instant>,
+	report_interval: Duration,
+	finished: bool
+}
+
+impl ConsolePresenter {
+	pub fn new() -> Self {
+		Self::create(Duration::from_millis(250))
+	}
+
+	pub fn create(report_interval: Duration) -> Self {
+		Self {
+			started: Instant::now(),
+			last_report: None,
+			report_interval,
+			finished: false
+		}
+	}
+
+	fn report(&self, commissioned: usize, completed: usize) {
+		let percentage = (completed as f32 / commissioned as f32) * 100.0;
+
+		eprint!("\rRendering: {}/{} : {:.1}% ({:.1}s)", completed, commissioned, percentage, self.started.elapsed().as_secs_f32());
+	}
+}
+
+impl Default for ConsolePresenter {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Presenter for ConsolePresenter {
+	fn start_frame(&mut self, _texture: &Texture, state: RaytracerState) {
+		match state {
+			RaytracerState::Running { commissioned, completed } => {
+				self.finished = false;
+
+				let report_due = match self.last_report {
+					None => true,
+					Some(last_report) => last_report.elapsed() >= self.report_interval
+				};
+
+				if report_due {
+					self.report(commissioned, completed);
+					self.last_report = Some(Instant::now());
+				}
+			}
+			RaytracerState::Finished { commissioned, completed } if !self.finished => {
+				self.report(commissioned, completed);
+				eprintln!();
+				self.finished = true;
+			}
+			_ => {}
+		}
+	}
+
+	fn end_frame(&mut self) {
+		let _ = stderr().flush();
+	}
+}
\ No newline at end of file
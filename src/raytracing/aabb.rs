@@ -0,0 +1,116 @@
+use crate::raytracing::vector_3d::Vec3;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Aabb {
+	pub minimum: Vec3,
+	pub maximum: Vec3
+}
+
+impl Aabb {
+	pub fn new() -> Self {
+		Self {
+			minimum: Vec3::create(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+			maximum: Vec3::create(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY)
+		}
+	}
+
+	pub fn create(minimum: Vec3, maximum: Vec3) -> Self {
+		Self {
+			minimum,
+			maximum
+		}
+	}
+
+	pub fn from_points(points: &[Vec3]) -> Self {
+		let mut aabb = Self::new();
+
+		for point in points {
+			aabb = aabb.extend(*point);
+		}
+
+		aabb
+	}
+
+	pub fn surrounding(lhs: Aabb, rhs: Aabb) -> Aabb {
+		Aabb {
+			minimum: Vec3::component_min(lhs.minimum, rhs.minimum),
+			maximum: Vec3::component_max(lhs.maximum, rhs.maximum)
+		}
+	}
+
+	pub fn extend(self, point: Vec3) -> Aabb {
+		Aabb {
+			minimum: Vec3::component_min(self.minimum, point),
+			maximum: Vec3::component_max(self.maximum, point)
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.minimum.x > self.maximum.x || self.minimum.y > self.maximum.y || self.minimum.z > self.maximum.z
+	}
+
+	pub fn extent(&self) -> Vec3 {
+		self.maximum - self.minimum
+	}
+
+	pub fn centroid(&self) -> Vec3 {
+		(self.minimum + self.maximum) * 0.5
+	}
+
+	pub fn surface_area(&self) -> f64 {
+		if self.is_empty() {
+			return 0.0;
+		}
+
+		let extent = self.extent();
+
+		2.0 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+	}
+
+	pub fn padded(self, minimum_extent: f64) -> Aabb {
+		let extent = self.extent();
+
+		let padding = Vec3::create(
+			if extent.x < minimum_extent { minimum_extent / 2.0 } else { 0.0 },
+			if extent.y < minimum_extent { minimum_extent / 2.0 } else { 0.0 },
+			if extent.z < minimum_extent { minimum_extent / 2.0 } else { 0.0 }
+		);
+
+		Aabb {
+			minimum: self.minimum - padding,
+			maximum: self.maximum + padding
+		}
+	}
+
+	pub fn hit(&self, origin: Vec3, inverse_direction: Vec3, t_min: f64, t_max: f64) -> Option<f64> {
+		let mut t_min = t_min;
+		let mut t_max = t_max;
+
+		for axis in 0..3 {
+			let t0 = (self.minimum[axis] - origin[axis]) * inverse_direction[axis];
+			let t1 = (self.maximum[axis] - origin[axis]) * inverse_direction[axis];
+
+			let (t_near, t_far) = if inverse_direction[axis] < 0.0 {
+				(t1, t0)
+			}
+			else {
+				(t0, t1)
+			};
+
+			t_min = t_near.max(t_min);
+			t_max = t_far.min(t_max);
+
+			if t_max < t_min {
+				return None;
+			}
+		}
+
+		Some(t_min)
+	}
+}
+
+impl Default for Aabb {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -0,0 +1,297 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::raytracing::color::Color;
+use crate::raytracing::sampler::hash_seed;
+use crate::raytracing::texture::{Texture, TextureWrapMode};
+use crate::raytracing::tone_mapping::{DisplaySettings, srgb_encode};
+use crate::raytracing::vector_3d::Vec3;
+
+// Render passes written next to the beauty image
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Aov {
+	// Distance from the camera to the first hit
+	Depth,
+	// World space shading normal at the first hit
+	Normal,
+	Albedo,
+	// One based index of the hit object in the scene, 0 where the camera sees the background
+	ObjectId,
+	// One based index of the hit material in the order the scene's objects use them, 0 for the background
+	MaterialId,
+	// World space position of the first hit
+	Position,
+	// Emission seen by the camera plus light that reached the first hit straight from an emitter
+	Direct,
+	// Light that reached the first hit after bouncing at least once more
+	Indirect
+}
+
+impl Aov {
+	pub const ALL: [Aov; 8] = [Aov::Depth, Aov::Normal, Aov::Albedo, Aov::ObjectId, Aov::MaterialId, Aov::Position, Aov::Direct, Aov::Indirect];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Aov::Depth => "depth",
+			Aov::Normal => "normal",
+			Aov::Albedo => "albedo",
+			Aov::ObjectId => "object_id",
+			Aov::MaterialId => "material_id",
+			Aov::Position => "position",
+			Aov::Direct => "direct",
+			Aov::Indirect => "indirect"
+		}
+	}
+
+	// Channel names in file formats that support them, stored in the texture's red, green and blue in this order
+	pub fn channel_names(&self) -> &'static [&'static str] {
+		match self {
+			Aov::Depth => &["Z"],
+			Aov::Normal => &["X", "Y", "Z"],
+			Aov::Albedo => &["R", "G", "B"],
+			Aov::ObjectId => &["id"],
+			Aov::MaterialId => &["id"],
+			Aov::Position => &["X", "Y", "Z"],
+			Aov::Direct => &["R", "G", "B"],
+			Aov::Indirect => &["R", "G", "B"]
+		}
+	}
+
+	// Half floats can't hold large IDs, distances or positions exactly
+	pub fn needs_full_precision(&self) -> bool {
+		matches!(self, Aov::Depth | Aov::ObjectId | Aov::MaterialId | Aov::Position)
+	}
+
+	// Parses a comma separated list of AOV names, or "all"
+	pub fn parse_list(value: &str) -> Result<Vec<Aov>, String> {
+		if value.trim() == "all" {
+			return Ok(Aov::ALL.to_vec());
+		}
+
+		let mut aovs = Vec::new();
+
+		for name in value.split(',') {
+			let aov = Aov::from_str(name)?;
+
+			if !aovs.contains(&aov) {
+				aovs.push(aov);
+			}
+		}
+
+		Ok(aovs)
+	}
+
+	// Makes the raw values viewable in 8 bit formats
+	pub fn preview_texture(&self, texture: &Texture, display: &DisplaySettings) -> Texture {
+		let width = texture.get_width().get();
+		let height = texture.get_height().get();
+
+		let max_depth = match self {
+			Aov::Depth => {
+				let mut max_depth: f32 = 0.0;
+
+				for y in 0..height {
+					for x in 0..width {
+						max_depth = max_depth.max(texture.get_pixel(x, y).unwrap().to_rgba().0[0]);
+					}
+				}
+
+				max_depth
+			}
+			_ => 0.0
+		};
+
+		let mut preview = Texture::new(texture.get_width(), texture.get_height(), TextureWrapMode::Clamp, Color::black());
+
+		for y in 0..height {
+			for x in 0..width {
+				let [r, g, b, _] = texture.get_pixel(x, y).unwrap().to_rgba().0;
+
+				let color = match self {
+					Aov::Depth if max_depth > 0.0 => Color::create(r / max_depth, r / max_depth, r / max_depth, 1.0),
+					Aov::Depth => Color::black(),
+					Aov::Normal if r == 0.0 && g == 0.0 && b == 0.0 => Color::black(),
+					Aov::Normal => Color::create(r * 0.5 + 0.5, g * 0.5 + 0.5, b * 0.5 + 0.5, 1.0),
+					Aov::Albedo => Color::create(srgb_encode(r), srgb_encode(g), srgb_encode(b), 1.0),
+					Aov::ObjectId | Aov::MaterialId => id_color(r as u32),
+					Aov::Position => Color::create(r, g, b, 1.0),
+					Aov::Direct | Aov::Indirect => display.apply(Color::create(r, g, b, 1.0))
+				};
+
+				preview.set_pixel(x, y, color);
+			}
+		}
+
+		preview
+	}
+}
+
+impl FromStr for Aov {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let value = value.trim();
+
+		Aov::ALL.iter()
+			.find(|aov| aov.name() == value)
+			.copied()
+			.ok_or_else(|| format!("unknown AOV '{}'", value))
+	}
+}
+
+impl Display for Aov {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+// What the camera ray of one sample hit
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SurfaceAovs {
+	pub depth: f64,
+	pub normal: Vec3,
+	pub position: Vec3,
+	pub albedo: Color,
+	pub object_id: u32,
+	pub material_id: u32
+}
+
+// Running sums of the AOVs of one pixel's samples
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AovSums {
+	pub sample_count: usize,
+	// Depth, normal and position are averaged over the samples that hit something
+	pub hit_count: usize,
+	pub depth: f64,
+	pub normal: [f64; 3],
+	pub position: [f64; 3],
+	pub albedo: [f64; 3],
+	pub direct: [f64; 3],
+	pub indirect: [f64; 3],
+	// IDs don't average, they come from the pixel's first sample that hit something
+	pub object_id: u32,
+	pub material_id: u32
+}
+
+impl AovSums {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn add_sample(&mut self, surface: Option<SurfaceAovs>, direct: Color, indirect: Color) {
+		if let Some(surface) = surface {
+			if self.hit_count == 0 {
+				self.object_id = surface.object_id;
+				self.material_id = surface.material_id;
+			}
+
+			self.hit_count += 1;
+			self.depth += surface.depth;
+			add_vec3(&mut self.normal, surface.normal);
+			add_vec3(&mut self.position, surface.position);
+			add_color(&mut self.albedo, surface.albedo);
+		}
+
+		add_color(&mut self.direct, direct);
+		add_color(&mut self.indirect, indirect);
+		self.sample_count += 1;
+	}
+
+	pub fn add(&mut self, other: &AovSums) {
+		if self.hit_count == 0 {
+			self.object_id = other.object_id;
+			self.material_id = other.material_id;
+		}
+
+		self.sample_count += other.sample_count;
+		self.hit_count += other.hit_count;
+		self.depth += other.depth;
+
+		for channel in 0..3 {
+			self.normal[channel] += other.normal[channel];
+			self.position[channel] += other.position[channel];
+			self.albedo[channel] += other.albedo[channel];
+			self.direct[channel] += other.direct[channel];
+			self.indirect[channel] += other.indirect[channel];
+		}
+	}
+
+	pub fn value(&self, aov: Aov) -> Color {
+		let per_hit = 1.0 / self.hit_count.max(1) as f64;
+		let per_sample = 1.0 / self.sample_count.max(1) as f64;
+
+		match aov {
+			Aov::Depth => {
+				let depth = (self.depth * per_hit) as f32;
+				Color::create(depth, depth, depth, 1.0)
+			}
+			Aov::Normal => to_color(self.normal, per_hit),
+			Aov::Albedo => to_color(self.albedo, per_sample),
+			Aov::ObjectId => Color::create(self.object_id as f32, self.object_id as f32, self.object_id as f32, 1.0),
+			Aov::MaterialId => Color::create(self.material_id as f32, self.material_id as f32, self.material_id as f32, 1.0),
+			Aov::Position => to_color(self.position, per_hit),
+			Aov::Direct => to_color(self.direct, per_sample),
+			Aov::Indirect => to_color(self.indirect, per_sample)
+		}
+	}
+}
+
+// Every ID gets a random but fixed color, the background stays black
+pub(crate) fn id_color(id: u32) -> Color {
+	if id == 0 {
+		return Color::black();
+	}
+
+	let hash = hash_seed(id as u64, &[]);
+	let channel = |shift: u32| 0.2 + 0.8 * ((hash >> shift) & 0xff) as f32 / 255.0;
+
+	Color::create(channel(0), channel(8), channel(16), 1.0)
+}
+
+fn add_vec3(sum: &mut [f64; 3], value: Vec3) {
+	sum[0] += value.x;
+	sum[1] += value.y;
+	sum[2] += value.z;
+}
+
+fn add_color(sum: &mut [f64; 3], value: Color) {
+	sum[0] += value.r as f64;
+	sum[1] += value.g as f64;
+	sum[2] += value.b as f64;
+}
+
+fn to_color(sum: [f64; 3], scale: f64) -> Color {
+	Color::create((sum[0] * scale) as f32, (sum[1] * scale) as f32, (sum[2] * scale) as f32, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn surface(object_id: u32) -> SurfaceAovs {
+		SurfaceAovs {
+			depth: 1.0,
+			normal: Vec3::create(0.0, 1.0, 0.0),
+			position: Vec3::new(),
+			albedo: Color::white(),
+			object_id,
+			material_id: object_id + 10
+		}
+	}
+
+	#[test]
+	fn ids_come_from_the_first_hit() {
+		let mut sums = AovSums::new();
+		sums.add_sample(None, Color::white(), Color::black());
+		sums.add_sample(Some(surface(3)), Color::white(), Color::black());
+		sums.add_sample(Some(surface(4)), Color::white(), Color::black());
+
+		assert_eq!((sums.object_id, sums.material_id), (3, 13));
+
+		let mut merged = AovSums::new();
+		merged.add_sample(None, Color::white(), Color::black());
+		merged.add(&sums);
+
+		assert_eq!((merged.object_id, merged.material_id), (3, 13));
+		assert_eq!((merged.sample_count, merged.hit_count), (4, 2));
+	}
+}
\ No newline at end of file
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+use crate::raytracing::color::Color;
+use crate::raytracing::environment::EnvironmentMap;
+use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::sky::PhysicalSky;
+use crate::raytracing::vector_3d::Vec3;
+
+#[derive(Clone, Debug)]
+pub enum Background {
+	Gradient {
+		bottom: Color,
+		top: Color
+	},
+	Constant(Color),
+	// The ones below are also sampled as lights
+	Environment(Arc<EnvironmentMap>),
+	Sky(Arc<PhysicalSky>)
+}
+
+impl Background {
+	pub fn sky() -> Self {
+		Self::Gradient {
+			bottom: Color::create(1.0, 1.0, 1.0, 1.0),
+			top: Color::create(0.5, 0.7, 1.0, 1.0)
+		}
+	}
+
+	pub fn black() -> Self {
+		Self::Constant(Color::black())
+	}
+
+	pub fn color(&self, ray: &Ray) -> Color {
+		match self {
+			Background::Gradient { bottom, top } => {
+				let unit_direction = ray.direction.normalized();
+
+				let t = (0.5 * (unit_direction.y + 1.0)) as f32;
+
+				*bottom * (1.0 - t) + *top * t
+			}
+			Background::Constant(color) => {
+				*color
+			}
+			Background::Environment(environment) => {
+				environment.color(ray.direction)
+			}
+			Background::Sky(sky) => {
+				sky.color(ray.direction)
+			}
+		}
+	}
+
+	// Whether `sample` can find directions that give off light
+	pub fn can_be_sampled(&self) -> bool {
+		match self {
+			Background::Gradient { .. } | Background::Constant(_) => false,
+			Background::Environment(environment) => environment.can_be_sampled(),
+			Background::Sky(sky) => sky.can_be_sampled()
+		}
+	}
+
+	// Normalized direction and its solid angle pdf
+	pub fn sample(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+		match self {
+			Background::Gradient { .. } | Background::Constant(_) => None,
+			Background::Environment(environment) => environment.sample(sampler),
+			Background::Sky(sky) => sky.sample(sampler)
+		}
+	}
+
+	pub fn pdf(&self, direction: Vec3) -> f64 {
+		match self {
+			Background::Gradient { .. } | Background::Constant(_) => 0.0,
+			Background::Environment(environment) => environment.pdf(direction),
+			Background::Sky(sky) => sky.pdf(direction)
+		}
+	}
+}
+
+impl Default for Background {
+	fn default() -> Self {
+		Self::sky()
+	}
+}
\ No newline at end of file
@@ -0,0 +1,120 @@
+use std::f64::consts::{PI, TAU};
+use std::sync::Arc;
+use crate::raytracing::color::Color;
+use crate::raytracing::distribution::Distribution2D;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::texture::Texture;
+
+// Shape of the lens opening, which is the shape out of focus highlights take on
+#[derive(Clone, Debug, PartialEq)]
+pub enum Bokeh {
+	Circle,
+	// Regular polygon of aperture blades, rotated by `rotation` degrees
+	Polygon {
+		blades: u32,
+		rotation: f64
+	},
+	Image(Arc<ApertureImage>)
+}
+
+impl Bokeh {
+	pub fn new() -> Self {
+		Bokeh::Circle
+	}
+
+	// Point on the aperture, within [-1, 1] on both axes, with +y up
+	pub fn sample(&self, sampler: &mut dyn Sampler) -> (f64, f64) {
+		let (u, v) = sampler.next_2d();
+
+		match self {
+			Bokeh::Circle => sample_disk(u, v),
+			Bokeh::Polygon { blades, rotation } if *blades >= 3 => sample_polygon(u, v, *blades, rotation.to_radians()),
+			Bokeh::Polygon { .. } => sample_disk(u, v),
+			Bokeh::Image(image) => image.sample(u, v)
+		}
+	}
+}
+
+impl Default for Bokeh {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// Aperture mask from an image, brighter texels let more light through.
+// The image is stretched over the square the circular aperture fits in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApertureImage {
+	distribution: Distribution2D
+}
+
+impl ApertureImage {
+	// None if the image is black everywhere and wouldn't let any light through
+	pub fn create(texture: &Texture) -> Option<Self> {
+		let width = texture.get_width().get() as usize;
+		let height = texture.get_height().get() as usize;
+
+		let luminance: Vec<f64> = (0..width * height)
+			.map(|index| Color::from(&texture.get_pixel((index % width) as u32, (index / width) as u32).unwrap()).luminance().max(0.0) as f64)
+			.collect();
+
+		let distribution = Distribution2D::create(&luminance, width, height);
+
+		if distribution.integral() > 0.0 {
+			Some(Self {
+				distribution
+			})
+		}
+		else {
+			None
+		}
+	}
+
+	fn sample(&self, u: f64, v: f64) -> (f64, f64) {
+		let (x, y, _) = self.distribution.sample(u, v);
+
+		// Rows go down in the image
+		(x * 2.0 - 1.0, 1.0 - y * 2.0)
+	}
+}
+
+// Uniform over the unit disk, Shirley and Chiu's concentric mapping keeps neighbouring samples close
+fn sample_disk(u: f64, v: f64) -> (f64, f64) {
+	let x = u * 2.0 - 1.0;
+	let y = v * 2.0 - 1.0;
+
+	if x == 0.0 && y == 0.0 {
+		return (0.0, 0.0);
+	}
+
+	let (radius, angle) = if x.abs() > y.abs() {
+		(x, PI / 4.0 * (y / x))
+	}
+	else {
+		(y, PI / 2.0 - PI / 4.0 * (x / y))
+	};
+
+	(radius * angle.cos(), radius * angle.sin())
+}
+
+// Uniform over a regular polygon with its corners on the unit circle, made of one triangle per blade
+fn sample_polygon(u: f64, v: f64, blades: u32, rotation: f64) -> (f64, f64) {
+	let position = u * blades as f64;
+	let blade = (position.floor() as u32).min(blades - 1);
+	let u = position - blade as f64;
+
+	let corner = |index: u32| {
+		let angle = rotation + PI / 2.0 + TAU * index as f64 / blades as f64;
+		(angle.cos(), angle.sin())
+	};
+
+	let (x1, y1) = corner(blade);
+	let (x2, y2) = corner(blade + 1);
+
+	// Uniform point in the triangle between the center and both corners
+	let root = u.sqrt();
+	let b1 = root * (1.0 - v);
+	let b2 = root * v;
+
+	(x1 * b1 + x2 * b2, y1 * b1 + y2 * b2)
+}
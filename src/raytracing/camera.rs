@@ -1,13 +1,94 @@
+use crate::raytracing::bokeh::Bokeh;
 use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
 use crate::raytracing::vector_3d::Vec3;
 
-#[derive(Copy, Clone, Default, PartialEq)]
+// Lengths are in millimeters like on a real camera, scene units count as meters for the size of the lens opening
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lens {
+	pub focal_length: f64,
+	// The image is fit inside the sensor, a wider image uses its full width and a taller one its full height
+	pub sensor_width: f64,
+	pub sensor_height: f64,
+	// Focal length divided by the diameter of the opening, 0 for a pinhole that keeps everything in focus
+	pub f_stop: f64,
+	// In scene units
+	pub focus_distance: f64,
+	pub bokeh: Bokeh
+}
+
+impl Lens {
+	// 50mm lens on a full frame sensor
+	pub fn new() -> Self {
+		Self {
+			focal_length: 50.0,
+			sensor_width: 36.0,
+			sensor_height: 24.0,
+			f_stop: 0.0,
+			focus_distance: 1.0,
+			bokeh: Bokeh::Circle
+		}
+	}
+
+	pub fn vertical_field_of_view(&self, aspect_ratio: f64) -> f64 {
+		2.0 * (self.image_height(aspect_ratio) / (2.0 * self.focal_length)).atan().to_degrees()
+	}
+
+	// The focal length that gives this sensor a vertical field of view of `vertical_field_of_view` degrees
+	pub fn focal_length_for(&self, vertical_field_of_view: f64, aspect_ratio: f64) -> f64 {
+		self.image_height(aspect_ratio) / (2.0 * (vertical_field_of_view.to_radians() / 2.0).tan())
+	}
+
+	// The f-stop that opens the lens to `aperture` scene units
+	pub fn f_stop_for(&self, aperture: f64) -> f64 {
+		if aperture > 0.0 {
+			self.focal_length / aperture / 1000.0
+		}
+		else {
+			0.0
+		}
+	}
+
+	fn image_height(&self, aspect_ratio: f64) -> f64 {
+		if aspect_ratio > self.sensor_width / self.sensor_height {
+			self.sensor_width / aspect_ratio
+		}
+		else {
+			self.sensor_height
+		}
+	}
+
+	// In scene units
+	pub fn aperture(&self) -> f64 {
+		if self.f_stop > 0.0 {
+			self.focal_length / self.f_stop / 1000.0
+		}
+		else {
+			0.0
+		}
+	}
+}
+
+impl Default for Lens {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Camera {
 	position: Vec3,
+	// Right and up in the image, the camera looks along -w
+	u: Vec3,
+	v: Vec3,
+	// The image on the plane in focus
 	lower_left_corner: Vec3,
 	horizontal: Vec3,
 	vertical: Vec3,
+	lens: Lens,
 	lens_radius: f64,
+	// At a distance of 1
+	viewport_height: f64
 }
 
 impl Camera {
@@ -24,8 +105,20 @@ impl Camera {
 		)
 	}
 
+	// `aperture` is the diameter of the lens opening in scene units, the camera gets the full frame lens that has it
 	pub fn create(position: Vec3, look_at_point: Vec3, up_direction: Vec3, vertical_field_of_view: f64, aspect_ratio: f64, focus_distance: f64, aperture: f64) -> Self {
-		let theta = vertical_field_of_view.to_radians();
+		let mut lens = Lens {
+			focus_distance,
+			..Lens::new()
+		};
+		lens.focal_length = lens.focal_length_for(vertical_field_of_view, aspect_ratio);
+		lens.f_stop = lens.f_stop_for(aperture);
+
+		Self::create_with_lens(position, look_at_point, up_direction, &lens, aspect_ratio)
+	}
+
+	pub fn create_with_lens(position: Vec3, look_at_point: Vec3, up_direction: Vec3, lens: &Lens, aspect_ratio: f64) -> Self {
+		let theta = lens.vertical_field_of_view(aspect_ratio).to_radians();
 		let height = (theta / 2.0).tan();
 
 		let viewport_height = 2.0 * height;
@@ -35,32 +128,93 @@ impl Camera {
 		let u = Vec3::cross(up_direction, w).normalized();
 		let v = Vec3::cross(w,u);
 
+		let focus_distance = lens.focus_distance;
+
 		let origin = position;
 		let horizontal = u * viewport_width * focus_distance;
 		let vertical = -v * viewport_height * focus_distance;
 		let lower_left_corner = origin - horizontal / 2.0 - vertical / 2.0 - w * focus_distance;
 
-		let lens_radius = aperture / 2.0;
-		
+		let lens_radius = lens.aperture() / 2.0;
+
 		Self {
 			position,
+			u,
+			v,
 			lower_left_corner,
 			horizontal,
 			vertical,
-			lens_radius
+			lens: lens.clone(),
+			lens_radius,
+			viewport_height
+		}
+	}
+
+	pub fn with_bokeh(self, bokeh: Bokeh) -> Self {
+		Self {
+			lens: Lens {
+				bokeh,
+				..self.lens
+			},
+			..self
 		}
 	}
 
-	pub fn cast_ray(&self, u: f64, v: f64) -> Ray {
-		let rd = Vec3::random_in_unit_disk() * self.lens_radius;
+	pub fn position(&self) -> Vec3 {
+		self.position
+	}
+
+	// The middle of the image on the plane in focus
+	pub fn look_at(&self) -> Vec3 {
+		self.lower_left_corner + self.horizontal / 2.0 + self.vertical / 2.0
+	}
+
+	pub fn up(&self) -> Vec3 {
+		self.v
+	}
+
+	pub fn lens(&self) -> &Lens {
+		&self.lens
+	}
+
+	// In degrees
+	pub fn vertical_field_of_view(&self) -> f64 {
+		2.0 * (self.viewport_height / 2.0).atan().to_degrees()
+	}
+
+	pub fn aspect_ratio(&self) -> f64 {
+		self.horizontal.length() / self.vertical.length()
+	}
+
+	pub fn focus_distance(&self) -> f64 {
+		self.lens.focus_distance
+	}
+
+	pub fn aperture(&self) -> f64 {
+		2.0 * self.lens_radius
+	}
+
+	pub fn bokeh(&self) -> &Bokeh {
+		&self.lens.bokeh
+	}
 
-		let offset = u * rd.x + v * rd.y;
+	// Angle between the rays through neighbouring pixels
+	pub fn pixel_spread_angle(&self, image_height: u32) -> f64 {
+		(self.viewport_height / image_height.max(1) as f64).atan()
+	}
 
-		let direction = self.lower_left_corner + self.horizontal * u  + self.vertical * v - self.position;
+	pub fn cast_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Ray {
+		// Drawn for pinholes too, so opening the lens doesn't shift the random numbers of everything after it
+		let (lens_x, lens_y) = self.lens.bokeh.sample(sampler);
+
+		let offset = self.u * (lens_x * self.lens_radius) + self.v * (lens_y * self.lens_radius);
+
+		let focus_point = self.lower_left_corner + self.horizontal * u + self.vertical * v;
+		let origin = self.position + offset;
 
 		Ray {
-			origin: self.position + offset,
-			direction
+			origin,
+			direction: focus_point - origin
 		}
 	}
-}
\ No newline at end of file
+}
@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Mul};
 use image::Rgba;
 use crate::raytracing::pixel::{MutPixel, Pixel};
 
@@ -28,6 +29,72 @@ impl Color {
 			a
 		}
 	}
+
+	pub fn black() -> Self {
+		Self::create(0.0, 0.0, 0.0, 1.0)
+	}
+
+	pub fn white() -> Self {
+		Self::create(1.0, 1.0, 1.0, 1.0)
+	}
+
+	pub fn is_black(&self) -> bool {
+		self.r <= 0.0 && self.g <= 0.0 && self.b <= 0.0
+	}
+
+	// Rec. 709 weights, only meaningful for linear colors
+	pub fn luminance(&self) -> f32 {
+		0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+	}
+}
+
+// Arithmetic only touches the color channels, the alpha of the left hand side is kept
+
+impl Add<Color> for Color {
+	type Output = Color;
+
+	fn add(self, rhs: Color) -> Self::Output {
+		Color {
+			r: self.r + rhs.r,
+			g: self.g + rhs.g,
+			b: self.b + rhs.b,
+			a: self.a
+		}
+	}
+}
+
+impl AddAssign<Color> for Color {
+	fn add_assign(&mut self, rhs: Color) {
+		self.r += rhs.r;
+		self.g += rhs.g;
+		self.b += rhs.b;
+	}
+}
+
+impl Mul<Color> for Color {
+	type Output = Color;
+
+	fn mul(self, rhs: Color) -> Self::Output {
+		Color {
+			r: self.r * rhs.r,
+			g: self.g * rhs.g,
+			b: self.b * rhs.b,
+			a: self.a
+		}
+	}
+}
+
+impl Mul<f32> for Color {
+	type Output = Color;
+
+	fn mul(self, rhs: f32) -> Self::Output {
+		Color {
+			r: self.r * rhs,
+			g: self.g * rhs,
+			b: self.b * rhs,
+			a: self.a
+		}
+	}
 }
 
 impl Default for Color {
@@ -84,9 +151,9 @@ impl<'a> From<&MutPixel<'a>> for Color {
 	}
 }
 
-impl Into<Rgba<f32>> for Color {
-	fn into(self) -> Rgba<f32> {
-		let values = [self.r, self.g, self.b, self.a];
+impl From<Color> for Rgba<f32> {
+	fn from(value: Color) -> Self {
+		let values = [value.r, value.g, value.b, value.a];
 
 		Rgba::from(values)
 	}
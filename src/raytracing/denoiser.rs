@@ -0,0 +1,314 @@
+use std::num::NonZeroU32;
+use rayon::prelude::*;
+use crate::raytracing::aov::Aov;
+use crate::raytracing::color::Color;
+use crate::raytracing::film::Film;
+use crate::raytracing::texture::{Texture, TextureWrapMode};
+
+// B3 spline, spread further apart every iteration
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+// Albedo channels below this aren't divided out, there is no lighting left to recover from them
+const MIN_ALBEDO: f32 = 1e-3;
+
+// Edge avoiding à-trous wavelet filter (Dammertz et al. 2010) over the film's colors.
+// Albedo, normal and depth AOVs keep it from blurring across edges, without them only the colors are compared.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Denoiser {
+	// Every iteration doubles the kernel's reach, 5 iterations cover 62 pixels
+	pub iterations: usize,
+	// Lower values keep more detail, and more noise
+	pub color_sigma: f64,
+	pub normal_sigma: f64,
+	pub albedo_sigma: f64,
+	// Relative to the depth of the filtered pixel, per pixel of distance
+	pub depth_sigma: f64
+}
+
+// Per pixel inputs that stay the same over all iterations
+#[derive(Copy, Clone, Debug, Default)]
+struct Guide {
+	albedo: [f32; 3],
+	normal: [f32; 3],
+	depth: f32
+}
+
+impl Denoiser {
+	pub fn new() -> Self {
+		Self::create(5, 0.5)
+	}
+
+	pub fn create(iterations: usize, color_sigma: f64) -> Self {
+		Self {
+			iterations,
+			color_sigma,
+			normal_sigma: 0.3,
+			albedo_sigma: 0.1,
+			depth_sigma: 0.02
+		}
+	}
+
+	// Denoised linear colors, same size as the film
+	pub fn denoise(&self, film: &Film) -> Texture {
+		let width = film.width() as usize;
+		let height = film.height() as usize;
+
+		let guides: Vec<Option<Guide>> = (0..width * height).map(|index| {
+			let aovs = film.aovs((index % width) as u32, (index / width) as u32)?;
+			let albedo = aovs.value(Aov::Albedo);
+			let normal = aovs.value(Aov::Normal);
+
+			Some(Guide {
+				albedo: [albedo.r, albedo.g, albedo.b],
+				normal: [normal.r, normal.g, normal.b],
+				depth: aovs.value(Aov::Depth).r
+			})
+		}).collect();
+
+		// The filter runs on the lighting, textures and material edges come back when the albedo is multiplied in again
+		let mut lighting: Vec<[f32; 3]> = (0..width * height).map(|index| {
+			let color = film.color((index % width) as u32, (index / width) as u32);
+			let rgb = [color.r, color.g, color.b];
+
+			match &guides[index] {
+				None => rgb,
+				Some(guide) => demodulate(rgb, guide.albedo)
+			}
+		}).collect();
+
+		let mut filtered = lighting.clone();
+
+		for iteration in 0..self.iterations {
+			let step = 1usize << iteration;
+			// Later iterations average over more samples, their results need less tolerance to stay sharp
+			let color_sigma = (self.color_sigma / (1u64 << iteration) as f64) as f32;
+
+			filtered.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+				for (x, pixel) in row.iter_mut().enumerate() {
+					*pixel = self.filter_pixel(x, y, width, height, step, color_sigma, &lighting, &guides);
+				}
+			});
+
+			std::mem::swap(&mut lighting, &mut filtered);
+		}
+
+		let mut texture = Texture::new(
+			NonZeroU32::new(film.width().max(1)).unwrap(),
+			NonZeroU32::new(film.height().max(1)).unwrap(),
+			TextureWrapMode::Clamp,
+			Color::black()
+		);
+
+		for (index, rgb) in lighting.iter().enumerate() {
+			let [r, g, b] = match &guides[index] {
+				None => *rgb,
+				Some(guide) => remodulate(*rgb, guide.albedo)
+			};
+
+			texture.set_pixel((index % width) as u32, (index / width) as u32, Color::create(r, g, b, 1.0));
+		}
+
+		texture
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn filter_pixel(&self, x: usize, y: usize, width: usize, height: usize, step: usize, color_sigma: f32, lighting: &[[f32; 3]], guides: &[Option<Guide>]) -> [f32; 3] {
+		let index = y * width + x;
+		let center = lighting[index];
+		let center_compressed = compress(center);
+		let center_guide = guides[index];
+
+		let color_factor = 1.0 / (color_sigma * color_sigma).max(1e-8);
+		let normal_factor = 1.0 / (self.normal_sigma * self.normal_sigma).max(1e-8) as f32;
+		let albedo_factor = 1.0 / (self.albedo_sigma * self.albedo_sigma).max(1e-8) as f32;
+
+		let mut sum = [0.0f32; 3];
+		let mut weight_sum = 0.0f32;
+
+		for (row, kernel_y) in KERNEL.iter().enumerate() {
+			let sample_y = y as i64 + (row as i64 - 2) * step as i64;
+			if sample_y < 0 || sample_y >= height as i64 {
+				continue;
+			}
+
+			for (column, kernel_x) in KERNEL.iter().enumerate() {
+				let sample_x = x as i64 + (column as i64 - 2) * step as i64;
+				if sample_x < 0 || sample_x >= width as i64 {
+					continue;
+				}
+
+				let sample_index = sample_y as usize * width + sample_x as usize;
+				let sample = lighting[sample_index];
+
+				let mut exponent = distance_squared(center_compressed, compress(sample)) * color_factor;
+
+				if let (Some(center_guide), Some(sample_guide)) = (&center_guide, &guides[sample_index]) {
+					exponent += distance_squared(center_guide.normal, sample_guide.normal) * normal_factor;
+					exponent += distance_squared(center_guide.albedo, sample_guide.albedo) * albedo_factor;
+
+					let pixel_distance = ((row as i64 - 2).abs().max((column as i64 - 2).abs()) as usize * step) as f32;
+					let depth_tolerance = self.depth_sigma as f32 * pixel_distance * center_guide.depth.max(1e-3);
+					exponent += (center_guide.depth - sample_guide.depth).abs() / depth_tolerance.max(1e-8);
+				}
+
+				let weight = kernel_x * kernel_y * (-exponent).exp();
+
+				for channel in 0..3 {
+					sum[channel] += sample[channel] * weight;
+				}
+				weight_sum += weight;
+			}
+		}
+
+		// The center pixel always has a weight, unless its own color isn't finite
+		if weight_sum <= 0.0 || !weight_sum.is_finite() {
+			return center;
+		}
+
+		sum.map(|value| value / weight_sum)
+	}
+}
+
+impl Default for Denoiser {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// Keeps bright outliers from dominating the color distance
+fn compress(rgb: [f32; 3]) -> [f32; 3] {
+	rgb.map(|value| {
+		let value = value.max(0.0);
+		value / (1.0 + value)
+	})
+}
+
+fn distance_squared(a: [f32; 3], b: [f32; 3]) -> f32 {
+	(0..3).map(|channel| (a[channel] - b[channel]) * (a[channel] - b[channel])).sum()
+}
+
+fn demodulate(rgb: [f32; 3], albedo: [f32; 3]) -> [f32; 3] {
+	let mut result = rgb;
+
+	for channel in 0..3 {
+		if albedo[channel] > MIN_ALBEDO {
+			result[channel] /= albedo[channel];
+		}
+	}
+
+	result
+}
+
+fn remodulate(rgb: [f32; 3], albedo: [f32; 3]) -> [f32; 3] {
+	let mut result = rgb;
+
+	for channel in 0..3 {
+		if albedo[channel] > MIN_ALBEDO {
+			result[channel] *= albedo[channel];
+		}
+	}
+
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::raytracing::aov::AovSums;
+	use crate::raytracing::sampler::{hash_seed, Pcg32};
+	use super::*;
+
+	const SIZE: u32 = 32;
+
+	// One sample per pixel, `guide` gives the pixel's normal or no AOVs at all
+	fn film_with(color: impl Fn(u32, u32) -> f32, guide: Option<&dyn Fn(u32, u32) -> [f64; 3]>) -> Film {
+		let mut film = Film::create(SIZE, SIZE);
+
+		for y in 0..SIZE {
+			for x in 0..SIZE {
+				let value = color(x, y);
+				film.add_samples(x, y, Color::create(value, value, value, 1.0), 0.0, 1);
+
+				if let Some(normal) = guide {
+					film.add_aovs(x, y, &AovSums {
+						sample_count: 1,
+						hit_count: 1,
+						depth: 2.0,
+						normal: normal(x, y),
+						albedo: [0.5, 0.5, 0.5],
+						..AovSums::new()
+					});
+				}
+			}
+		}
+
+		film
+	}
+
+	fn noisy(seed: u64) -> impl Fn(u32, u32) -> f32 {
+		move |x, y| Pcg32::create(hash_seed(seed, &[x as u64, y as u64])).next_range(0.2, 0.8) as f32
+	}
+
+	fn red(texture: &Texture) -> Vec<f32> {
+		texture.get_raw().chunks(4).map(|pixel| pixel[0]).collect()
+	}
+
+	fn variance(values: &[f32]) -> f32 {
+		let mean = values.iter().sum::<f32>() / values.len() as f32;
+
+		values.iter().map(|value| (value - mean) * (value - mean)).sum::<f32>() / values.len() as f32
+	}
+
+	// Mean of one column of pixels
+	fn column(values: &[f32], x: u32) -> f32 {
+		(0..SIZE).map(|y| values[(y * SIZE + x) as usize]).sum::<f32>() / SIZE as f32
+	}
+
+	#[test]
+	fn constant_films_are_unchanged() {
+		let flat = |_, _| [0.0, 1.0, 0.0];
+
+		for guide in [Some(&flat as &dyn Fn(u32, u32) -> [f64; 3]), None] {
+			let denoised = Denoiser::new().denoise(&film_with(|_, _| 0.3, guide));
+
+			assert!(red(&denoised).iter().all(|value| (value - 0.3).abs() < 1e-5));
+		}
+	}
+
+	#[test]
+	fn noise_drops_on_flat_films() {
+		let flat = |_, _| [0.0, 1.0, 0.0];
+		let film = film_with(noisy(1), Some(&flat));
+		let noise = variance(&red(&film.to_texture()));
+
+		let denoised = variance(&red(&Denoiser::new().denoise(&film)));
+
+		assert!(denoised < noise * 0.25, "{} -> {}", noise, denoised);
+	}
+
+	#[test]
+	fn films_without_aovs_are_denoised() {
+		let film = film_with(noisy(2), None);
+		let noise = variance(&red(&film.to_texture()));
+
+		let denoised = red(&Denoiser::new().denoise(&film));
+
+		assert_eq!(denoised.len(), (SIZE * SIZE) as usize);
+		assert!(denoised.iter().all(|value| value.is_finite()));
+		assert!(variance(&denoised) < noise * 0.5, "{} -> {}", noise, variance(&denoised));
+	}
+
+	#[test]
+	fn normal_edges_survive() {
+		// The two halves are too close in color for the color distance alone to keep them apart
+		let color = |x: u32, _| if x < SIZE / 2 { 0.45 } else { 0.55 };
+		let edge = |x: u32, _| if x < SIZE / 2 { [1.0, 0.0, 0.0] } else { [0.0, 0.0, 1.0] };
+		let flat = |_, _| [0.0, 1.0, 0.0];
+
+		let guided = red(&Denoiser::new().denoise(&film_with(color, Some(&edge))));
+		let unguided = red(&Denoiser::new().denoise(&film_with(color, Some(&flat))));
+
+		assert!((column(&guided, SIZE / 2 - 1) - 0.45).abs() < 1e-3);
+		assert!((column(&guided, SIZE / 2) - 0.55).abs() < 1e-3);
+		assert!((column(&unguided, SIZE / 2 - 1) - 0.45).abs() > 0.01);
+	}
+}
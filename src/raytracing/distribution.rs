@@ -0,0 +1,223 @@
+// Piecewise constant distribution over [0, 1), after Pharr, Jakob and Humphreys' Physically Based Rendering
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution1D {
+	function: Vec<f64>,
+	cdf: Vec<f64>,
+	integral: f64
+}
+
+impl Distribution1D {
+	// Negative values count as 0, a function that is 0 everywhere samples uniformly but has a pdf of 0
+	pub fn create(function: Vec<f64>) -> Self {
+		let function: Vec<f64> = function.into_iter().map(|value| value.max(0.0)).collect();
+		let count = function.len().max(1);
+
+		let mut cdf = Vec::with_capacity(count + 1);
+		cdf.push(0.0);
+
+		for value in &function {
+			cdf.push(cdf.last().unwrap() + value / count as f64);
+		}
+
+		let integral = *cdf.last().unwrap();
+
+		if integral > 0.0 {
+			for value in cdf.iter_mut() {
+				*value /= integral;
+			}
+		}
+		else {
+			for (index, value) in cdf.iter_mut().enumerate() {
+				*value = index as f64 / count as f64;
+			}
+		}
+
+		Self {
+			function,
+			cdf,
+			integral
+		}
+	}
+
+	pub fn count(&self) -> usize {
+		self.function.len()
+	}
+
+	pub fn integral(&self) -> f64 {
+		self.integral
+	}
+
+	pub fn value(&self, index: usize) -> f64 {
+		self.function.get(index).copied().unwrap_or(0.0)
+	}
+
+	// Position in [0, 1), its pdf and the index of the piece it lies in
+	pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+		let count = self.function.len();
+		if count == 0 {
+			return (u, 0.0, 0);
+		}
+
+		let index = (self.cdf.partition_point(|value| *value <= u) - 1).min(count - 1);
+
+		let start = self.cdf[index];
+		let width = self.cdf[index + 1] - start;
+
+		let offset = if width > 0.0 {
+			(u - start) / width
+		}
+		else {
+			0.0
+		};
+
+		let position = ((index as f64 + offset) / count as f64).min(1.0 - f64::EPSILON);
+
+		(position, self.pdf(index), index)
+	}
+
+	// Density at any position inside piece `index`
+	pub fn pdf(&self, index: usize) -> f64 {
+		if self.integral > 0.0 {
+			self.value(index) / self.integral
+		}
+		else {
+			0.0
+		}
+	}
+}
+
+// Distribution over [0, 1)², rows are picked first and the position inside a row after that
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution2D {
+	rows: Vec<Distribution1D>,
+	marginal: Distribution1D
+}
+
+impl Distribution2D {
+	// `values` holds `height` rows of `width` values each
+	pub fn create(values: &[f64], width: usize, height: usize) -> Self {
+		let rows: Vec<Distribution1D> = (0..height)
+			.map(|row| Distribution1D::create(values[row * width..(row + 1) * width].to_vec()))
+			.collect();
+
+		let marginal = Distribution1D::create(rows.iter().map(Distribution1D::integral).collect());
+
+		Self {
+			rows,
+			marginal
+		}
+	}
+
+	pub fn integral(&self) -> f64 {
+		self.marginal.integral()
+	}
+
+	// Position with x along the rows and y across them, and its pdf
+	pub fn sample(&self, u: f64, v: f64) -> (f64, f64, f64) {
+		let (y, row_pdf, row) = self.marginal.sample(v);
+
+		match self.rows.get(row) {
+			None => (u, y, 0.0),
+			Some(distribution) => {
+				let (x, column_pdf, _) = distribution.sample(u);
+
+				(x, y, row_pdf * column_pdf)
+			}
+		}
+	}
+
+	pub fn pdf(&self, x: f64, y: f64) -> f64 {
+		if self.rows.is_empty() || self.marginal.integral() <= 0.0 {
+			return 0.0;
+		}
+
+		let row = ((y * self.rows.len() as f64) as usize).min(self.rows.len() - 1);
+		let distribution = &self.rows[row];
+		let column = ((x * distribution.count() as f64) as usize).min(distribution.count().saturating_sub(1));
+
+		distribution.value(column) / self.marginal.integral()
+	}
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SAMPLES: usize = 100_000;
+
+	fn stratified(index: usize, count: usize) -> f64 {
+		(index as f64 + 0.5) / count as f64
+	}
+
+	#[test]
+	fn samples_1d_follow_their_pdf() {
+		let distribution = Distribution1D::create(vec![1.0, 0.0, 3.0, 0.5, 2.0]);
+		let count = distribution.count();
+
+		let total: f64 = (0..count).map(|index| distribution.pdf(index) / count as f64).sum();
+		assert!((total - 1.0).abs() < 1e-12);
+
+		let mut hits = vec![0usize; count];
+		for index in 0..SAMPLES {
+			let (position, pdf, piece) = distribution.sample(stratified(index, SAMPLES));
+
+			assert!((0.0..1.0).contains(&position));
+			assert_eq!(piece, (position * count as f64) as usize);
+			assert_eq!(pdf, distribution.pdf(piece));
+			hits[piece] += 1;
+		}
+
+		assert_eq!(hits[1], 0);
+		for (piece, hits) in hits.into_iter().enumerate() {
+			let expected = distribution.pdf(piece) / count as f64;
+			assert!((hits as f64 / SAMPLES as f64 - expected).abs() < 1e-3, "piece {}", piece);
+		}
+	}
+
+	#[test]
+	fn zero_1d_functions_sample_uniformly_with_a_zero_pdf() {
+		let distribution = Distribution1D::create(vec![0.0, -1.0, 0.0, 0.0]);
+
+		for index in 0..16 {
+			let u = stratified(index, 16);
+			let (position, pdf, _) = distribution.sample(u);
+
+			assert!((position - u).abs() < 1e-12);
+			assert_eq!(pdf, 0.0);
+		}
+	}
+
+	#[test]
+	fn samples_2d_follow_their_pdf() {
+		let (width, height) = (4, 3);
+		let values = [
+			1.0, 2.0, 0.0, 1.0,
+			0.0, 0.0, 0.0, 0.0,
+			4.0, 1.0, 3.0, 0.5
+		];
+		let distribution = Distribution2D::create(&values, width, height);
+		let cell_area = 1.0 / (width * height) as f64;
+
+		let cell_center = |cell: usize| ((cell % width) as f64 + 0.5) / width as f64;
+		let row_center = |cell: usize| ((cell / width) as f64 + 0.5) / height as f64;
+
+		let total: f64 = (0..width * height).map(|cell| distribution.pdf(cell_center(cell), row_center(cell)) * cell_area).sum();
+		assert!((total - 1.0).abs() < 1e-12);
+
+		let side = 400;
+		let mut hits = vec![0usize; width * height];
+		for row in 0..side {
+			for column in 0..side {
+				let (x, y, pdf) = distribution.sample(stratified(column, side), stratified(row, side));
+
+				assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+				assert!((pdf - distribution.pdf(x, y)).abs() < 1e-12);
+				hits[(y * height as f64) as usize * width + (x * width as f64) as usize] += 1;
+			}
+		}
+
+		for (cell, hits) in hits.into_iter().enumerate() {
+			let expected = distribution.pdf(cell_center(cell), row_center(cell)) * cell_area;
+			assert!((hits as f64 / (side * side) as f64 - expected).abs() < 1e-3, "cell {}", cell);
+		}
+	}
+}
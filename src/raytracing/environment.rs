@@ -0,0 +1,141 @@
+use std::f64::consts::PI;
+use std::fmt::{Debug, Formatter};
+use crate::raytracing::color::Color;
+use crate::raytracing::distribution::Distribution2D;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::texture::Texture;
+use crate::raytracing::texture_sampling::TextureSampling;
+use crate::raytracing::vector_2d::Vec2;
+use crate::raytracing::vector_3d::Vec3;
+
+// Light from infinitely far away, stored as an equirectangular image with +y at the top row
+#[derive(Clone)]
+pub struct EnvironmentMap {
+	texture: Texture,
+	// Around +y, in degrees
+	rotation: f64,
+	intensity: f32,
+	// Over the image, proportional to luminance times the solid angle every texel covers
+	distribution: Distribution2D
+}
+
+impl EnvironmentMap {
+	pub fn create(texture: Texture, rotation: f64, intensity: f32) -> Self {
+		let width = texture.get_width().get() as usize;
+		let height = texture.get_height().get() as usize;
+
+		let luminance: Vec<f64> = (0..width * height)
+			.map(|index| Color::from(&texture.get_pixel((index % width) as u32, (index / width) as u32).unwrap()).luminance().max(0.0) as f64)
+			.collect();
+
+		// Lookups are bilinear and wrap around, so every texel also lights up the area of its neighbours
+		let mut values = vec![0.0; width * height];
+
+		for y in 0..height {
+			let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+
+			for x in 0..width {
+				let mut maximum: f64 = 0.0;
+
+				for offset_y in [height - 1, 0, 1] {
+					for offset_x in [width - 1, 0, 1] {
+						maximum = maximum.max(luminance[((y + offset_y) % height) * width + (x + offset_x) % width]);
+					}
+				}
+
+				values[y * width + x] = maximum * sin_theta;
+			}
+		}
+
+		Self {
+			distribution: Distribution2D::create(&values, width, height),
+			texture,
+			rotation,
+			intensity
+		}
+	}
+
+	pub fn texture(&self) -> &Texture {
+		&self.texture
+	}
+
+	pub fn rotation(&self) -> f64 {
+		self.rotation
+	}
+
+	pub fn intensity(&self) -> f32 {
+		self.intensity
+	}
+
+	// Whether it gives off any light that sampling could find
+	pub fn can_be_sampled(&self) -> bool {
+		self.intensity > 0.0 && self.distribution.integral() > 0.0
+	}
+
+	pub fn color(&self, direction: Vec3) -> Color {
+		let (x, y) = self.to_image(direction);
+		let color = self.texture.sample_bilinear(Vec2::create(x, 1.0 - y));
+
+		Color::create(color.r, color.g, color.b, 1.0) * self.intensity
+	}
+
+	// Normalized direction towards the environment and its solid angle pdf
+	pub fn sample(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+		let (u, v) = sampler.next_2d();
+		let (x, y, image_pdf) = self.distribution.sample(u, v);
+
+		let sin_theta = (PI * y).sin();
+		if image_pdf <= 0.0 || sin_theta <= 0.0 {
+			return None;
+		}
+
+		Some((self.to_direction(x, y), image_pdf / (2.0 * PI * PI * sin_theta)))
+	}
+
+	pub fn pdf(&self, direction: Vec3) -> f64 {
+		let (x, y) = self.to_image(direction);
+
+		let sin_theta = (PI * y).sin();
+		if sin_theta <= 0.0 {
+			return 0.0;
+		}
+
+		self.distribution.pdf(x, y) / (2.0 * PI * PI * sin_theta)
+	}
+
+	// Position in the image, both in [0, 1) with y growing downwards
+	fn to_image(&self, direction: Vec3) -> (f64, f64) {
+		let direction = rotate_y(direction.normalized(), -self.rotation);
+
+		let theta = direction.y.clamp(-1.0, 1.0).acos();
+		let phi = direction.z.atan2(direction.x).rem_euclid(2.0 * PI);
+
+		(phi / (2.0 * PI), theta / PI)
+	}
+
+	fn to_direction(&self, x: f64, y: f64) -> Vec3 {
+		let theta = PI * y;
+		let phi = 2.0 * PI * x;
+
+		let direction = Vec3::create(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+
+		rotate_y(direction, self.rotation)
+	}
+}
+
+impl Debug for EnvironmentMap {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("EnvironmentMap")
+			.field("width", &self.texture.get_width())
+			.field("height", &self.texture.get_height())
+			.field("rotation", &self.rotation)
+			.field("intensity", &self.intensity)
+			.finish()
+	}
+}
+
+fn rotate_y(direction: Vec3, degrees: f64) -> Vec3 {
+	let (sin, cos) = degrees.to_radians().sin_cos();
+
+	Vec3::create(cos * direction.x + sin * direction.z, direction.y, -sin * direction.x + cos * direction.z)
+}
\ No newline at end of file
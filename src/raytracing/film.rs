@@ -0,0 +1,415 @@
+use std::num::NonZeroU32;
+use crate::raytracing::aov::{Aov, AovSums};
+use crate::raytracing::color::Color;
+use crate::raytracing::filter::Filter;
+use crate::raytracing::raytracer::AdaptiveSettings;
+use crate::raytracing::texture::{Texture, TextureWrapMode};
+
+// Keeps dark pixels from needing an ever smaller absolute error to converge
+const ERROR_LUMINANCE_FLOOR: f64 = 0.01;
+
+// Relative standard error of the mean luminance, None until there are enough samples to estimate a variance
+pub fn relative_error(luminance_sum: f64, luminance_square_sum: f64, sample_count: usize) -> Option<f64> {
+	if sample_count < 2 {
+		return None;
+	}
+
+	let count = sample_count as f64;
+	let mean = luminance_sum / count;
+	let variance = ((luminance_square_sum - luminance_sum * mean) / (count - 1.0)).max(0.0);
+	let standard_error = (variance / count).sqrt();
+
+	Some(standard_error / mean.max(ERROR_LUMINANCE_FLOOR))
+}
+
+// Splats arrive in whatever order the workers finish, fixed point sums make the result independent of that order
+const SPLAT_SCALE: f64 = (1u64 << 24) as f64;
+
+// Filter weighted samples of one pixel, added to the pixel at `x`, `y`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Splat {
+	pub x: u32,
+	pub y: u32,
+	pub weighted_color: [f64; 3],
+	pub weight: f64
+}
+
+// Collects the splats of all samples taken in one pixel, so the film gets one splat per neighbouring pixel instead of one per sample
+#[derive(Clone, Debug)]
+pub struct SplatWindow {
+	x: u32,
+	y: u32,
+	filter: Filter,
+	reach: u32,
+	splats: Vec<([f64; 3], f64)>,
+	weights_x: Vec<f64>,
+	weights_y: Vec<f64>
+}
+
+impl SplatWindow {
+	pub fn create(x: u32, y: u32, filter: Filter) -> Self {
+		let reach = filter.pixel_reach();
+		let size = 2 * reach as usize + 1;
+
+		Self {
+			x,
+			y,
+			filter,
+			reach,
+			splats: vec![([0.0; 3], 0.0); size * size],
+			weights_x: vec![0.0; size],
+			weights_y: vec![0.0; size]
+		}
+	}
+
+	// `offset_x` and `offset_y` are the sample position inside the pixel, in [0, 1)
+	pub fn add_sample(&mut self, offset_x: f64, offset_y: f64, color: Color) {
+		let size = self.weights_x.len();
+
+		for index in 0..size {
+			let pixel_offset = index as f64 - self.reach as f64;
+			self.weights_x[index] = self.filter.evaluate_1d(offset_x - 0.5 - pixel_offset);
+			self.weights_y[index] = self.filter.evaluate_1d(offset_y - 0.5 - pixel_offset);
+		}
+
+		for row in 0..size {
+			for column in 0..size {
+				let weight = self.weights_x[column] * self.weights_y[row];
+				if weight == 0.0 {
+					continue;
+				}
+
+				let (weighted_color, weight_sum) = &mut self.splats[row * size + column];
+				weighted_color[0] += color.r as f64 * weight;
+				weighted_color[1] += color.g as f64 * weight;
+				weighted_color[2] += color.b as f64 * weight;
+				*weight_sum += weight;
+			}
+		}
+	}
+
+	pub fn into_splats(self, width: u32, height: u32) -> Vec<Splat> {
+		let size = 2 * self.reach as usize + 1;
+		let mut splats = Vec::with_capacity(self.splats.len());
+
+		for (index, (weighted_color, weight)) in self.splats.into_iter().enumerate() {
+			let x = self.x as i64 + (index % size) as i64 - self.reach as i64;
+			let y = self.y as i64 + (index / size) as i64 - self.reach as i64;
+
+			if weight == 0.0 || x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+				continue;
+			}
+
+			splats.push(Splat {
+				x: x as u32,
+				y: y as u32,
+				weighted_color,
+				weight
+			});
+		}
+
+		splats
+	}
+}
+
+// Running per pixel sums of linear radiance.
+// The statistics only count the samples taken inside a pixel, the color is reconstructed from the splats of all samples near it.
+#[derive(Clone, Debug, Default)]
+pub struct Film {
+	width: u32,
+	height: u32,
+	sums: Vec<[f64; 3]>,
+	luminance_square_sums: Vec<f64>,
+	sample_counts: Vec<usize>,
+	splat_sums: Vec<[i64; 3]>,
+	splat_weights: Vec<i64>,
+	// Unfiltered, only allocated once the first AOVs arrive
+	aov_sums: Vec<AovSums>
+}
+
+impl Film {
+	pub fn create(width: u32, height: u32) -> Self {
+		let pixel_count = width as usize * height as usize;
+
+		Self {
+			width,
+			height,
+			sums: vec![[0.0; 3]; pixel_count],
+			luminance_square_sums: vec![0.0; pixel_count],
+			sample_counts: vec![0; pixel_count],
+			splat_sums: vec![[0; 3]; pixel_count],
+			splat_weights: vec![0; pixel_count],
+			aov_sums: Vec::new()
+		}
+	}
+
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	pub fn clear(&mut self) {
+		self.sums.fill([0.0; 3]);
+		self.luminance_square_sums.fill(0.0);
+		self.sample_counts.fill(0);
+		self.splat_sums.fill([0; 3]);
+		self.splat_weights.fill(0);
+		self.aov_sums = Vec::new();
+	}
+
+	pub fn add_splat(&mut self, splat: &Splat) {
+		let index = match self.index(splat.x, splat.y) {
+			None => return,
+			Some(index) => index
+		};
+
+		for (sum, weighted_color) in self.splat_sums[index].iter_mut().zip(splat.weighted_color) {
+			*sum = sum.saturating_add(to_fixed_point(weighted_color));
+		}
+
+		self.splat_weights[index] = self.splat_weights[index].saturating_add(to_fixed_point(splat.weight));
+	}
+
+	// Filtered pixel color, falls back to the plain average where the filter weights cancel out
+	pub fn color(&self, x: u32, y: u32) -> Color {
+		let index = match self.index(x, y) {
+			None => return Color::black(),
+			Some(index) => index
+		};
+
+		let weight = self.splat_weights[index];
+		if weight <= 0 {
+			return self.average(x, y);
+		}
+
+		let [r, g, b] = self.splat_sums[index];
+		let scale = 1.0 / weight as f64;
+
+		Color::create((r as f64 * scale) as f32, (g as f64 * scale) as f32, (b as f64 * scale) as f32, 1.0)
+	}
+
+	pub fn add_samples(&mut self, x: u32, y: u32, color_sum: Color, luminance_square_sum: f64, sample_count: usize) {
+		let index = match self.index(x, y) {
+			None => return,
+			Some(index) => index
+		};
+
+		let sum = &mut self.sums[index];
+		sum[0] += color_sum.r as f64;
+		sum[1] += color_sum.g as f64;
+		sum[2] += color_sum.b as f64;
+
+		self.luminance_square_sums[index] += luminance_square_sum;
+		self.sample_counts[index] += sample_count;
+	}
+
+	pub fn add_aovs(&mut self, x: u32, y: u32, aovs: &AovSums) {
+		let index = match self.index(x, y) {
+			None => return,
+			Some(index) => index
+		};
+
+		if self.aov_sums.is_empty() {
+			self.aov_sums = vec![AovSums::new(); self.sums.len()];
+		}
+
+		self.aov_sums[index].add(aovs);
+	}
+
+	pub fn has_aovs(&self) -> bool {
+		!self.aov_sums.is_empty()
+	}
+
+	pub fn aovs(&self, x: u32, y: u32) -> Option<&AovSums> {
+		let index = self.index(x, y)?;
+
+		self.aov_sums.get(index)
+	}
+
+	pub fn sample_count(&self, x: u32, y: u32) -> usize {
+		match self.index(x, y) {
+			None => 0,
+			Some(index) => self.sample_counts[index]
+		}
+	}
+
+	// Whether any sample, of this pixel or a neighbor, reached the pixel yet
+	pub fn is_sampled(&self, x: u32, y: u32) -> bool {
+		match self.index(x, y) {
+			None => false,
+			Some(index) => self.sample_counts[index] > 0 || self.splat_weights[index] != 0
+		}
+	}
+
+	pub fn average(&self, x: u32, y: u32) -> Color {
+		let index = match self.index(x, y) {
+			None => return Color::black(),
+			Some(index) => index
+		};
+
+		let sample_count = self.sample_counts[index];
+		if sample_count == 0 {
+			return Color::black();
+		}
+
+		let [r, g, b] = self.sums[index];
+		let scale = 1.0 / sample_count as f64;
+
+		Color::create((r * scale) as f32, (g * scale) as f32, (b * scale) as f32, 1.0)
+	}
+
+	pub fn relative_error(&self, x: u32, y: u32) -> Option<f64> {
+		let index = self.index(x, y)?;
+
+		self.index_relative_error(index)
+	}
+
+	pub fn converged_pixels(&self, settings: &AdaptiveSettings) -> Vec<bool> {
+		(0..self.sample_counts.len()).map(|index| {
+			if self.sample_counts[index] < settings.min_samples_per_pixel {
+				return false;
+			}
+
+			match self.index_relative_error(index) {
+				None => false,
+				Some(error) => error <= settings.error_threshold
+			}
+		}).collect()
+	}
+
+	// Reconstructed linear colors, unlike the raytracer's texture nothing is tone mapped
+	pub fn to_texture(&self) -> Texture {
+		let mut texture = Texture::new(
+			NonZeroU32::new(self.width.max(1)).unwrap(),
+			NonZeroU32::new(self.height.max(1)).unwrap(),
+			TextureWrapMode::Clamp,
+			Color::black()
+		);
+
+		for y in 0..self.height {
+			for x in 0..self.width {
+				texture.set_pixel(x, y, self.color(x, y));
+			}
+		}
+
+		texture
+	}
+
+	// Raw AOV values, None if the render didn't produce any
+	pub fn aov_texture(&self, aov: Aov) -> Option<Texture> {
+		if self.aov_sums.is_empty() {
+			return None;
+		}
+
+		let mut texture = Texture::new(
+			NonZeroU32::new(self.width.max(1)).unwrap(),
+			NonZeroU32::new(self.height.max(1)).unwrap(),
+			TextureWrapMode::Clamp,
+			Color::black()
+		);
+
+		for y in 0..self.height {
+			for x in 0..self.width {
+				let index = y as usize * self.width as usize + x as usize;
+				texture.set_pixel(x, y, self.aov_sums[index].value(aov));
+			}
+		}
+
+		Some(texture)
+	}
+
+	// Grayscale heatmap, white is the highest sample count of any pixel
+	pub fn sample_count_texture(&self) -> Texture {
+		let mut texture = Texture::new(
+			NonZeroU32::new(self.width.max(1)).unwrap(),
+			NonZeroU32::new(self.height.max(1)).unwrap(),
+			TextureWrapMode::Clamp,
+			Color::black()
+		);
+
+		let max_sample_count = self.sample_counts.iter().copied().max().unwrap_or(0).max(1);
+
+		for y in 0..self.height {
+			for x in 0..self.width {
+				let value = self.sample_count(x, y) as f32 / max_sample_count as f32;
+				texture.set_pixel(x, y, Color::create(value, value, value, 1.0));
+			}
+		}
+
+		texture
+	}
+
+	fn index_relative_error(&self, index: usize) -> Option<f64> {
+		let [r, g, b] = self.sums[index];
+		let luminance_sum = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+
+		relative_error(luminance_sum, self.luminance_square_sums[index], self.sample_counts[index])
+	}
+
+	fn index(&self, x: u32, y: u32) -> Option<usize> {
+		if x >= self.width || y >= self.height {
+			return None;
+		}
+
+		Some(y as usize * self.width as usize + x as usize)
+	}
+}
+
+fn to_fixed_point(value: f64) -> i64 {
+	if !value.is_finite() {
+		return 0;
+	}
+
+	(value * SPLAT_SCALE).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::seq::SliceRandom;
+	use crate::raytracing::sampler::Pcg32;
+	use super::*;
+
+	fn random_splats(rng: &mut Pcg32, count: usize) -> Vec<Splat> {
+		(0..count)
+			.map(|_| Splat {
+				x: rng.next_u32() % 4,
+				y: rng.next_u32() % 3,
+				weighted_color: [rng.next_range(-0.5, 20.0), rng.next_range(0.0, 1.0), rng.next_range(0.0, 1e-3)],
+				weight: rng.next_range(-0.1, 1.0)
+			})
+			.collect()
+	}
+
+	fn film_with(splats: &[Splat]) -> Film {
+		let mut film = Film::create(4, 3);
+		for splat in splats {
+			film.add_splat(splat);
+		}
+
+		film
+	}
+
+	#[test]
+	fn splat_order_does_not_change_the_image() {
+		let mut rng = Pcg32::create(9);
+		let mut splats = random_splats(&mut rng, 5000);
+
+		let expected = film_with(&splats);
+
+		splats.reverse();
+		let reversed = film_with(&splats);
+
+		splats.shuffle(&mut rand::thread_rng());
+		let shuffled = film_with(&splats);
+
+		for y in 0..3 {
+			for x in 0..4 {
+				assert_eq!(reversed.color(x, y), expected.color(x, y));
+				assert_eq!(shuffled.color(x, y), expected.color(x, y));
+			}
+		}
+	}
+}
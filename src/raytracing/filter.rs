@@ -0,0 +1,190 @@
+use std::f64::consts::PI;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde_derive::{Serialize, Deserialize};
+
+// Pixel reconstruction filters, every sample is weighted by its offset from the pixel center.
+// All filters are separable, the radius is in pixels along each axis.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Filter {
+	Box {
+		radius: f64
+	},
+	Tent {
+		radius: f64
+	},
+	Gaussian {
+		radius: f64
+	},
+	Mitchell {
+		radius: f64
+	},
+	Lanczos {
+		radius: f64
+	}
+}
+
+impl Filter {
+	pub fn new() -> Self {
+		Filter::Box {
+			radius: 0.5
+		}
+	}
+
+	pub fn radius(&self) -> f64 {
+		match self {
+			Filter::Box { radius } => *radius,
+			Filter::Tent { radius } => *radius,
+			Filter::Gaussian { radius } => *radius,
+			Filter::Mitchell { radius } => *radius,
+			Filter::Lanczos { radius } => *radius
+		}
+	}
+
+	pub fn with_radius(&self, radius: f64) -> Self {
+		match self {
+			Filter::Box { .. } => Filter::Box { radius },
+			Filter::Tent { .. } => Filter::Tent { radius },
+			Filter::Gaussian { .. } => Filter::Gaussian { radius },
+			Filter::Mitchell { .. } => Filter::Mitchell { radius },
+			Filter::Lanczos { .. } => Filter::Lanczos { radius }
+		}
+	}
+
+	// How many pixels away from its own pixel a sample can still contribute to
+	pub fn pixel_reach(&self) -> u32 {
+		((self.radius() + 0.5).ceil() as u32).saturating_sub(1)
+	}
+
+	pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+		self.evaluate_1d(x) * self.evaluate_1d(y)
+	}
+
+	pub fn evaluate_1d(&self, x: f64) -> f64 {
+		let radius = self.radius();
+		let x = x.abs();
+
+		if x > radius {
+			return 0.0;
+		}
+
+		match self {
+			Filter::Box { .. } => 1.0,
+			Filter::Tent { .. } => radius - x,
+			Filter::Gaussian { .. } => {
+				// Shifted down so it reaches zero at the radius instead of being cut off
+				let sigma = radius / 3.0;
+
+				gaussian(x, sigma) - gaussian(radius, sigma)
+			}
+			Filter::Mitchell { .. } => mitchell(2.0 * x / radius),
+			Filter::Lanczos { .. } => sinc(x) * sinc(x / radius)
+		}
+	}
+}
+
+impl Default for Filter {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// Filter name with an optional radius, e.g. "mitchell" or "gaussian,2"
+impl FromStr for Filter {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let (name, radius) = match value.split_once(',') {
+			None => (value.trim(), None),
+			Some((name, radius)) => (name.trim(), Some(radius.trim()))
+		};
+
+		let filter = match name {
+			"box" => Filter::Box { radius: 0.5 },
+			"tent" => Filter::Tent { radius: 1.0 },
+			"gaussian" => Filter::Gaussian { radius: 1.5 },
+			"mitchell" => Filter::Mitchell { radius: 2.0 },
+			"lanczos" => Filter::Lanczos { radius: 2.0 },
+			other => return Err(format!("unknown filter '{}'", other))
+		};
+
+		let radius = match radius {
+			None => return Ok(filter),
+			Some(radius) => radius
+		};
+
+		match radius.parse::<f64>() {
+			Ok(radius) if radius > 0.0 && radius.is_finite() => Ok(filter.with_radius(radius)),
+			_ => Err(format!("invalid filter radius '{}'", radius))
+		}
+	}
+}
+
+impl Display for Filter {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Filter::Box { .. } => "box",
+			Filter::Tent { .. } => "tent",
+			Filter::Gaussian { .. } => "gaussian",
+			Filter::Mitchell { .. } => "mitchell",
+			Filter::Lanczos { .. } => "lanczos"
+		};
+
+		write!(f, "{},{}", name, self.radius())
+	}
+}
+
+fn gaussian(x: f64, sigma: f64) -> f64 {
+	(-(x * x) / (2.0 * sigma * sigma)).exp()
+}
+
+fn sinc(x: f64) -> f64 {
+	if x.abs() < 1e-5 {
+		return 1.0;
+	}
+
+	let x = x * PI;
+
+	x.sin() / x
+}
+
+// Mitchell-Netravali with B = C = 1/3, `x` is scaled to [0, 2]
+fn mitchell(x: f64) -> f64 {
+	const B: f64 = 1.0 / 3.0;
+	const C: f64 = 1.0 / 3.0;
+
+	let x = x.abs();
+
+	let value = if x > 1.0 {
+		(-B - 6.0 * C) * x.powi(3) + (6.0 * B + 30.0 * C) * x.powi(2) + (-12.0 * B - 48.0 * C) * x + (8.0 * B + 24.0 * C)
+	}
+	else {
+		(12.0 - 9.0 * B - 6.0 * C) * x.powi(3) + (-18.0 + 12.0 * B + 6.0 * C) * x.powi(2) + (6.0 - 2.0 * B)
+	};
+
+	value / 6.0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn filters_round_trip_through_strings() {
+		for name in ["box", "tent", "gaussian", "mitchell", "lanczos"] {
+			let filter = name.parse::<Filter>().unwrap();
+			assert_eq!(filter.to_string().parse::<Filter>(), Ok(filter));
+
+			let filter = filter.with_radius(0.1 + 1.0 / 3.0);
+			assert_eq!(filter.to_string().parse::<Filter>(), Ok(filter));
+		}
+	}
+
+	#[test]
+	fn rejects_invalid_filters() {
+		for value in ["", "sinc", "box,0", "tent,-1", "gaussian,inf", "mitchell,wide"] {
+			assert!(value.parse::<Filter>().is_err(), "{}", value);
+		}
+	}
+}
\ No newline at end of file
@@ -1,15 +1,24 @@
 use std::sync::Arc;
 use crate::raytracing::materials::Material;
 use crate::raytracing::ray::Ray;
+use crate::raytracing::vector_2d::Vec2;
 use crate::raytracing::vector_3d::Vec3;
 
 #[derive(Clone)]
 pub struct HitRecord {
 	pub point: Vec3,
 	pub normal: Vec3,
+	pub geometric_normal: Vec3,
 	pub material: Option<Arc<dyn Material + Send + Sync>>,
 	pub front_face: bool,
 	pub t: f64,
+	// Index of the hit object in the scene, set by the scene
+	pub object_index: Option<usize>,
+	pub uv: Vec2,
+	// UV units per world unit around the hit, how far apart texels are on the surface
+	pub uv_density: f64,
+	// Width of the pixel's footprint at the hit in UV units, set by the integrator for texture filtering
+	pub uv_footprint: f64
 }
 
 impl HitRecord {
@@ -17,9 +26,14 @@ impl HitRecord {
 		Self {
 			point: Vec3::create(0.0,0.0,0.0),
 			normal: Vec3::create(0.0,0.0,0.0),
+			geometric_normal: Vec3::create(0.0,0.0,0.0),
 			material: None,
 			front_face: false,
-			t: 0.0
+			t: 0.0,
+			object_index: None,
+			uv: Vec2::new(),
+			uv_density: 0.0,
+			uv_footprint: 0.0
 		}
 	}
 
@@ -31,7 +45,19 @@ impl HitRecord {
 		}
 		else {
 			-normal
+		};
+		self.geometric_normal = self.normal;
+	}
+
+	pub fn set_face_normals(&mut self, ray: &Ray, geometric_normal: Vec3, shading_normal: Vec3) {
+		self.front_face = Vec3::dot(ray.direction, geometric_normal) < 0.0;
+
+		(self.normal, self.geometric_normal) = if self.front_face {
+			(shading_normal, geometric_normal)
 		}
+		else {
+			(-shading_normal, -geometric_normal)
+		};
 	}
 }
 
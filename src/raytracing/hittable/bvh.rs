@@ -0,0 +1,431 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::raytracing::aabb::Aabb;
+use crate::raytracing::hit_record::HitRecord;
+use crate::raytracing::ray::Ray;
+use crate::raytracing::vector_3d::Vec3;
+
+const BIN_COUNT: usize = 16;
+const MAX_LEAF_SIZE: usize = 4;
+const MAX_DEPTH: usize = 60;
+const TRAVERSAL_COST: f64 = 1.0;
+const INTERSECTION_COST: f64 = 1.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum BvhNodeContent {
+	Leaf {
+		first_primitive: usize,
+		primitive_count: usize
+	},
+	Interior {
+		second_child: usize,
+		axis: usize
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct BvhNode {
+	bounds: Aabb,
+	content: BvhNodeContent
+}
+
+#[derive(Copy, Clone)]
+struct BuildPrimitive {
+	index: usize,
+	bounds: Aabb,
+	centroid: Vec3
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct BvhStatistics {
+	pub primitive_count: usize,
+	pub node_count: usize,
+	pub leaf_count: usize,
+	pub depth: usize,
+	pub rays_traced: u64,
+	pub nodes_visited: u64,
+	pub average_nodes_visited: f64
+}
+
+// Shared by every thread tracing through the BVH, so they are only kept when asked for
+#[derive(Debug, Default)]
+struct TraversalCounters {
+	rays_traced: AtomicU64,
+	nodes_visited: AtomicU64
+}
+
+pub struct Bvh {
+	nodes: Vec<BvhNode>,
+	primitive_indices: Vec<usize>,
+	depth: usize,
+	counters: Option<TraversalCounters>
+}
+
+impl Bvh {
+	pub fn build(primitive_bounds: &[Aabb]) -> Self {
+		Self::build_with_statistics(primitive_bounds, false)
+	}
+
+	// With `collect_statistics`, `hit` counts the rays and visited nodes for `statistics`
+	pub fn build_with_statistics(primitive_bounds: &[Aabb], collect_statistics: bool) -> Self {
+		let mut primitives: Vec<BuildPrimitive> = primitive_bounds.iter()
+			.enumerate()
+			.map(|(index, bounds)| BuildPrimitive {
+				index,
+				bounds: *bounds,
+				centroid: bounds.centroid()
+			})
+			.collect();
+
+		let mut bvh = Self {
+			nodes: Vec::with_capacity(primitives.len() * 2),
+			primitive_indices: Vec::with_capacity(primitives.len()),
+			depth: 0,
+			counters: collect_statistics.then(TraversalCounters::default)
+		};
+
+		if !primitives.is_empty() {
+			bvh.build_node(&mut primitives, 1);
+		}
+
+		bvh
+	}
+
+	pub fn bounds(&self) -> Aabb {
+		match self.nodes.first() {
+			None => Aabb::new(),
+			Some(root) => root.bounds
+		}
+	}
+
+	// Rays are only counted for BVHs built with statistics, the others report 0
+	pub fn statistics(&self) -> BvhStatistics {
+		let (rays_traced, nodes_visited) = match &self.counters {
+			None => (0, 0),
+			Some(counters) => (counters.rays_traced.load(Ordering::Relaxed), counters.nodes_visited.load(Ordering::Relaxed))
+		};
+
+		let average_nodes_visited = if rays_traced == 0 {
+			0.0
+		}
+		else {
+			nodes_visited as f64 / rays_traced as f64
+		};
+
+		BvhStatistics {
+			primitive_count: self.primitive_indices.len(),
+			node_count: self.nodes.len(),
+			leaf_count: self.nodes.iter().filter(|node| matches!(node.content, BvhNodeContent::Leaf { .. })).count(),
+			depth: self.depth,
+			rays_traced,
+			nodes_visited,
+			average_nodes_visited
+		}
+	}
+
+	pub fn reset_statistics(&self) {
+		if let Some(counters) = &self.counters {
+			counters.rays_traced.store(0, Ordering::Relaxed);
+			counters.nodes_visited.store(0, Ordering::Relaxed);
+		}
+	}
+
+	pub fn hit<F>(&self, ray: &Ray, t_min: f64, t_max: f64, hit_primitive: F) -> Option<HitRecord>
+		where F: Fn(usize, &Ray, f64, f64) -> Option<HitRecord> {
+		let mut nodes_visited = 0;
+
+		let hit_record = self.hit_counted(ray, t_min, t_max, &mut nodes_visited, hit_primitive);
+
+		if let Some(counters) = &self.counters {
+			counters.rays_traced.fetch_add(1, Ordering::Relaxed);
+			counters.nodes_visited.fetch_add(nodes_visited as u64, Ordering::Relaxed);
+		}
+
+		hit_record
+	}
+
+	pub fn hit_counted<F>(&self, ray: &Ray, t_min: f64, t_max: f64, nodes_visited: &mut usize, hit_primitive: F) -> Option<HitRecord>
+		where F: Fn(usize, &Ray, f64, f64) -> Option<HitRecord> {
+		if self.nodes.is_empty() {
+			return None;
+		}
+
+		let inverse_direction = Vec3::create(
+			1.0 / ray.direction.x,
+			1.0 / ray.direction.y,
+			1.0 / ray.direction.z
+		);
+
+		let mut hit_record = None;
+		let mut closest_t = t_max;
+
+		let mut stack = [0usize; MAX_DEPTH + 4];
+		let mut stack_size = 0;
+		let mut current = 0;
+
+		loop {
+			let node = &self.nodes[current];
+			*nodes_visited += 1;
+
+			if node.bounds.hit(ray.origin, inverse_direction, t_min, closest_t).is_some() {
+				match node.content {
+					BvhNodeContent::Leaf { first_primitive, primitive_count } => {
+						for index in &self.primitive_indices[first_primitive..first_primitive + primitive_count] {
+							if let Some(new_record) = hit_primitive(*index, ray, t_min, closest_t) {
+								closest_t = new_record.t;
+								hit_record = Some(new_record);
+							}
+						}
+					}
+					BvhNodeContent::Interior { second_child, axis } => {
+						if ray.direction[axis] < 0.0 {
+							stack[stack_size] = current + 1;
+							current = second_child;
+						}
+						else {
+							stack[stack_size] = second_child;
+							current += 1;
+						}
+						stack_size += 1;
+						continue;
+					}
+				}
+			}
+
+			if stack_size == 0 {
+				break;
+			}
+			stack_size -= 1;
+			current = stack[stack_size];
+		}
+
+		hit_record
+	}
+
+	fn build_node(&mut self, primitives: &mut [BuildPrimitive], depth: usize) -> usize {
+		self.depth = self.depth.max(depth);
+
+		let bounds = primitives.iter()
+			.fold(Aabb::new(), |bounds, primitive| Aabb::surrounding(bounds, primitive.bounds));
+
+		let node_index = self.nodes.len();
+
+		if primitives.len() == 1 || depth >= MAX_DEPTH {
+			return self.push_leaf(bounds, primitives);
+		}
+
+		let centroid_bounds = primitives.iter()
+			.fold(Aabb::new(), |bounds, primitive| bounds.extend(primitive.centroid));
+
+		let axis = centroid_bounds.extent().max_dimension();
+		let axis_minimum = centroid_bounds.minimum[axis];
+		let axis_extent = centroid_bounds.extent()[axis];
+
+		if axis_extent <= 0.0 {
+			return self.push_leaf(bounds, primitives);
+		}
+
+		let bin_of = |primitive: &BuildPrimitive| {
+			let relative = (primitive.centroid[axis] - axis_minimum) / axis_extent;
+			((relative * BIN_COUNT as f64) as usize).min(BIN_COUNT - 1)
+		};
+
+		let mut bin_bounds = [Aabb::new(); BIN_COUNT];
+		let mut bin_counts = [0usize; BIN_COUNT];
+
+		for primitive in primitives.iter() {
+			let bin = bin_of(primitive);
+			bin_bounds[bin] = Aabb::surrounding(bin_bounds[bin], primitive.bounds);
+			bin_counts[bin] += 1;
+		}
+
+		let mut right_areas = [0.0; BIN_COUNT];
+		let mut right_counts = [0usize; BIN_COUNT];
+		let mut accumulated_bounds = Aabb::new();
+		let mut accumulated_count = 0;
+
+		for bin in (1..BIN_COUNT).rev() {
+			accumulated_bounds = Aabb::surrounding(accumulated_bounds, bin_bounds[bin]);
+			accumulated_count += bin_counts[bin];
+			right_areas[bin] = accumulated_bounds.surface_area();
+			right_counts[bin] = accumulated_count;
+		}
+
+		let mut best_split = 0;
+		let mut best_cost = f64::INFINITY;
+		let mut accumulated_bounds = Aabb::new();
+		let mut accumulated_count = 0;
+
+		for split in 0..BIN_COUNT - 1 {
+			accumulated_bounds = Aabb::surrounding(accumulated_bounds, bin_bounds[split]);
+			accumulated_count += bin_counts[split];
+
+			if accumulated_count == 0 || right_counts[split + 1] == 0 {
+				continue;
+			}
+
+			let cost = accumulated_bounds.surface_area() * accumulated_count as f64
+				+ right_areas[split + 1] * right_counts[split + 1] as f64;
+
+			if cost < best_cost {
+				best_cost = cost;
+				best_split = split;
+			}
+		}
+
+		let parent_area = bounds.surface_area();
+		let split_cost = if parent_area > 0.0 {
+			TRAVERSAL_COST + INTERSECTION_COST * best_cost / parent_area
+		}
+		else {
+			f64::INFINITY
+		};
+		let leaf_cost = INTERSECTION_COST * primitives.len() as f64;
+
+		if primitives.len() <= MAX_LEAF_SIZE && leaf_cost <= split_cost {
+			return self.push_leaf(bounds, primitives);
+		}
+
+		let mut middle = partition(primitives, |primitive| bin_of(primitive) <= best_split);
+
+		if middle == 0 || middle == primitives.len() {
+			middle = primitives.len() / 2;
+			primitives.select_nth_unstable_by(middle, |lhs, rhs| {
+				lhs.centroid[axis].total_cmp(&rhs.centroid[axis])
+			});
+		}
+
+		self.nodes.push(BvhNode {
+			bounds,
+			content: BvhNodeContent::Interior {
+				second_child: 0,
+				axis
+			}
+		});
+
+		let (left, right) = primitives.split_at_mut(middle);
+		self.build_node(left, depth + 1);
+		let second_child = self.build_node(right, depth + 1);
+
+		self.nodes[node_index].content = BvhNodeContent::Interior {
+			second_child,
+			axis
+		};
+
+		node_index
+	}
+
+	fn push_leaf(&mut self, bounds: Aabb, primitives: &[BuildPrimitive]) -> usize {
+		let node_index = self.nodes.len();
+
+		self.nodes.push(BvhNode {
+			bounds,
+			content: BvhNodeContent::Leaf {
+				first_primitive: self.primitive_indices.len(),
+				primitive_count: primitives.len()
+			}
+		});
+
+		self.primitive_indices.extend(primitives.iter().map(|primitive| primitive.index));
+
+		node_index
+	}
+}
+
+fn partition<T, F: Fn(&T) -> bool>(values: &mut [T], predicate: F) -> usize {
+	let mut middle = 0;
+
+	for index in 0..values.len() {
+		if predicate(&values[index]) {
+			values.swap(index, middle);
+			middle += 1;
+		}
+	}
+
+	middle
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use crate::raytracing::color::Color;
+	use crate::raytracing::hittable::Hittable;
+	use crate::raytracing::hittable::sphere::Sphere;
+	use crate::raytracing::materials::lambertian::Lambertian;
+	use crate::raytracing::sampler::Pcg32;
+	use super::*;
+
+	fn random_vec3(rng: &mut Pcg32, min: f64, max: f64) -> Vec3 {
+		Vec3::create(rng.next_range(min, max), rng.next_range(min, max), rng.next_range(min, max))
+	}
+
+	fn random_spheres(rng: &mut Pcg32, count: usize) -> Vec<Sphere> {
+		let material = Arc::new(Lambertian::create(Color::white()));
+
+		(0..count)
+			.map(|_| Sphere::create(random_vec3(rng, -10.0, 10.0), rng.next_range(0.1, 1.5), material.clone()))
+			.collect()
+	}
+
+	fn brute_force(spheres: &[Sphere], ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+		let mut closest = None;
+		let mut closest_t = t_max;
+
+		for sphere in spheres {
+			if let Some(hit_record) = sphere.hit(ray, t_min, closest_t) {
+				closest_t = hit_record.t;
+				closest = Some(hit_record);
+			}
+		}
+
+		closest
+	}
+
+	#[test]
+	fn finds_the_same_hits_as_brute_force() {
+		let mut rng = Pcg32::create(7);
+		let spheres = random_spheres(&mut rng, 300);
+		let bounds: Vec<Aabb> = spheres.iter().map(|sphere| sphere.bounding_box()).collect();
+		let bvh = Bvh::build(&bounds);
+
+		for _ in 0..2000 {
+			let ray = Ray::create(random_vec3(&mut rng, -15.0, 15.0), random_vec3(&mut rng, -1.0, 1.0));
+
+			let expected = brute_force(&spheres, &ray, 0.001, f64::INFINITY);
+			let actual = bvh.hit(&ray, 0.001, f64::INFINITY, |index, ray, t_min, t_max| spheres[index].hit(ray, t_min, t_max));
+
+			assert_eq!(expected.map(|hit_record| hit_record.t), actual.map(|hit_record| hit_record.t));
+		}
+	}
+
+	#[test]
+	fn handles_coincident_centroids() {
+		let material = Arc::new(Lambertian::create(Color::white()));
+		let spheres: Vec<Sphere> = (1..=20)
+			.map(|radius| Sphere::create(Vec3::new(), radius as f64 * 0.1, material.clone()))
+			.collect();
+		let bounds: Vec<Aabb> = spheres.iter().map(|sphere| sphere.bounding_box()).collect();
+		let bvh = Bvh::build(&bounds);
+
+		let ray = Ray::create(Vec3::create(0.0, 0.0, -10.0), Vec3::create(0.0, 0.0, 1.0));
+		let hit_record = bvh.hit(&ray, 0.001, f64::INFINITY, |index, ray, t_min, t_max| spheres[index].hit(ray, t_min, t_max)).unwrap();
+
+		assert!((hit_record.t - 8.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn only_counts_rays_when_asked_to() {
+		let mut rng = Pcg32::create(3);
+		let spheres = random_spheres(&mut rng, 50);
+		let bounds: Vec<Aabb> = spheres.iter().map(|sphere| sphere.bounding_box()).collect();
+		let ray = Ray::create(Vec3::create(0.0, 0.0, -20.0), Vec3::create(0.0, 0.0, 1.0));
+
+		let bvh = Bvh::build(&bounds);
+		bvh.hit(&ray, 0.001, f64::INFINITY, |index, ray, t_min, t_max| spheres[index].hit(ray, t_min, t_max));
+		assert_eq!(bvh.statistics().rays_traced, 0);
+
+		let bvh = Bvh::build_with_statistics(&bounds, true);
+		bvh.hit(&ray, 0.001, f64::INFINITY, |index, ray, t_min, t_max| spheres[index].hit(ray, t_min, t_max));
+		assert_eq!(bvh.statistics().rays_traced, 1);
+		assert!(bvh.statistics().nodes_visited > 0);
+	}
+}
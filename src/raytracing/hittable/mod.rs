@@ -1,8 +1,64 @@
 pub mod sphere;
+pub mod triangle;
+pub mod triangle_mesh;
+pub mod bvh;
 
+use std::sync::Arc;
+use crate::raytracing::aabb::Aabb;
 use crate::raytracing::hit_record::HitRecord;
+use crate::raytracing::materials::Material;
 use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::ObjectDescription;
+use crate::raytracing::vector_3d::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SurfaceSample {
+	pub point: Vec3,
+	pub normal: Vec3,
+	pub pdf: f64
+}
 
 pub trait Hittable {
 	fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+	fn bounding_box(&self) -> Aabb;
+
+	// Like `hit`, also adds the BVH nodes visited on the way to `nodes_visited`
+	fn hit_counted(&self, ray: &Ray, t_min: f64, t_max: f64, _nodes_visited: &mut usize) -> Option<HitRecord> {
+		self.hit(ray, t_min, t_max)
+	}
+
+	fn is_emissive(&self) -> bool {
+		false
+	}
+
+	fn materials(&self) -> Vec<Arc<dyn Material + Send + Sync>> {
+		Vec::new()
+	}
+
+	// Samples a point on the surface as seen from `origin`, the pdf is with respect to solid angle at `origin`
+	fn sample_surface(&self, _origin: Vec3, _sampler: &mut dyn Sampler) -> Option<SurfaceSample> {
+		None
+	}
+
+	fn pdf_value(&self, _origin: Vec3, _direction: Vec3) -> f64 {
+		0.0
+	}
+
+	// How a scene file writes this hittable using the material called `material`, None if it can't
+	fn to_description(&self, _material: String) -> Option<ObjectDescription> {
+		None
+	}
+}
+
+pub fn solid_angle_pdf(origin: Vec3, point: Vec3, normal: Vec3, area_pdf: f64) -> f64 {
+	let to_point = point - origin;
+	let distance_squared = to_point.length_squared();
+	let cosine = Vec3::dot(normal, to_point).abs() / distance_squared.sqrt();
+
+	if cosine <= f64::EPSILON {
+		return 0.0;
+	}
+
+	area_pdf * distance_squared / cosine
 }
\ No newline at end of file
@@ -1,8 +1,13 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
+use crate::raytracing::aabb::Aabb;
 use crate::raytracing::hit_record::HitRecord;
-use crate::raytracing::hittable::Hittable;
+use crate::raytracing::hittable::{Hittable, solid_angle_pdf, SurfaceSample};
 use crate::raytracing::materials::Material;
 use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::{from_vec3, ObjectDescription};
+use crate::raytracing::vector_2d::Vec2;
 use crate::raytracing::vector_3d::Vec3;
 
 pub struct Sphere {
@@ -45,17 +50,138 @@ impl Hittable for Sphere {
 		}
 
 		let hit_point = ray.at(root);
+		let outward_normal = (hit_point - self.center) / self.radius;
 
 		let mut hit_record = HitRecord {
 			point: hit_point,
-			normal: (hit_point - self.center) / self.radius,
+			normal: outward_normal,
+			geometric_normal: outward_normal,
 			material: Some(self.material.clone()),
 			front_face: true,
-			t: root
+			t: root,
+			object_index: None,
+			uv: sphere_uv(outward_normal),
+			// v runs over half the circumference
+			uv_density: 1.0 / (PI * self.radius.abs()),
+			uv_footprint: 0.0
 		};
 
 		hit_record.set_face_normal(ray, hit_record.normal);
 
-		return Some(hit_record);
+		Some(hit_record)
 	}
-}
\ No newline at end of file
+
+	fn bounding_box(&self) -> Aabb {
+		let radius = Vec3::create(self.radius.abs(), self.radius.abs(), self.radius.abs());
+
+		Aabb::create(self.center - radius, self.center + radius)
+	}
+
+	fn is_emissive(&self) -> bool {
+		self.material.is_emissive()
+	}
+
+	fn materials(&self) -> Vec<Arc<dyn Material + Send + Sync>> {
+		vec![self.material.clone()]
+	}
+
+	fn sample_surface(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Option<SurfaceSample> {
+		let radius = self.radius.abs();
+		let to_center = self.center - origin;
+		let distance_squared = to_center.length_squared();
+
+		if distance_squared <= radius.powi(2) {
+			let normal = Vec3::random_normalized(sampler);
+			let point = self.center + normal * radius;
+			let area_pdf = 1.0 / (4.0 * PI * radius.powi(2));
+
+			return Some(SurfaceSample {
+				point,
+				normal,
+				pdf: solid_angle_pdf(origin, point, normal, area_pdf)
+			});
+		}
+
+		let (cos_theta_sample, phi_sample) = sampler.next_2d();
+
+		let cone_size = cone_size(radius, distance_squared);
+		let cos_theta = 1.0 - cos_theta_sample * cone_size;
+		let sin_theta = (1.0 - cos_theta.powi(2)).max(0.0).sqrt();
+		let phi = phi_sample * std::f64::consts::TAU;
+
+		let w = to_center / distance_squared.sqrt();
+		let (u, v) = w.orthonormal_basis();
+		let direction = u * (phi.cos() * sin_theta) + v * (phi.sin() * sin_theta) + w * cos_theta;
+
+		// Closest point on the ray if it only grazes the sphere due to rounding
+		let t = match self.hit(&Ray::create(origin, direction), 0.0, f64::INFINITY) {
+			None => Vec3::dot(to_center, direction),
+			Some(hit_record) => hit_record.t
+		};
+
+		let point = origin + direction * t;
+
+		Some(SurfaceSample {
+			point,
+			normal: (point - self.center).normalized(),
+			pdf: 1.0 / (2.0 * PI * cone_size)
+		})
+	}
+
+	fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+		let hit_record = match self.hit(&Ray::create(origin, direction), 0.001, f64::INFINITY) {
+			None => return 0.0,
+			Some(hit_record) => hit_record
+		};
+
+		let radius = self.radius.abs();
+		let distance_squared = (self.center - origin).length_squared();
+
+		if distance_squared <= radius.powi(2) {
+			let area_pdf = 1.0 / (4.0 * PI * radius.powi(2));
+			return solid_angle_pdf(origin, hit_record.point, hit_record.normal, area_pdf);
+		}
+
+		1.0 / (2.0 * PI * cone_size(radius, distance_squared))
+	}
+
+	fn to_description(&self, material: String) -> Option<ObjectDescription> {
+		Some(ObjectDescription::Sphere {
+			center: from_vec3(self.center),
+			radius: self.radius,
+			material
+		})
+	}
+}
+
+// 1 - cos of the angle the sphere covers as seen from outside, without cancelling to 0 for small or distant spheres
+fn cone_size(radius: f64, distance_squared: f64) -> f64 {
+	let theta_max = (radius / distance_squared.sqrt()).min(1.0).asin();
+
+	2.0 * (theta_max / 2.0).sin().powi(2)
+}
+
+// Longitude and latitude, u starts at -x and runs around +y, v goes from the bottom pole to the top one
+fn sphere_uv(normal: Vec3) -> Vec2 {
+	let theta = (-normal.y).clamp(-1.0, 1.0).acos();
+	let phi = (-normal.z).atan2(normal.x) + PI;
+
+	Vec2::create(phi / (2.0 * PI), theta / PI)
+}
+#[cfg(test)]
+mod tests {
+	use crate::raytracing::color::Color;
+	use crate::raytracing::materials::lambertian::Lambertian;
+	use super::*;
+
+	#[test]
+	fn small_distant_spheres_have_a_finite_pdf() {
+		let sphere = Sphere::create(Vec3::create(0.0, 0.0, 1e5), 1e-3, Arc::new(Lambertian::create(Color::white())));
+
+		let pdf = sphere.pdf_value(Vec3::new(), Vec3::create(0.0, 0.0, 1.0));
+		let solid_angle = PI * (1e-3f64 / 1e5).powi(2);
+
+		assert!(pdf.is_finite());
+		assert!((pdf * solid_angle - 1.0).abs() < 1e-6);
+	}
+}
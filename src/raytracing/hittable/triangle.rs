@@ -0,0 +1,329 @@
+use std::sync::Arc;
+use crate::raytracing::aabb::Aabb;
+use crate::raytracing::hit_record::HitRecord;
+use crate::raytracing::hittable::{Hittable, solid_angle_pdf, SurfaceSample};
+use crate::raytracing::hittable::triangle_mesh::MeshTriangle;
+use crate::raytracing::materials::Material;
+use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::{from_vec2, from_vec3, ObjectDescription};
+use crate::raytracing::vector_2d::Vec2;
+use crate::raytracing::vector_3d::Vec3;
+
+pub struct Triangle {
+	pub vertices: [Vec3; 3],
+	pub normals: Option<[Vec3; 3]>,
+	pub uvs: Option<[Vec2; 3]>,
+	pub material: Arc<dyn Material + Send + Sync>
+}
+
+impl Triangle {
+	pub fn create(vertices: [Vec3; 3], material: Arc<dyn Material + Send + Sync>) -> Self {
+		Self {
+			vertices,
+			normals: None,
+			uvs: None,
+			material
+		}
+	}
+
+	pub fn create_with_attributes(vertices: [Vec3; 3], normals: Option<[Vec3; 3]>, uvs: Option<[Vec2; 3]>, material: Arc<dyn Material + Send + Sync>) -> Self {
+		Self {
+			vertices,
+			normals,
+			uvs,
+			material
+		}
+	}
+}
+
+impl Hittable for Triangle {
+	fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+		let [p0, p1, p2] = self.vertices;
+		let intersection = intersect_triangle(ray, p0, p1, p2, t_min, t_max)?;
+
+		Some(intersection.to_hit_record(ray, self.vertices, self.normals, self.uvs, self.material.clone()))
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		Aabb::from_points(&self.vertices).padded(TRIANGLE_BOUNDS_PADDING)
+	}
+
+	fn is_emissive(&self) -> bool {
+		self.material.is_emissive()
+	}
+
+	fn materials(&self) -> Vec<Arc<dyn Material + Send + Sync>> {
+		vec![self.material.clone()]
+	}
+
+	fn sample_surface(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Option<SurfaceSample> {
+		let area = triangle_area(self.vertices);
+		if area <= 0.0 {
+			return None;
+		}
+
+		let (point, normal) = sample_triangle(self.vertices, sampler);
+		let pdf = solid_angle_pdf(origin, point, normal, 1.0 / area);
+
+		if pdf <= 0.0 {
+			return None;
+		}
+
+		Some(SurfaceSample {
+			point,
+			normal,
+			pdf
+		})
+	}
+
+	fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+		let [p0, p1, p2] = self.vertices;
+
+		match intersect_triangle(&Ray::create(origin, direction), p0, p1, p2, 0.001, f64::INFINITY) {
+			None => 0.0,
+			Some(intersection) => {
+				let normal = Vec3::cross(p1 - p0, p2 - p0).normalized();
+				solid_angle_pdf(origin, intersection.interpolate(self.vertices), normal, 1.0 / triangle_area(self.vertices))
+			}
+		}
+	}
+
+	fn to_description(&self, material: String) -> Option<ObjectDescription> {
+		if self.normals.is_none() && self.uvs.is_none() {
+			return Some(ObjectDescription::Triangle {
+				vertices: self.vertices.map(from_vec3),
+				material
+			});
+		}
+
+		// Only meshes have room for the normals and UVs
+		Some(ObjectDescription::Mesh {
+			vertices: self.vertices.map(from_vec3).to_vec(),
+			normals: self.normals.map(|normals| normals.map(from_vec3).to_vec()).unwrap_or_default(),
+			uvs: self.uvs.map(|uvs| uvs.map(from_vec2).to_vec()).unwrap_or_default(),
+			triangles: vec![MeshTriangle {
+				vertices: [0, 1, 2],
+				normals: self.normals.map(|_| [0, 1, 2]),
+				uvs: self.uvs.map(|_| [0, 1, 2])
+			}],
+			material
+		})
+	}
+}
+
+pub fn triangle_area(vertices: [Vec3; 3]) -> f64 {
+	let [p0, p1, p2] = vertices;
+
+	Vec3::cross(p1 - p0, p2 - p0).length() * 0.5
+}
+
+pub fn sample_triangle(vertices: [Vec3; 3], sampler: &mut dyn Sampler) -> (Vec3, Vec3) {
+	let [p0, p1, p2] = vertices;
+	let (first, second) = sampler.next_2d();
+
+	let root = first.sqrt();
+	let b1 = second * root;
+	let b0 = 1.0 - root;
+
+	let point = p0 * b0 + p1 * b1 + p2 * (1.0 - b0 - b1);
+	let normal = Vec3::cross(p1 - p0, p2 - p0).normalized();
+
+	(point, normal)
+}
+
+pub const TRIANGLE_BOUNDS_PADDING: f64 = 1e-8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TriangleIntersection {
+	pub t: f64,
+	pub barycentric: [f64; 3]
+}
+
+impl TriangleIntersection {
+	pub fn interpolate(&self, values: [Vec3; 3]) -> Vec3 {
+		values[0] * self.barycentric[0] + values[1] * self.barycentric[1] + values[2] * self.barycentric[2]
+	}
+
+	pub fn interpolate_2d(&self, values: [Vec2; 3]) -> Vec2 {
+		values[0] * self.barycentric[0] + values[1] * self.barycentric[1] + values[2] * self.barycentric[2]
+	}
+
+	// Triangles without UVs use the barycentric coordinates of the second and third vertex instead
+	pub fn to_hit_record(&self, ray: &Ray, vertices: [Vec3; 3], normals: Option<[Vec3; 3]>, uvs: Option<[Vec2; 3]>, material: Arc<dyn Material + Send + Sync>) -> HitRecord {
+		let [p0, p1, p2] = vertices;
+		let geometric_normal = Vec3::cross(p1 - p0, p2 - p0).normalized();
+
+		let uvs = uvs.unwrap_or([Vec2::create(0.0, 0.0), Vec2::create(1.0, 0.0), Vec2::create(0.0, 1.0)]);
+		let [uv0, uv1, uv2] = uvs;
+		let uv_edge_1 = uv1 - uv0;
+		let uv_edge_2 = uv2 - uv0;
+		let uv_area = (uv_edge_1.x * uv_edge_2.y - uv_edge_1.y * uv_edge_2.x).abs() * 0.5;
+		let area = triangle_area(vertices);
+
+		let uv_density = if area > 0.0 {
+			(uv_area / area).sqrt()
+		}
+		else {
+			0.0
+		};
+
+		let shading_normal = match normals {
+			None => geometric_normal,
+			Some(normals) => {
+				let interpolated = self.interpolate(normals);
+				let length = interpolated.length();
+
+				if length > 0.0 {
+					interpolated / length
+				}
+				else {
+					geometric_normal
+				}
+			}
+		};
+
+		let mut hit_record = HitRecord {
+			point: ray.at(self.t),
+			normal: shading_normal,
+			geometric_normal,
+			material: Some(material),
+			front_face: true,
+			t: self.t,
+			object_index: None,
+			uv: self.interpolate_2d(uvs),
+			uv_density,
+			uv_footprint: 0.0
+		};
+
+		hit_record.set_face_normals(ray, geometric_normal, shading_normal);
+
+		hit_record
+	}
+}
+
+// Watertight ray/triangle intersection after Woop, Benthin and Wald (2013).
+// Vertices are transformed into a ray space where the ray runs along +z through the origin,
+// so shared edges are evaluated identically for both neighbouring triangles.
+pub fn intersect_triangle(ray: &Ray, p0: Vec3, p1: Vec3, p2: Vec3, t_min: f64, t_max: f64) -> Option<TriangleIntersection> {
+	let direction = ray.direction;
+
+	let kz = direction.abs().max_dimension();
+	let mut kx = (kz + 1) % 3;
+	let mut ky = (kx + 1) % 3;
+
+	if direction[kz] < 0.0 {
+		std::mem::swap(&mut kx, &mut ky);
+	}
+
+	let shear_x = direction[kx] / direction[kz];
+	let shear_y = direction[ky] / direction[kz];
+	let shear_z = 1.0 / direction[kz];
+
+	let a = p0 - ray.origin;
+	let b = p1 - ray.origin;
+	let c = p2 - ray.origin;
+
+	let ax = a[kx] - shear_x * a[kz];
+	let ay = a[ky] - shear_y * a[kz];
+	let bx = b[kx] - shear_x * b[kz];
+	let by = b[ky] - shear_y * b[kz];
+	let cx = c[kx] - shear_x * c[kz];
+	let cy = c[ky] - shear_y * c[kz];
+
+	let u = cx * by - cy * bx;
+	let v = ax * cy - ay * cx;
+	let w = bx * ay - by * ax;
+
+	if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+		return None;
+	}
+
+	let determinant = u + v + w;
+	if determinant == 0.0 {
+		return None;
+	}
+
+	let az = shear_z * a[kz];
+	let bz = shear_z * b[kz];
+	let cz = shear_z * c[kz];
+
+	let inverse_determinant = 1.0 / determinant;
+	let t = (u * az + v * bz + w * cz) * inverse_determinant;
+
+	if t < t_min || t_max < t {
+		return None;
+	}
+
+	Some(TriangleIntersection {
+		t,
+		barycentric: [u * inverse_determinant, v * inverse_determinant, w * inverse_determinant]
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const P0: Vec3 = Vec3 { x: -1.0, y: -1.0, z: 0.0 };
+	const P1: Vec3 = Vec3 { x: 1.0, y: -1.0, z: 0.0 };
+	const P2: Vec3 = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+
+	fn ray_towards(x: f64, y: f64) -> Ray {
+		Ray::create(Vec3::create(x, y, 5.0), Vec3::create(0.0, 0.0, -1.0))
+	}
+
+	#[test]
+	fn hits_inside_with_matching_barycentrics() {
+		let intersection = intersect_triangle(&ray_towards(0.1, -0.2), P0, P1, P2, 0.001, f64::INFINITY).unwrap();
+
+		assert!((intersection.t - 5.0).abs() < 1e-12);
+
+		let [b0, b1, b2] = intersection.barycentric;
+		assert!((b0 + b1 + b2 - 1.0).abs() < 1e-12);
+
+		let point = P0 * b0 + P1 * b1 + P2 * b2;
+		assert!((point.x - 0.1).abs() < 1e-12);
+		assert!((point.y + 0.2).abs() < 1e-12);
+	}
+
+	#[test]
+	fn misses_outside() {
+		assert!(intersect_triangle(&ray_towards(0.9, 0.9), P0, P1, P2, 0.001, f64::INFINITY).is_none());
+		assert!(intersect_triangle(&ray_towards(0.0, -1.5), P0, P1, P2, 0.001, f64::INFINITY).is_none());
+	}
+
+	#[test]
+	fn hits_both_windings() {
+		assert!(intersect_triangle(&ray_towards(0.0, 0.0), P0, P2, P1, 0.001, f64::INFINITY).is_some());
+	}
+
+	#[test]
+	fn respects_t_range() {
+		assert!(intersect_triangle(&ray_towards(0.0, 0.0), P0, P1, P2, 0.001, 4.0).is_none());
+		assert!(intersect_triangle(&ray_towards(0.0, 0.0), P0, P1, P2, 6.0, f64::INFINITY).is_none());
+	}
+
+	#[test]
+	fn parallel_rays_miss() {
+		let ray = Ray::create(Vec3::create(-5.0, 0.0, 0.0), Vec3::create(1.0, 0.0, 0.0));
+		assert!(intersect_triangle(&ray, P0, P1, P2, 0.001, f64::INFINITY).is_none());
+	}
+
+	#[test]
+	fn shared_edges_are_watertight() {
+		// Two triangles sharing the diagonal from P1 to P3, rays along it have to hit at least one of them
+		const P3: Vec3 = Vec3 { x: -1.0, y: 1.0, z: 0.0 };
+
+		for i in 1..100 {
+			let s = i as f64 / 100.0;
+			let on_edge = P1 * (1.0 - s) + P3 * s;
+
+			let ray = Ray::create(on_edge + Vec3::create(0.3, 0.7, 5.0), Vec3::create(-0.3, -0.7, -5.0));
+			let first = intersect_triangle(&ray, P0, P1, P3, 0.001, f64::INFINITY);
+			let second = intersect_triangle(&ray, P1, Vec3::create(1.0, 1.0, 0.0), P3, 0.001, f64::INFINITY);
+
+			assert!(first.is_some() || second.is_some(), "ray through the shared edge at {} missed both triangles", s);
+		}
+	}
+}
@@ -0,0 +1,211 @@
+use std::sync::Arc;
+use serde_derive::{Serialize, Deserialize};
+use crate::raytracing::aabb::Aabb;
+use crate::raytracing::hit_record::HitRecord;
+use crate::raytracing::hittable::bvh::Bvh;
+use crate::raytracing::hittable::{Hittable, solid_angle_pdf, SurfaceSample};
+use crate::raytracing::hittable::triangle::{intersect_triangle, sample_triangle, triangle_area, TRIANGLE_BOUNDS_PADDING};
+use crate::raytracing::materials::Material;
+use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::{from_vec2, from_vec3, ObjectDescription};
+use crate::raytracing::vector_2d::Vec2;
+use crate::raytracing::vector_3d::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeshTriangle {
+	pub vertices: [usize; 3],
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub normals: Option<[usize; 3]>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub uvs: Option<[usize; 3]>
+}
+
+impl MeshTriangle {
+	pub fn create(vertices: [usize; 3]) -> Self {
+		Self {
+			vertices,
+			normals: None,
+			uvs: None
+		}
+	}
+}
+
+pub struct TriangleMesh {
+	vertices: Vec<Vec3>,
+	normals: Vec<Vec3>,
+	uvs: Vec<Vec2>,
+	triangles: Vec<MeshTriangle>,
+	material: Arc<dyn Material + Send + Sync>,
+	bvh: Bvh,
+	cumulative_areas: Vec<f64>
+}
+
+impl TriangleMesh {
+	pub fn create(vertices: Vec<Vec3>, normals: Vec<Vec3>, uvs: Vec<Vec2>, triangles: Vec<MeshTriangle>, material: Arc<dyn Material + Send + Sync>) -> Self {
+		let triangle_bounds: Vec<Aabb> = triangles.iter()
+			.map(|triangle| {
+				let [i0, i1, i2] = triangle.vertices;
+				Aabb::from_points(&[vertices[i0], vertices[i1], vertices[i2]]).padded(TRIANGLE_BOUNDS_PADDING)
+			})
+			.collect();
+
+		let bvh = Bvh::build(&triangle_bounds);
+
+		let mut total_area = 0.0;
+		let cumulative_areas = triangles.iter()
+			.map(|triangle| {
+				let [i0, i1, i2] = triangle.vertices;
+				total_area += triangle_area([vertices[i0], vertices[i1], vertices[i2]]);
+				total_area
+			})
+			.collect();
+
+		Self {
+			vertices,
+			normals,
+			uvs,
+			triangles,
+			material,
+			bvh,
+			cumulative_areas
+		}
+	}
+
+	pub fn surface_area(&self) -> f64 {
+		self.cumulative_areas.last().copied().unwrap_or(0.0)
+	}
+
+	pub fn vertices(&self) -> &[Vec3] {
+		&self.vertices
+	}
+
+	pub fn normals(&self) -> &[Vec3] {
+		&self.normals
+	}
+
+	pub fn uvs(&self) -> &[Vec2] {
+		&self.uvs
+	}
+
+	pub fn triangles(&self) -> &[MeshTriangle] {
+		&self.triangles
+	}
+
+	pub fn material(&self) -> &Arc<dyn Material + Send + Sync> {
+		&self.material
+	}
+
+	pub fn set_material(&mut self, material: Arc<dyn Material + Send + Sync>) {
+		self.material = material;
+	}
+
+	pub fn bvh(&self) -> &Bvh {
+		&self.bvh
+	}
+
+	pub fn triangle_count(&self) -> usize {
+		self.triangles.len()
+	}
+
+	pub fn triangle_vertices(&self, index: usize) -> [Vec3; 3] {
+		let [i0, i1, i2] = self.triangles[index].vertices;
+
+		[self.vertices[i0], self.vertices[i1], self.vertices[i2]]
+	}
+
+	pub fn triangle_normals(&self, index: usize) -> Option<[Vec3; 3]> {
+		let [i0, i1, i2] = self.triangles[index].normals?;
+
+		Some([self.normals[i0], self.normals[i1], self.normals[i2]])
+	}
+
+	pub fn triangle_uvs(&self, index: usize) -> Option<[Vec2; 3]> {
+		let [i0, i1, i2] = self.triangles[index].uvs?;
+
+		Some([self.uvs[i0], self.uvs[i1], self.uvs[i2]])
+	}
+
+	pub fn hit_triangle(&self, index: usize, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+		let vertices = self.triangle_vertices(index);
+		let [p0, p1, p2] = vertices;
+
+		let intersection = intersect_triangle(ray, p0, p1, p2, t_min, t_max)?;
+
+		Some(intersection.to_hit_record(ray, vertices, self.triangle_normals(index), self.triangle_uvs(index), self.material.clone()))
+	}
+}
+
+impl Hittable for TriangleMesh {
+	fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+		self.bvh.hit(ray, t_min, t_max, |index, ray, t_min, t_max| {
+			self.hit_triangle(index, ray, t_min, t_max)
+		})
+	}
+
+	fn bounding_box(&self) -> Aabb {
+		self.bvh.bounds()
+	}
+
+	fn hit_counted(&self, ray: &Ray, t_min: f64, t_max: f64, nodes_visited: &mut usize) -> Option<HitRecord> {
+		self.bvh.hit_counted(ray, t_min, t_max, nodes_visited, |index, ray, t_min, t_max| {
+			self.hit_triangle(index, ray, t_min, t_max)
+		})
+	}
+
+	fn is_emissive(&self) -> bool {
+		self.material.is_emissive()
+	}
+
+	fn materials(&self) -> Vec<Arc<dyn Material + Send + Sync>> {
+		vec![self.material.clone()]
+	}
+
+	fn sample_surface(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Option<SurfaceSample> {
+		let total_area = self.surface_area();
+		if total_area <= 0.0 {
+			return None;
+		}
+
+		let target = sampler.next_range(0.0, total_area);
+		let index = self.cumulative_areas.partition_point(|area| *area <= target)
+			.min(self.triangles.len() - 1);
+
+		let (point, normal) = sample_triangle(self.triangle_vertices(index), sampler);
+		let pdf = solid_angle_pdf(origin, point, normal, 1.0 / total_area);
+
+		if pdf <= 0.0 {
+			return None;
+		}
+
+		Some(SurfaceSample {
+			point,
+			normal,
+			pdf
+		})
+	}
+
+	fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+		let total_area = self.surface_area();
+		if total_area <= 0.0 {
+			return 0.0;
+		}
+
+		match self.hit(&Ray::create(origin, direction), 0.001, f64::INFINITY) {
+			None => 0.0,
+			Some(hit_record) => {
+				solid_angle_pdf(origin, hit_record.point, hit_record.geometric_normal, 1.0 / total_area)
+			}
+		}
+	}
+
+	fn to_description(&self, material: String) -> Option<ObjectDescription> {
+		Some(ObjectDescription::Mesh {
+			vertices: self.vertices.iter().copied().map(from_vec3).collect(),
+			normals: self.normals.iter().copied().map(from_vec3).collect(),
+			uvs: self.uvs.iter().copied().map(from_vec2).collect(),
+			triangles: self.triangles.clone(),
+			material
+		})
+	}
+}
@@ -0,0 +1,179 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde_derive::{Serialize, Deserialize};
+use crate::raytracing::color::Color;
+
+// What every camera sample computes, everything besides the path tracer is meant for finding out why a scene looks wrong.
+// The debug integrators write displayable values, they still go through exposure and tone mapping.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Integrator {
+	PathTracer,
+	// Shading normal at the first hit, mapped from [-1, 1] to [0, 1]
+	Normal,
+	// Distance to the first hit, black at the camera and white at `max_depth` or further
+	Depth {
+		#[serde(default = "default_max_depth")]
+		max_depth: f64
+	},
+	Albedo,
+	// Texture coordinates at the first hit in red and green
+	Uv,
+	// Heatmap of the bounces paths made before they ended, red is `max_bounces`
+	BounceCount,
+	// False color per material, black for the background
+	MaterialId,
+	// Heatmap of the BVH nodes the camera ray visited, including the ones of meshes, red is `max_nodes` or more
+	BvhCost {
+		max_nodes: usize
+	},
+	// How much of the hemisphere above the first hit is open, occluders further away than `radius` don't count
+	AmbientOcclusion {
+		radius: f64
+	}
+}
+
+fn default_max_depth() -> f64 {
+	10.0
+}
+
+impl Integrator {
+	pub fn new() -> Self {
+		Integrator::PathTracer
+	}
+
+	pub fn is_path_tracer(&self) -> bool {
+		matches!(self, Integrator::PathTracer)
+	}
+}
+
+impl Default for Integrator {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// Integrator name with an optional parameter, e.g. "normal" or "ambient_occlusion,0.5"
+impl FromStr for Integrator {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let (name, parameter) = match value.split_once(',') {
+			None => (value.trim(), None),
+			Some((name, parameter)) => (name.trim(), Some(parameter.trim()))
+		};
+
+		let integrator = match name {
+			"path_tracer" => Integrator::PathTracer,
+			"normal" => Integrator::Normal,
+			"depth" => {
+				let max_depth = match parameter {
+					None => default_max_depth(),
+					Some(parameter) => match parameter.parse::<f64>() {
+						Ok(max_depth) if max_depth > 0.0 && !max_depth.is_nan() => max_depth,
+						_ => return Err(format!("invalid maximum depth '{}'", parameter))
+					}
+				};
+
+				return Ok(Integrator::Depth { max_depth });
+			}
+			"albedo" => Integrator::Albedo,
+			"uv" => Integrator::Uv,
+			"bounce_count" => Integrator::BounceCount,
+			"material_id" => Integrator::MaterialId,
+			"bvh_cost" => {
+				let max_nodes = match parameter {
+					None => 100,
+					Some(parameter) => match parameter.parse::<usize>() {
+						Ok(max_nodes) if max_nodes > 0 => max_nodes,
+						_ => return Err(format!("invalid maximum node count '{}'", parameter))
+					}
+				};
+
+				return Ok(Integrator::BvhCost { max_nodes });
+			}
+			"ambient_occlusion" => {
+				let radius = match parameter {
+					None => 1.0,
+					Some(parameter) => match parameter.parse::<f64>() {
+						Ok(radius) if radius > 0.0 && !radius.is_nan() => radius,
+						_ => return Err(format!("invalid ambient occlusion radius '{}'", parameter))
+					}
+				};
+
+				return Ok(Integrator::AmbientOcclusion { radius });
+			}
+			other => return Err(format!("unknown integrator '{}'", other))
+		};
+
+		match parameter {
+			None => Ok(integrator),
+			Some(parameter) => Err(format!("integrator '{}' takes no parameter, got '{}'", name, parameter))
+		}
+	}
+}
+
+impl Display for Integrator {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Integrator::PathTracer => write!(f, "path_tracer"),
+			Integrator::Normal => write!(f, "normal"),
+			Integrator::Depth { max_depth } => write!(f, "depth,{}", max_depth),
+			Integrator::Albedo => write!(f, "albedo"),
+			Integrator::Uv => write!(f, "uv"),
+			Integrator::BounceCount => write!(f, "bounce_count"),
+			Integrator::MaterialId => write!(f, "material_id"),
+			Integrator::BvhCost { max_nodes } => write!(f, "bvh_cost,{}", max_nodes),
+			Integrator::AmbientOcclusion { radius } => write!(f, "ambient_occlusion,{}", radius)
+		}
+	}
+}
+
+// Blue for 0 over cyan, green and yellow to red for 1 and above
+pub fn heatmap(value: f64) -> Color {
+	const STOPS: [[f32; 3]; 5] = [[0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0]];
+
+	let position = value.clamp(0.0, 1.0) as f32 * (STOPS.len() - 1) as f32;
+	let index = (position.floor() as usize).min(STOPS.len() - 2);
+	let t = position - index as f32;
+
+	let [r, g, b] = [0, 1, 2].map(|channel| STOPS[index][channel] + (STOPS[index + 1][channel] - STOPS[index][channel]) * t);
+
+	Color::create(r, g, b, 1.0)
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const INTEGRATORS: [Integrator; 9] = [
+		Integrator::PathTracer,
+		Integrator::Normal,
+		Integrator::Depth { max_depth: 250.5 },
+		Integrator::Albedo,
+		Integrator::Uv,
+		Integrator::BounceCount,
+		Integrator::MaterialId,
+		Integrator::BvhCost { max_nodes: 40 },
+		Integrator::AmbientOcclusion { radius: 0.25 }
+	];
+
+	#[test]
+	fn integrators_round_trip() {
+		for integrator in INTEGRATORS {
+			assert_eq!(integrator.to_string().parse::<Integrator>(), Ok(integrator));
+
+			let json = serde_json::to_string(&integrator).unwrap();
+			assert_eq!(serde_json::from_str::<Integrator>(&json).unwrap(), integrator, "{}", json);
+		}
+	}
+
+	#[test]
+	fn parameters_are_optional_and_checked() {
+		assert_eq!("depth".parse::<Integrator>(), Ok(Integrator::Depth { max_depth: default_max_depth() }));
+		assert_eq!(serde_json::from_str::<Integrator>(r#"{ "type": "depth" }"#).unwrap(), Integrator::Depth { max_depth: default_max_depth() });
+
+		for invalid in ["depth,0", "depth,-2", "depth,NaN", "bvh_cost,0", "ambient_occlusion,0", "normal,1", "path"] {
+			assert!(invalid.parse::<Integrator>().is_err(), "{}", invalid);
+		}
+	}
+}
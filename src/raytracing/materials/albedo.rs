@@ -0,0 +1,33 @@
+use std::sync::Arc;
+use crate::raytracing::color::Color;
+use crate::raytracing::hit_record::HitRecord;
+use crate::raytracing::texture_sampling::{MipMap, TextureFilter, TextureSampling};
+
+// Reflectance of a material, either the same everywhere or looked up by the hit's UV coordinates
+#[derive(Clone)]
+pub enum Albedo {
+	Color(Color),
+	Texture {
+		texture: Arc<MipMap>,
+		filter: TextureFilter
+	}
+}
+
+impl Albedo {
+	pub fn value(&self, hit_record: &HitRecord) -> Color {
+		match self {
+			Albedo::Color(color) => *color,
+			Albedo::Texture { texture, filter } => {
+				let color = texture.sample(hit_record.uv, hit_record.uv_footprint, *filter);
+
+				Color::create(color.r, color.g, color.b, 1.0)
+			}
+		}
+	}
+}
+
+impl From<Color> for Albedo {
+	fn from(color: Color) -> Self {
+		Albedo::Color(color)
+	}
+}
@@ -1,9 +1,10 @@
-use rand::{Rng, thread_rng};
 use crate::raytracing::color::Color;
 use crate::raytracing::hit_record::HitRecord;
 use crate::raytracing::materials::{Material};
 use crate::raytracing::materials::util::{reflect, reflectance, refract};
 use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::MaterialDescription;
 use crate::raytracing::vector_3d::Vec3;
 
 pub struct Dielectric {
@@ -24,8 +25,14 @@ impl Dielectric {
 	}
 }
 
+impl Default for Dielectric {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl Material for Dielectric {
-	fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)> {
+	fn scatter(&self, ray: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<(Color, Ray)> {
 		let refraction_ratio = if hit_record.front_face {
 			1.0 / self.index_of_refraction
 		}
@@ -40,9 +47,7 @@ impl Material for Dielectric {
 
 		let cannot_refract = refraction_ratio * sin_theta > 1.0;
 
-		let mut rand = thread_rng();
-
-		let direction = if cannot_refract || reflectance(cos_theta, refraction_ratio) > rand.gen_range(0.0..1.0) {
+		let direction = if cannot_refract || reflectance(cos_theta, refraction_ratio) > sampler.next_1d() {
 			reflect(unit_direction, hit_record.normal)
 		}
 		else {
@@ -57,4 +62,10 @@ impl Material for Dielectric {
 
 		Some((attenuation, ray))
 	}
+
+	fn to_description(&self) -> Option<MaterialDescription> {
+		Some(MaterialDescription::Dielectric {
+			index_of_refraction: self.index_of_refraction
+		})
+	}
 }
\ No newline at end of file
@@ -0,0 +1,66 @@
+use crate::raytracing::color::Color;
+use crate::raytracing::hit_record::HitRecord;
+use crate::raytracing::materials::Material;
+use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::{from_color, MaterialDescription};
+
+pub struct DiffuseLight {
+	pub emit: Color,
+	pub two_sided: bool
+}
+
+impl DiffuseLight {
+	pub fn new() -> Self {
+		Self {
+			emit: Color::white(),
+			two_sided: true
+		}
+	}
+
+	pub fn create(emit: Color) -> Self {
+		Self {
+			emit,
+			two_sided: true
+		}
+	}
+
+	pub fn create_one_sided(emit: Color) -> Self {
+		Self {
+			emit,
+			two_sided: false
+		}
+	}
+}
+
+impl Default for DiffuseLight {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Material for DiffuseLight {
+	fn scatter(&self, _ray: &Ray, _hit_record: &HitRecord, _sampler: &mut dyn Sampler) -> Option<(Color, Ray)> {
+		None
+	}
+
+	fn emitted(&self, _ray: &Ray, hit_record: &HitRecord) -> Color {
+		if self.two_sided || hit_record.front_face {
+			self.emit
+		}
+		else {
+			Color::black()
+		}
+	}
+
+	fn is_emissive(&self) -> bool {
+		!self.emit.is_black()
+	}
+
+	fn to_description(&self) -> Option<MaterialDescription> {
+		Some(MaterialDescription::DiffuseLight {
+			emit: from_color(self.emit),
+			two_sided: self.two_sided
+		})
+	}
+}
\ No newline at end of file
@@ -1,37 +1,88 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
 use crate::raytracing::color::Color;
 use crate::raytracing::hit_record::HitRecord;
+use crate::raytracing::materials::albedo::Albedo;
 use crate::raytracing::materials::Material;
 use crate::raytracing::NEAR_ZERO_THRESHOLD;
 use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::{from_color, MaterialDescription};
+use crate::raytracing::texture_sampling::{MipMap, TextureFilter};
 use crate::raytracing::vector_3d::Vec3;
 
 pub struct Lambertian {
-	pub albedo: Color
+	pub albedo: Albedo
 }
 
 impl Lambertian {
 	pub fn new() -> Self {
 		Self {
-			albedo: Color::new()
+			albedo: Albedo::Color(Color::new())
 		}
 	}
 
 	pub fn create(albedo: Color) -> Self {
 		Self {
-			albedo
+			albedo: Albedo::Color(albedo)
 		}
 	}
+
+	pub fn create_textured(texture: Arc<MipMap>, filter: TextureFilter) -> Self {
+		Self {
+			albedo: Albedo::Texture {
+				texture,
+				filter
+			}
+		}
+	}
+}
+
+impl Default for Lambertian {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Material for Lambertian {
-	fn scatter(&self, _ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)> {
-		let mut scatter_direction = hit_record.normal + Vec3::random_normalized();
+	fn scatter(&self, _ray: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<(Color, Ray)> {
+		let mut scatter_direction = hit_record.normal + Vec3::random_normalized(sampler);
 
 		if scatter_direction.near_zero(NEAR_ZERO_THRESHOLD) {
 			scatter_direction = hit_record.normal;
 		}
 
 		let scattered = Ray::create(hit_record.point, scatter_direction);
-		Some((self.albedo, scattered))
+		Some((self.albedo.value(hit_record), scattered))
+	}
+
+	fn evaluate(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+		let pdf = self.pdf(ray, hit_record, direction);
+
+		self.albedo.value(hit_record) * pdf as f32
+	}
+
+	fn pdf(&self, _ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+		let cosine = Vec3::dot(hit_record.normal, direction.normalized());
+
+		cosine.max(0.0) / PI
+	}
+
+	fn is_specular(&self) -> bool {
+		false
+	}
+
+	fn albedo(&self, hit_record: &HitRecord) -> Color {
+		self.albedo.value(hit_record)
+	}
+
+	fn to_description(&self) -> Option<MaterialDescription> {
+		match &self.albedo {
+			Albedo::Color(albedo) => Some(MaterialDescription::Lambertian {
+				albedo: from_color(*albedo),
+				texture: None
+			}),
+			Albedo::Texture { .. } => None
+		}
 	}
 }
\ No newline at end of file
@@ -1,42 +1,78 @@
+use std::sync::Arc;
 use crate::raytracing::color::Color;
 use crate::raytracing::hit_record::HitRecord;
+use crate::raytracing::materials::albedo::Albedo;
 use crate::raytracing::materials::{Material, util};
 use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::{from_color, MaterialDescription};
+use crate::raytracing::texture_sampling::{MipMap, TextureFilter};
 use crate::raytracing::vector_3d::Vec3;
 
 pub struct Metal {
-	albedo: Color,
+	albedo: Albedo,
 	fuzz: f64
 }
 
 impl Metal {
 	pub fn new() -> Self {
 		Self {
-			albedo: Color::new(),
+			albedo: Albedo::Color(Color::new()),
 			fuzz: 0.0
 		}
 	}
 
 	pub fn create(albedo: Color, fuzz: f64) -> Self {
 		Self {
-			albedo,
+			albedo: Albedo::Color(albedo),
+			fuzz
+		}
+	}
+
+	pub fn create_textured(texture: Arc<MipMap>, filter: TextureFilter, fuzz: f64) -> Self {
+		Self {
+			albedo: Albedo::Texture {
+				texture,
+				filter
+			},
 			fuzz
 		}
 	}
 }
 
+impl Default for Metal {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl Material for Metal {
-	fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)> {
+	fn scatter(&self, ray: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<(Color, Ray)> {
 		let reflected = util::reflect(ray.direction.normalized(), hit_record.normal);
 		let scattered = Ray {
 			origin: hit_record.point,
-			direction: reflected + Vec3::random_in_unit_sphere() * self.fuzz
+			direction: reflected + Vec3::random_in_unit_sphere(sampler) * self.fuzz
 		};
 
 		if Vec3::dot(scattered.direction, hit_record.normal) < 0.0 {
 			return None;
 		}
 
-		Some((self.albedo, scattered))
+		Some((self.albedo.value(hit_record), scattered))
+	}
+
+	fn albedo(&self, hit_record: &HitRecord) -> Color {
+		self.albedo.value(hit_record)
+	}
+
+	fn to_description(&self) -> Option<MaterialDescription> {
+		match &self.albedo {
+			Albedo::Color(albedo) => Some(MaterialDescription::Metal {
+				albedo: from_color(*albedo),
+				fuzz: self.fuzz,
+				texture: None
+			}),
+			Albedo::Texture { .. } => None
+		}
 	}
 }
\ No newline at end of file
@@ -1,12 +1,50 @@
+pub mod albedo;
 pub mod lambertian;
 pub mod metal;
 pub mod dielectric;
+pub mod diffuse_light;
 pub mod util;
 
 use crate::raytracing::color::Color;
 use crate::raytracing::hit_record::HitRecord;
 use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::MaterialDescription;
+use crate::raytracing::vector_3d::Vec3;
 
 pub trait Material {
-	fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)>;
-}
+	fn scatter(&self, ray: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<(Color, Ray)>;
+
+	// BSDF times the cosine term for scattering `ray` into `direction`
+	fn evaluate(&self, _ray: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> Color {
+		Color::black()
+	}
+
+	// Solid angle pdf of `scatter` producing `direction`
+	fn pdf(&self, _ray: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> f64 {
+		0.0
+	}
+
+	// Specular materials can't be evaluated for arbitrary directions and skip light sampling
+	fn is_specular(&self) -> bool {
+		true
+	}
+
+	// Reflectance for the albedo AOV
+	fn albedo(&self, _hit_record: &HitRecord) -> Color {
+		Color::white()
+	}
+
+	fn emitted(&self, _ray: &Ray, _hit_record: &HitRecord) -> Color {
+		Color::black()
+	}
+
+	fn is_emissive(&self) -> bool {
+		false
+	}
+
+	// None for materials scene files can't describe, like textured ones
+	fn to_description(&self) -> Option<MaterialDescription> {
+		None
+	}
+}
\ No newline at end of file
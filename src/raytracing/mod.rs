@@ -1,41 +1,91 @@
 use std::sync::Arc;
-use rand::{Rng, thread_rng};
 use workers_pool::{TaskState, Worker};
 use workers_pool::TaskState::Finished;
 use scene::RaytracingScene;
+use crate::raytracing::aov::{AovSums, id_color, SurfaceAovs};
 use crate::raytracing::color::Color;
+use crate::raytracing::film::{relative_error, Splat, SplatWindow};
+use crate::raytracing::filter::Filter;
+use crate::raytracing::hit_record::HitRecord;
 use crate::raytracing::hittable::Hittable;
-use crate::raytracing::ray::Ray;
+use crate::raytracing::integrator::{heatmap, Integrator};
+use crate::raytracing::materials::Material;
+use crate::raytracing::pause::PauseSignal;
+use crate::raytracing::ray::{Ray, RayCone};
+use crate::raytracing::raytracer::AdaptiveSettings;
+use crate::raytracing::sampler::{Sampler, SamplerType};
+use crate::raytracing::vector_3d::Vec3;
 use crate::raytracing::work::RaytracingWork;
 
 pub mod vector_2d;
 pub mod vector_3d;
 pub mod color;
 pub mod texture;
+pub mod texture_sampling;
 pub mod pixel;
 pub mod scene;
+pub mod film;
+pub mod filter;
+pub mod aov;
+pub mod integrator;
+pub mod denoiser;
+pub mod tone_mapping;
+pub mod sampler;
+pub mod scene_description;
 pub mod ray;
 pub mod camera;
+pub mod bokeh;
 pub mod hittable;
 pub mod hit_record;
 pub mod materials;
 pub mod work;
 pub mod raytracer;
+pub mod wavefront;
+pub mod aabb;
+pub mod background;
+pub mod distribution;
+pub mod environment;
+pub mod sky;
+pub mod pause;
 
 #[derive(Default, Clone)]
 pub struct RaytracingContext {
     pub image_width: u32,
     pub image_height: u32,
     pub samples_per_pixel: usize,
+    // Safety cap on the path length, Russian roulette ends paths long before it
     pub max_bounces: usize,
-    pub scene: Arc<RaytracingScene>
+    // Bounces before Russian roulette can end paths
+    pub russian_roulette_depth: usize,
+    pub integrator: Integrator,
+    pub sampler: SamplerType,
+    pub filter: Filter,
+    pub seed: u64,
+    // Samples per pixel over all passes, stratification depends on it
+    pub total_samples_per_pixel: usize,
+    // Index of the first sample taken in this pass, so every pass draws different random numbers
+    pub first_sample_index: usize,
+    pub scene: Arc<RaytracingScene>,
+    pub pause: Arc<PauseSignal>,
+    pub adaptive: Option<AdaptiveSettings>,
+    pub aovs: bool,
+    // Pixels that already converged in an earlier pass, indexed by y * image_width + x, empty if there are none
+    pub converged: Arc<Vec<bool>>
 }
 
-#[derive(Default, Copy, Clone, PartialEq, Debug)]
+// How many samples an adaptive pixel takes between convergence checks
+const ADAPTIVE_CHECK_INTERVAL: usize = 4;
+
+#[derive(Default, Clone, PartialEq, Debug)]
 pub struct RaytracingResult {
     pub x: u32,
     pub y: u32,
-    pub pixel_color: Color,
+    pub color_sum: Color,
+    pub luminance_square_sum: f64,
+    pub sample_count: usize,
+    // Filter weighted contributions of this pixel's samples to it and its neighbours
+    pub splats: Vec<Splat>,
+    pub aovs: Option<AovSums>
 }
 
 #[derive(Debug, Clone, Default)]
@@ -54,7 +104,11 @@ impl Worker for RaytracingWorker {
     type Context = RaytracingContext;
 
     fn execute(&mut self, data: Option<Self::Data>, context: &Arc<Self::Context>) -> (Option<Self::Result>, TaskState) {
-        let mut rand = thread_rng();
+        // Paused workers block here holding on to their work, so resuming continues exactly where they stopped
+        if !context.pause.wait_until_running() {
+            self.current_task_state = None;
+            return (None, TaskState::Finished);
+        }
 
         let data = match data {
             None => {
@@ -100,84 +154,375 @@ impl Worker for RaytracingWorker {
         let x = work.0;
         let y = work.1;
 
+        let pixel_index = y as usize * image_width as usize + x as usize;
+
+        if context.converged.get(pixel_index).copied().unwrap_or(false) {
+            let result = RaytracingResult {
+                x,
+                y,
+                color_sum: Color::black(),
+                luminance_square_sum: 0.0,
+                sample_count: 0,
+                splats: Vec::new(),
+                aovs: None
+            };
+            return (Some(result), TaskState::Continue);
+        }
+
         let mut color = Color::new();
+        let mut luminance_sum = 0.0;
+        let mut luminance_square_sum = 0.0;
+        let mut sample_count = 0;
+        let mut aovs = context.aovs.then(AovSums::new);
+
+        let mut sampler = context.sampler.create_sampler(context.seed, context.total_samples_per_pixel);
+        let mut splat_window = SplatWindow::create(x, y, context.filter);
 
-        for _ in 0..context.samples_per_pixel {
-            let u_offset = rand.gen_range(0.0..1.0);
-            let v_offset = rand.gen_range(0.0..1.0);
+        while sample_count < context.samples_per_pixel {
+            sampler.start_sample(x, y, context.first_sample_index + sample_count);
+
+            let (u_offset, v_offset) = sampler.next_2d();
             let u = (x as f64 + u_offset) / image_width as f64;
             let v = (y as f64 + v_offset) / image_height as f64;
 
-            let ray = context.scene.camera.cast_ray(u,v);
-            let new_color = ray_color(&ray, &context.scene, context.max_bounces);
+            let ray = context.scene.camera.cast_ray(u, v, sampler.as_mut());
+            let cone = RayCone::create(context.scene.camera.pixel_spread_angle(image_height));
+            let radiance = integrator_radiance(&ray, cone, context, sampler.as_mut());
+            let new_color = radiance.total();
+
+            if let Some(aovs) = &mut aovs {
+                aovs.add_sample(radiance.surface, radiance.emitted + radiance.direct, radiance.indirect);
+            }
             color = Color {
                 r: color.r + new_color.r,
                 g: color.g + new_color.g,
                 b: color.b + new_color.b,
                 a: 1.0
             };
-        }
 
-        let scale = 1.0 / context.samples_per_pixel as f32;
+            splat_window.add_sample(u_offset, v_offset, new_color);
 
-        let color = Color {
-            r: (color.r * scale).sqrt(),
-            g: (color.g * scale).sqrt(),
-            b: (color.b * scale).sqrt(),
-            a: 1.0
-        };
+            let luminance = new_color.luminance() as f64;
+            luminance_sum += luminance;
+            luminance_square_sum += luminance * luminance;
+            sample_count += 1;
+
+            if let Some(adaptive) = &context.adaptive {
+                if sample_count >= adaptive.min_samples_per_pixel && sample_count % ADAPTIVE_CHECK_INTERVAL == 0 {
+                    let converged = match relative_error(luminance_sum, luminance_square_sum, sample_count) {
+                        None => false,
+                        Some(error) => error <= adaptive.error_threshold
+                    };
+
+                    if converged {
+                        break;
+                    }
+                }
+            }
+        }
 
         let result = RaytracingResult {
             x,
             y,
-            pixel_color: color
+            color_sum: color,
+            luminance_square_sum,
+            sample_count,
+            splats: splat_window.into_splats(image_width, image_height),
+            aovs
         };
         (Some(result), TaskState::Continue)
     }
 }
 
-fn ray_color(ray: &Ray, scene: &RaytracingScene, depth: usize) -> Color {
-    if depth == 0 {
-        return Color::new();
+// Radiance of a path split up by the vertex it was picked up at, relative to the path's first vertex
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct PathRadiance {
+    // Emission of the first vertex, or the background
+    emitted: Color,
+    // Light arriving at the first vertex straight from an emitter
+    direct: Color,
+    // Light arriving at the first vertex after more bounces
+    indirect: Color,
+    // How often the path scattered before it ended
+    bounces: usize,
+    // What the camera ray hit, only filled in when AOVs are rendered
+    surface: Option<SurfaceAovs>
+}
+
+impl PathRadiance {
+    fn from_emission(emitted: Color) -> Self {
+        Self {
+            emitted,
+            ..Self::default()
+        }
+    }
+
+    fn total(&self) -> Color {
+        self.emitted + self.direct + self.indirect
     }
 
-    if let Some(hit_record) = scene.hit(ray, 0.001, f64::INFINITY) {
-        return if let Some(material) = &hit_record.material {
-            let scattered = material.scatter(&ray, &hit_record);
-            if let Some((attenuation, ray)) = scattered {
-                let new_color = ray_color(&ray, scene, depth - 1);
-
-                Color {
-                    r: attenuation.r * new_color.r,
-                    g: attenuation.g * new_color.g,
-                    b: attenuation.b * new_color.b,
-                    a: 1.0
-                }
-            } else {
-                Color::create(0.0, 0.0, 0.0, 1.0)
+    // Emission found by the ray leaving vertex `bounce - 1`, already multiplied by the path's throughput
+    fn add_emission(&mut self, bounce: usize, emitted: Color) {
+        match bounce {
+            0 => self.emitted += emitted,
+            1 => self.direct += emitted,
+            _ => self.indirect += emitted
+        }
+    }
+
+    // Light sampled at vertex `bounce`, already multiplied by the path's throughput
+    fn add_direct_light(&mut self, bounce: usize, direct_light: Color) {
+        match bounce {
+            0 => self.direct += direct_light,
+            _ => self.indirect += direct_light
+        }
+    }
+}
+
+// Follows the path vertex by vertex until Russian roulette ends it, which it may once the path bounced `roulette_depth` times.
+// `max_bounces` only keeps pathological paths from running forever, the few that reach it are cut short
+fn ray_color(ray: &Ray, cone: RayCone, scene: &RaytracingScene, max_bounces: usize, roulette_depth: usize, aovs: bool, sampler: &mut dyn Sampler) -> PathRadiance {
+    let mut radiance = PathRadiance::default();
+    let mut ray = *ray;
+    let mut cone = cone;
+    let mut throughput = Color::white();
+    // The pdf the previous bounce sampled `ray` with, None for camera rays and specular bounces
+    let mut bsdf_pdf: Option<f64> = None;
+
+    for bounce in 0..max_bounces {
+        sampler.start_bounce(bounce);
+
+        let mut hit_record = match scene.hit(&ray, 0.001, f64::INFINITY) {
+            None => {
+                let background = scene.background.color(&ray);
+
+                // Light sampling can find the background too, weigh it against that like any other emitter
+                let background = match bsdf_pdf {
+                    Some(bsdf_pdf) if scene.background.can_be_sampled() => {
+                        background * power_heuristic(bsdf_pdf, scene.background_pdf(ray.direction)) as f32
+                    }
+                    _ => background
+                };
+
+                radiance.add_emission(bounce, throughput * background);
+                break;
             }
-        } else {
-            Color {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-                a: 1.0
+            Some(hit_record) => hit_record
+        };
+
+        cone = cone.propagate(hit_record.t * ray.direction.length());
+        hit_record.uv_footprint = cone.width * hit_record.uv_density;
+
+        if aovs && bounce == 0 {
+            radiance.surface = Some(surface_aovs(&ray, &hit_record, scene));
+        }
+
+        let material = match &hit_record.material {
+            None => break,
+            Some(material) => material
+        };
+
+        let emitted = material.emitted(&ray, &hit_record);
+
+        let emitted = match bsdf_pdf {
+            Some(bsdf_pdf) if !emitted.is_black() => {
+                let light_pdf = scene.light_pdf(ray.origin, ray.direction);
+                emitted * power_heuristic(bsdf_pdf, light_pdf) as f32
+            }
+            _ => emitted
+        };
+
+        radiance.add_emission(bounce, throughput * emitted);
+
+        let (attenuation, scattered) = match material.scatter(&ray, &hit_record, sampler) {
+            None => break,
+            Some(scattered) => scattered
+        };
+
+        radiance.bounces = bounce + 1;
+
+        if material.is_specular() {
+            bsdf_pdf = None;
+        }
+        else {
+            let direct_light = sample_direct_light(&ray, &hit_record, material.as_ref(), scene, sampler);
+            radiance.add_direct_light(bounce, throughput * direct_light);
+
+            bsdf_pdf = Some(material.pdf(&ray, &hit_record, scattered.direction));
+        }
+
+        throughput = throughput * attenuation;
+        ray = scattered;
+
+        if throughput.is_black() {
+            break;
+        }
+
+        // Paths that carry little light end early, the ones that survive are brightened to make up for the others
+        if bounce + 1 >= roulette_depth {
+            let termination_probability = (1.0 - throughput.r.max(throughput.g).max(throughput.b) as f64).max(0.05);
+
+            if sampler.next_1d() < termination_probability {
+                break;
             }
+
+            throughput = throughput * (1.0 / (1.0 - termination_probability)) as f32;
+        }
+    }
+
+    radiance
+}
+
+// Radiance of a camera sample, the debug integrators only emit their value
+fn integrator_radiance(ray: &Ray, cone: RayCone, context: &RaytracingContext, sampler: &mut dyn Sampler) -> PathRadiance {
+    let scene = context.scene.as_ref();
+
+    let color = match context.integrator {
+        Integrator::PathTracer => {
+            return ray_color(ray, cone, scene, context.max_bounces, context.russian_roulette_depth, context.aovs, sampler);
+        }
+        Integrator::BounceCount => {
+            let radiance = ray_color(ray, cone, scene, context.max_bounces, context.russian_roulette_depth, false, sampler);
+            heatmap(radiance.bounces as f64 / context.max_bounces.max(1) as f64)
         }
+        Integrator::BvhCost { max_nodes } => {
+            let mut nodes_visited = 0;
+            scene.hit_counted(ray, 0.001, f64::INFINITY, &mut nodes_visited);
+
+            heatmap(nodes_visited as f64 / max_nodes.max(1) as f64)
+        }
+        Integrator::Normal => first_hit(ray, cone, scene).map_or(Color::black(), |hit_record| {
+            let normal = hit_record.normal * 0.5 + 0.5;
+            Color::create(normal.x as f32, normal.y as f32, normal.z as f32, 1.0)
+        }),
+        Integrator::Depth { max_depth } => first_hit(ray, cone, scene).map_or(Color::black(), |hit_record| {
+            let value = (hit_record.t * ray.direction.length() / max_depth).min(1.0) as f32;
+            Color::create(value, value, value, 1.0)
+        }),
+        Integrator::Albedo => first_hit(ray, cone, scene)
+            .and_then(|hit_record| hit_record.material.as_ref().map(|material| material.albedo(&hit_record)))
+            .unwrap_or(Color::black()),
+        Integrator::Uv => first_hit(ray, cone, scene).map_or(Color::black(), |hit_record| {
+            Color::create(hit_record.uv.x as f32, hit_record.uv.y as f32, 0.0, 1.0)
+        }),
+        Integrator::MaterialId => first_hit(ray, cone, scene).map_or(Color::black(), |hit_record| {
+            let material_id = hit_record.material.as_ref().and_then(|material| scene.material_id(material));
+            id_color(material_id.map_or(0, |id| id as u32 + 1))
+        }),
+        Integrator::AmbientOcclusion { radius } => first_hit(ray, cone, scene).map_or(Color::black(), |hit_record| {
+            sampler.start_bounce(0);
+
+            // Cosine weighted, like the light a diffuse surface would receive from an evenly lit sky
+            let mut direction = hit_record.normal + Vec3::random_normalized(sampler);
+            if direction.near_zero(1e-8) {
+                direction = hit_record.normal;
+            }
+
+            let occlusion_ray = Ray::create(hit_record.point, direction.normalized());
+
+            match scene.hit(&occlusion_ray, 0.001, radius) {
+                None => Color::white(),
+                Some(_) => Color::black()
+            }
+        })
+    };
+
+    PathRadiance {
+        surface: context.aovs.then(|| first_hit(ray, cone, scene).map(|hit_record| surface_aovs(ray, &hit_record, scene))).flatten(),
+        ..PathRadiance::from_emission(color)
     }
+}
 
-    let unit_direction = ray.direction.normalized();
+// Closest hit of a camera ray, with the texture footprint the ray cone has there
+fn first_hit(ray: &Ray, cone: RayCone, scene: &RaytracingScene) -> Option<HitRecord> {
+    let mut hit_record = scene.hit(ray, 0.001, f64::INFINITY)?;
+    hit_record.uv_footprint = cone.propagate(hit_record.t * ray.direction.length()).width * hit_record.uv_density;
 
-    let t = 0.5 * (unit_direction.y + 1.0);
+    Some(hit_record)
+}
 
-    let color = Color {
-        r: ((1.0 - t) + t * 0.5) as f32,
-        g: ((1.0 - t) + t * 0.7) as f32,
-        b: ((1.0 - t) + t * 1.0) as f32,
-        a: 1.0
+// Doesn't draw from the sampler, so rendering AOVs doesn't change the beauty image
+fn surface_aovs(ray: &Ray, hit_record: &HitRecord, scene: &RaytracingScene) -> SurfaceAovs {
+    let (albedo, material_id) = match &hit_record.material {
+        None => (Color::black(), None),
+        Some(material) => (material.albedo(hit_record), scene.material_id(material))
     };
 
-    color
+    SurfaceAovs {
+        depth: hit_record.t * ray.direction.length(),
+        normal: hit_record.normal,
+        position: hit_record.point,
+        albedo,
+        object_id: hit_record.object_index.map_or(0, |index| index as u32 + 1),
+        material_id: material_id.map_or(0, |id| id as u32 + 1)
+    }
 }
 
+fn sample_direct_light(ray: &Ray, hit_record: &HitRecord, material: &(dyn Material + Send + Sync), scene: &RaytracingScene, sampler: &mut dyn Sampler) -> Color {
+    let sample = match scene.sample_light(hit_record.point, sampler) {
+        None => return Color::black(),
+        Some(sample) => sample
+    };
+
+    if sample.pdf.is_nan() || sample.pdf <= 0.0 {
+        return Color::black();
+    }
+
+    let direction = sample.direction;
+    let distance = sample.distance;
+
+    let bsdf = material.evaluate(ray, hit_record, direction);
+    if bsdf.is_black() {
+        return Color::black();
+    }
+
+    let shadow_ray = Ray::create(hit_record.point, direction);
+
+    let emitted = if distance.is_infinite() {
+        // The background is only visible if nothing is in the way at all
+        if scene.hit(&shadow_ray, 0.001, f64::INFINITY).is_some() {
+            return Color::black();
+        }
+
+        scene.background.color(&shadow_ray)
+    }
+    else {
+        let light_hit = match scene.hit(&shadow_ray, 0.001, distance * (1.0 + SHADOW_RAY_TOLERANCE)) {
+            None => return Color::black(),
+            Some(light_hit) => light_hit
+        };
+
+        if light_hit.t < distance * (1.0 - SHADOW_RAY_TOLERANCE) {
+            return Color::black();
+        }
+
+        match &light_hit.material {
+            None => return Color::black(),
+            Some(light_material) => light_material.emitted(&shadow_ray, &light_hit)
+        }
+    };
+
+    let weight = power_heuristic(sample.pdf, material.pdf(ray, hit_record, direction));
+
+    emitted * bsdf * (weight / sample.pdf) as f32
+}
+
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    if pdf.is_infinite() {
+        return 1.0;
+    }
+
+    let pdf_squared = pdf.powi(2);
+    let sum = pdf_squared + other_pdf.powi(2);
+
+    if sum > 0.0 {
+        pdf_squared / sum
+    }
+    else {
+        0.0
+    }
+}
+
+const SHADOW_RAY_TOLERANCE: f64 = 1e-4;
+
 pub const NEAR_ZERO_THRESHOLD: f64 = f64::EPSILON;
@@ -0,0 +1,74 @@
+use std::sync::{Condvar, Mutex};
+
+#[derive(Default, Debug)]
+struct PauseState {
+	paused: bool,
+	cancelled: bool
+}
+
+// Shared by the raytracer and its workers, paused workers sleep on it until the render is resumed or stopped
+#[derive(Default, Debug)]
+pub struct PauseSignal {
+	state: Mutex<PauseState>,
+	changed: Condvar
+}
+
+impl PauseSignal {
+	pub fn new() -> Self {
+		Self {
+			state: Mutex::new(PauseState::default()),
+			changed: Condvar::new()
+		}
+	}
+
+	pub fn pause(&self) {
+		self.state.lock().unwrap().paused = true;
+	}
+
+	pub fn resume(&self) {
+		self.state.lock().unwrap().paused = false;
+		self.changed.notify_all();
+	}
+
+	// Wakes paused workers for good, the pool can only shut down once none of them is waiting
+	pub fn cancel(&self) {
+		self.state.lock().unwrap().cancelled = true;
+		self.changed.notify_all();
+	}
+
+	// Blocks while paused, false if the render was cancelled and the worker should give up its work
+	pub fn wait_until_running(&self) -> bool {
+		let state = self.changed.wait_while(self.state.lock().unwrap(), |state| state.paused && !state.cancelled).unwrap();
+
+		!state.cancelled
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::thread;
+	use super::*;
+
+	#[test]
+	fn paused_workers_wait_for_resume_or_cancel() {
+		let signal = Arc::new(PauseSignal::new());
+		assert!(signal.wait_until_running());
+
+		signal.pause();
+		let waiting = {
+			let signal = signal.clone();
+			thread::spawn(move || signal.wait_until_running())
+		};
+		signal.resume();
+		assert!(waiting.join().unwrap());
+
+		signal.pause();
+		let waiting = {
+			let signal = signal.clone();
+			thread::spawn(move || signal.wait_until_running())
+		};
+		signal.cancel();
+		assert!(!waiting.join().unwrap());
+	}
+}
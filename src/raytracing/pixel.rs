@@ -24,7 +24,7 @@ impl<'a> Pixel<'a> {
 	}
 
 	pub fn to_rgba(&self) -> Rgba<f32> {
-		Rgba::from(self.target.clone())
+		Rgba::from(*self.target)
 	}
 }
 
@@ -81,7 +81,7 @@ impl<'a> MutPixel<'a> {
 	}
 
 	pub fn to_rgba(&self) -> Rgba<f32> {
-		Rgba::from(self.target.clone())
+		Rgba::from(*self.target)
 	}
 }
 
@@ -30,4 +30,27 @@ impl Default for Ray {
 	fn default() -> Self {
 		Self::new()
 	}
+}
+
+// Footprint of a pixel along a path, a ray cone after Akenine-Möller et al. (2019) that ignores surface curvature
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct RayCone {
+	pub width: f64,
+	pub spread_angle: f64
+}
+
+impl RayCone {
+	pub fn create(spread_angle: f64) -> Self {
+		Self {
+			width: 0.0,
+			spread_angle
+		}
+	}
+
+	pub fn propagate(&self, distance: f64) -> Self {
+		Self {
+			width: self.width + self.spread_angle * distance,
+			spread_angle: self.spread_angle
+		}
+	}
 }
\ No newline at end of file
@@ -1,18 +1,33 @@
 use std::num::NonZeroU32;
 use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
 use workers_pool::WorkersPool;
 use crate::raytracing::color::Color;
+use crate::raytracing::denoiser::Denoiser;
+use crate::raytracing::film::Film;
+use crate::raytracing::pause::PauseSignal;
 use crate::raytracing::raytracer::{Raytracer, RaytracerSettings, RaytracerState};
 use crate::raytracing::{RaytracingContext, RaytracingWorker};
 use crate::raytracing::scene::RaytracingScene;
 use crate::raytracing::texture::{Texture, TextureWrapMode};
+use crate::raytracing::tone_mapping::DisplaySettings;
 use crate::raytracing::work::generator::{RaytracingWorkGenerator};
 
 pub struct CPURaytracer {
 	texture: Texture,
+	film: Film,
+	// Linear output of the denoiser, the texture shows this instead of the film while it exists
+	denoised: Option<Texture>,
+	// The denoiser runs on a copy of the film on its own thread, so the viewer keeps responding while it works
+	denoising: Option<JoinHandle<Texture>>,
+	// Set when the film changed while `denoising` was busy, it then starts over once it's done
+	denoise_again: bool,
 	settings: RaytracerSettings,
 	scene: Arc<RaytracingScene>,
 	current_workers: Option<WorkersPool<RaytracingWorker>>,
+	pause: Arc<PauseSignal>,
+	current_pass: usize,
 	state: RaytracerState,
 }
 
@@ -25,31 +40,64 @@ impl CPURaytracer {
 			Color::create(0.0,0.0,0.0,0.0)
 		);
 
+		let film = Film::create(settings.width, settings.height);
+
 		Self {
 			texture,
+			film,
+			denoised: None,
+			denoising: None,
+			denoise_again: false,
 			settings,
 			scene,
 			current_workers: None,
+			pause: Arc::new(PauseSignal::new()),
+			current_pass: 0,
 			state: RaytracerState::Created
 		}
 	}
-}
 
-impl Raytracer for CPURaytracer {
-	fn start_rendering(&mut self) {
-		self.stop_rendering();
+	pub fn film(&self) -> &Film {
+		&self.film
+	}
 
-		self.texture.clear(Color::create(0.0,0.0,0.0,1.0));
+	pub fn current_pass(&self) -> usize {
+		self.current_pass
+	}
+
+	fn pixel_count(&self) -> usize {
+		self.settings.width as usize * self.settings.height as usize
+	}
 
-		let context = RaytracingContext {
+	fn pass_context(&self) -> RaytracingContext {
+		// Pixels can only converge within a pass once they take at least the minimum samples in it, across passes the film decides
+		let converged = match &self.settings.adaptive {
+			Some(adaptive) if self.current_pass > 0 => self.film.converged_pixels(adaptive),
+			_ => Vec::new()
+		};
+
+		RaytracingContext {
 			image_width: self.settings.width,
 			image_height: self.settings.height,
-			samples_per_pixel: self.settings.samples_per_pixel,
+			samples_per_pixel: self.settings.samples_in_pass(self.current_pass),
 			max_bounces: self.settings.max_bounces,
-			scene: self.scene.clone()
-		};
+			russian_roulette_depth: self.settings.russian_roulette_depth,
+			integrator: self.settings.integrator,
+			sampler: self.settings.sampler,
+			filter: self.settings.filter,
+			seed: self.settings.seed,
+			total_samples_per_pixel: self.settings.samples_per_pixel,
+			first_sample_index: self.settings.first_sample_in_pass(self.current_pass),
+			scene: self.scene.clone(),
+			pause: self.pause.clone(),
+			adaptive: self.settings.adaptive,
+			aovs: self.settings.renders_aovs(),
+			converged: Arc::new(converged)
+		}
+	}
 
-		let mut new_workers = WorkersPool::new(context);
+	fn start_pass(&mut self) {
+		let mut new_workers = WorkersPool::new(self.pass_context());
 
 		let generator = RaytracingWorkGenerator {
 			width: self.settings.width,
@@ -59,14 +107,108 @@ impl Raytracer for CPURaytracer {
 
 		generator.generate(&mut new_workers).unwrap();
 
+		self.current_workers = Some(new_workers);
+	}
+
+	fn start_denoising(&mut self) {
+		let denoiser = match self.settings.denoiser {
+			None => {
+				// A denoiser that is still running gets detached, its result is dropped
+				self.denoising = None;
+				self.denoise_again = false;
+				self.denoised = None;
+				self.update_texture();
+				return;
+			}
+			Some(denoiser) => denoiser
+		};
+
+		if self.denoising.is_some() {
+			self.denoise_again = true;
+			return;
+		}
+
+		let film = self.film.clone();
+		self.denoising = Some(thread::spawn(move || denoiser.denoise(&film)));
+	}
+
+	fn finish_denoising(&mut self) {
+		if !self.denoising.as_ref().is_some_and(|denoising| denoising.is_finished()) {
+			return;
+		}
+
+		if let Some(denoised) = self.denoising.take().and_then(|denoising| denoising.join().ok()) {
+			self.denoised = Some(denoised);
+			self.update_texture();
+		}
+
+		if self.denoise_again {
+			self.denoise_again = false;
+			self.start_denoising();
+		}
+	}
+
+	fn update_texture(&mut self) {
+		let display = self.settings.display;
+
+		// Pixels without samples keep the cleared color
+		for y in 0..self.film.height() {
+			for x in 0..self.film.width() {
+				if !self.film.is_sampled(x, y) {
+					continue;
+				}
+
+				let color = match &self.denoised {
+					None => self.film.color(x, y),
+					Some(denoised) => {
+						let [r, g, b, _] = denoised.get_pixel(x, y).unwrap().to_rgba().0;
+						Color::create(r, g, b, 1.0)
+					}
+				};
+
+				self.texture.set_pixel(x, y, display.apply(color));
+			}
+		}
+	}
+}
+
+impl Raytracer for CPURaytracer {
+	fn start_rendering(&mut self) {
+		self.stop_rendering();
+
+		let width = self.settings.width;
+		let height = self.settings.height;
+
+		if self.texture.get_width().get() != width || self.texture.get_height().get() != height {
+			self.texture = Texture::new(
+				NonZeroU32::new(width).unwrap(),
+				NonZeroU32::new(height).unwrap(),
+				TextureWrapMode::Clamp,
+				Color::create(0.0,0.0,0.0,0.0)
+			);
+			self.film = Film::create(width, height);
+		}
+
+		self.texture.clear(Color::create(0.0,0.0,0.0,1.0));
+		self.film.clear();
+		self.denoised = None;
+		self.denoising = None;
+		self.denoise_again = false;
+
+		self.pause = Arc::new(PauseSignal::new());
+		self.current_pass = 0;
+
+		self.start_pass();
+
 		self.state = RaytracerState::Running {
-			commissioned: self.settings.width as usize * self.settings.height as usize,
+			commissioned: self.pixel_count() * self.settings.pass_count(),
 			completed: 0
 		};
-		self.current_workers = Some(new_workers);
 	}
 
 	fn stop_rendering(&mut self) {
+		// Paused workers have to wake up before the pool can join them
+		self.pause.cancel();
 		self.current_workers = None;
 		self.state = RaytracerState::Stopped;
 	}
@@ -77,32 +219,88 @@ impl Raytracer for CPURaytracer {
 	}
 
 	fn pause_rendering(&mut self) {
-		todo!()
+		if let RaytracerState::Running { commissioned, completed } = self.state {
+			self.pause.pause();
+
+			self.state = RaytracerState::Paused {
+				commissioned,
+				completed
+			};
+		}
 	}
 
 	fn continue_rendering(&mut self) {
-		todo!()
+		if let RaytracerState::Paused { commissioned, completed } = self.state {
+			self.pause.resume();
+
+			self.state = RaytracerState::Running {
+				commissioned,
+				completed
+			};
+		}
 	}
 
 	fn update(&mut self) {
+		self.finish_denoising();
+
 		match self.state {
 			RaytracerState::Created => {}
 			RaytracerState::Running { commissioned, mut completed } => {
-				let workers = self.current_workers.as_mut().unwrap();
-				let results = workers.collect_finished().unwrap();
+				// Gone once all pixels are done, the render may still wait for the denoiser then
+				let results = match self.current_workers.as_mut() {
+					None => Vec::new(),
+					Some(workers) => workers.collect_finished().unwrap()
+				};
 
 				for result in results {
-					self.texture.set_pixel(result.x, result.y, result.pixel_color);
+					if result.sample_count > 0 {
+						self.film.add_samples(result.x, result.y, result.color_sum, result.luminance_square_sum, result.sample_count);
+					}
+
+					if let Some(aovs) = &result.aovs {
+						self.film.add_aovs(result.x, result.y, aovs);
+					}
+
+					for splat in &result.splats {
+						self.film.add_splat(splat);
+
+						// A denoised pass stays on screen until the next one is done
+						if self.denoised.is_none() {
+							self.texture.set_pixel(splat.x, splat.y, self.settings.display.apply(self.film.color(splat.x, splat.y)));
+						}
+					}
 					completed += 1;
 				}
 
 				if completed >= commissioned {
-					self.state = RaytracerState::Finished {
-						commissioned,
-						completed
+					if self.current_workers.take().is_some() && self.settings.denoiser.is_some() {
+						self.start_denoising();
+					}
+
+					// Finished only once the denoised image is on screen
+					self.state = if self.denoising.is_none() {
+						RaytracerState::Finished {
+							commissioned,
+							completed
+						}
+					}
+					else {
+						RaytracerState::Running {
+							commissioned,
+							completed
+						}
 					};
 				}
 				else {
+					if completed >= (self.current_pass + 1) * self.pixel_count() {
+						if self.settings.denoiser.is_some() {
+							self.start_denoising();
+						}
+
+						self.current_pass += 1;
+						self.start_pass();
+					}
+
 					self.state = RaytracerState::Running {
 						commissioned,
 						completed
@@ -121,6 +319,17 @@ impl Raytracer for CPURaytracer {
 		self.settings = new_settings;
 	}
 
+	fn set_display_settings(&mut self, display: DisplaySettings) {
+		self.settings.display = display;
+		self.update_texture();
+	}
+
+	// Passes started from now on render the AOVs the denoiser is guided by
+	fn set_denoiser(&mut self, denoiser: Option<Denoiser>) {
+		self.settings.denoiser = denoiser;
+		self.start_denoising();
+	}
+
 	fn set_scene(&mut self, scene: Arc<RaytracingScene>) {
 		self.scene = scene;
 	}
@@ -132,4 +341,131 @@ impl Raytracer for CPURaytracer {
 	fn get_current_texture(&self) -> &Texture {
 		&self.texture
 	}
+
+	fn get_film(&self) -> Option<&Film> {
+		Some(&self.film)
+	}
+}
+
+impl Drop for CPURaytracer {
+	fn drop(&mut self) {
+		self.pause.cancel();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Mutex;
+	use std::thread;
+	use workers_pool::{TaskState, Worker};
+	use crate::raytracing::sampler::SamplerType;
+	use super::*;
+
+	fn settings(generation_mode: &str) -> RaytracerSettings {
+		RaytracerSettings {
+			width: 24,
+			height: 16,
+			samples_per_pixel: 4,
+			generation_mode: generation_mode.parse().unwrap(),
+			sampler: SamplerType::Sobol,
+			seed: 5,
+			..RaytracerSettings::default()
+		}
+	}
+
+	fn film_colors(film: &Film) -> Vec<Color> {
+		(0..film.height())
+			.flat_map(|y| (0..film.width()).map(move |x| (x, y)))
+			.map(|(x, y)| film.color(x, y))
+			.collect()
+	}
+
+	fn render(settings: RaytracerSettings) -> Film {
+		let mut raytracer = CPURaytracer::new(settings, Arc::new(RaytracingScene::create_cornell_box(1.5)));
+		raytracer.render_to_completion();
+
+		raytracer.film().clone()
+	}
+
+	// Hands the work out to `threads` workers like the pool does, results are added in the order they arrive
+	fn render_with_threads(settings: RaytracerSettings, threads: usize) -> Film {
+		let raytracer = CPURaytracer::new(settings.clone(), Arc::new(RaytracingScene::create_cornell_box(1.5)));
+		let context = Arc::new(raytracer.pass_context());
+
+		let work = Mutex::new(settings.generation_mode.to_work(settings.width, settings.height).into_iter());
+		let film = Mutex::new(Film::create(settings.width, settings.height));
+
+		thread::scope(|scope| {
+			for _ in 0..threads {
+				scope.spawn(|| {
+					let mut worker = RaytracingWorker::default();
+
+					loop {
+						let mut data = work.lock().unwrap().next();
+						if data.is_none() {
+							break;
+						}
+
+						loop {
+							let (result, state) = worker.execute(data.take(), &context);
+
+							if let Some(result) = result {
+								let mut film = film.lock().unwrap();
+								film.add_samples(result.x, result.y, result.color_sum, result.luminance_square_sum, result.sample_count);
+								for splat in &result.splats {
+									film.add_splat(splat);
+								}
+							}
+
+							if matches!(state, TaskState::Finished) {
+								break;
+							}
+						}
+					}
+				});
+			}
+		});
+
+		film.into_inner().unwrap()
+	}
+
+	#[test]
+	fn generation_modes_render_the_same_image() {
+		let expected = film_colors(&render(settings("pixel")));
+		assert!(expected.iter().any(|color| !color.is_black()));
+
+		for mode in ["line,reverse,random", "stripe,random", "tile,5x3,random/line", "tile,8x8,stripe-first,reverse-x/stripe"] {
+			assert_eq!(film_colors(&render(settings(mode))), expected, "{}", mode);
+		}
+	}
+
+	#[test]
+	fn aovs_do_not_change_the_image() {
+		let expected = film_colors(&render(settings("pixel")));
+		let film = render(RaytracerSettings { aovs: true, ..settings("pixel") });
+
+		assert!(film.has_aovs());
+		assert_eq!(film_colors(&film), expected);
+	}
+
+	#[test]
+	fn renders_finish_once_the_denoiser_is_done() {
+		let mut raytracer = CPURaytracer::new(RaytracerSettings { denoiser: Some(Denoiser::new()), ..settings("pixel") }, Arc::new(RaytracingScene::create_cornell_box(1.5)));
+
+		assert!(matches!(raytracer.render_to_completion(), RaytracerState::Finished { .. }));
+		assert!(raytracer.film().has_aovs());
+		assert!(raytracer.denoising.is_none());
+		assert!(raytracer.denoised.is_some());
+	}
+
+	#[test]
+	fn thread_counts_render_the_same_image() {
+		let expected = film_colors(&render_with_threads(settings("tile,4x4"), 1));
+
+		for threads in [2, 3, 8] {
+			assert_eq!(film_colors(&render_with_threads(settings("tile,4x4"), threads)), expected, "{} threads", threads);
+		}
+
+		assert_eq!(film_colors(&render(settings("tile,4x4"))), expected);
+	}
 }
\ No newline at end of file
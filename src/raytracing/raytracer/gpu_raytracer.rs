@@ -1,9 +1,11 @@
 use std::num::NonZeroU32;
 use std::sync::Arc;
 use crate::raytracing::color::Color;
+use crate::raytracing::denoiser::Denoiser;
 use crate::raytracing::raytracer::{Raytracer, RaytracerSettings, RaytracerState};
 use crate::raytracing::scene::RaytracingScene;
 use crate::raytracing::texture::{Texture, TextureWrapMode};
+use crate::raytracing::tone_mapping::DisplaySettings;
 
 pub struct GPURaytracer {
 	texture: Texture,
@@ -57,6 +59,14 @@ impl Raytracer for GPURaytracer {
 		self.settings = new_settings;
 	}
 
+	fn set_display_settings(&mut self, display: DisplaySettings) {
+		self.settings.display = display;
+	}
+
+	fn set_denoiser(&mut self, denoiser: Option<Denoiser>) {
+		self.settings.denoiser = denoiser;
+	}
+
 	fn set_scene(&mut self, scene: Arc<RaytracingScene>) {
 		self.scene = scene;
 	}
@@ -66,6 +76,6 @@ impl Raytracer for GPURaytracer {
 	}
 
 	fn get_current_texture(&self) -> &Texture {
-		todo!()
+		&self.texture
 	}
 }
\ No newline at end of file
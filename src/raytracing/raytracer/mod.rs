@@ -2,12 +2,23 @@ pub mod cpu_raytracer;
 pub mod gpu_raytracer;
 
 use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+use crate::presenter::Presenter;
+use crate::raytracing::denoiser::Denoiser;
+use crate::raytracing::film::Film;
+use crate::raytracing::filter::Filter;
+use crate::raytracing::integrator::Integrator;
+use crate::raytracing::sampler::SamplerType;
 use crate::raytracing::scene::RaytracingScene;
 use crate::raytracing::texture::Texture;
+use crate::raytracing::tone_mapping::DisplaySettings;
 use crate::raytracing::work::generator::GenerationMode;
 
 use serde_derive::{Serialize, Deserialize};
 
+const COMPLETION_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum RaytracerState {
 	Created,
@@ -36,10 +47,87 @@ pub trait Raytracer {
 	fn update(&mut self);
 
 	fn change_settings(&mut self, new_settings: RaytracerSettings);
+	// Only redoes the texture from the accumulated samples, a running render keeps going
+	fn set_display_settings(&mut self, display: DisplaySettings);
+	// Same for the denoiser, None shows the noisy samples again
+	fn set_denoiser(&mut self, denoiser: Option<Denoiser>);
 	fn set_scene(&mut self, scene: Arc<RaytracingScene>);
 
 	fn get_state(&self) -> RaytracerState;
 	fn get_current_texture(&self) -> &Texture;
+
+	// Linear accumulation buffer behind the texture, if the raytracer keeps one
+	fn get_film(&self) -> Option<&Film> {
+		None
+	}
+
+	fn is_finished(&self) -> bool {
+		matches!(self.get_state(), RaytracerState::Finished { .. })
+	}
+
+	fn step(&mut self) -> RaytracerState {
+		self.update();
+		self.get_state()
+	}
+
+	// Starts a new render and blocks until it is no longer running
+	fn render_to_completion(&mut self) -> RaytracerState {
+		self.start_rendering();
+
+		loop {
+			match self.step() {
+				RaytracerState::Running { .. } => sleep(COMPLETION_POLL_INTERVAL),
+				state => return state
+			}
+		}
+	}
+
+	fn present_to_completion(&mut self, presenter: &mut dyn Presenter) -> RaytracerState {
+		self.start_rendering();
+
+		loop {
+			let state = self.step();
+
+			presenter.start_frame(self.get_current_texture(), state);
+			presenter.end_frame();
+
+			match state {
+				RaytracerState::Running { .. } => sleep(COMPLETION_POLL_INTERVAL),
+				state => return state
+			}
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ProgressiveSettings {
+	pub samples_per_pass: usize
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AdaptiveSettings {
+	pub min_samples_per_pixel: usize,
+	// Relative standard error of the mean pixel luminance below which a pixel counts as converged
+	pub error_threshold: f64
+}
+
+impl AdaptiveSettings {
+	pub fn new() -> Self {
+		Self::create(16, 0.01)
+	}
+
+	pub fn create(min_samples_per_pixel: usize, error_threshold: f64) -> Self {
+		Self {
+			min_samples_per_pixel,
+			error_threshold
+		}
+	}
+}
+
+impl Default for AdaptiveSettings {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -47,6 +135,79 @@ pub struct RaytracerSettings {
 	pub width: u32,
 	pub height: u32,
 	pub samples_per_pixel: usize,
+	// Safety cap on the path length, Russian roulette ends paths long before it
 	pub max_bounces: usize,
-	pub generation_mode: GenerationMode
+	// Bounces before Russian roulette can end paths
+	pub russian_roulette_depth: usize,
+	// The path tracer, or one of the debug views
+	pub integrator: Integrator,
+	pub generation_mode: GenerationMode,
+	pub sampler: SamplerType,
+	pub filter: Filter,
+	// Exposure and tone mapping from the linear film to the texture
+	pub display: DisplaySettings,
+	// Images only depend on the seed and the other settings, not on thread count or generation order
+	pub seed: u64,
+	// Renders the whole frame with `samples_per_pass` at a time until `samples_per_pixel` is reached
+	pub progressive: Option<ProgressiveSettings>,
+	// Stops sampling pixels once they converged, `samples_per_pixel` becomes the upper limit
+	pub adaptive: Option<AdaptiveSettings>,
+	// Fills the film's AOV buffers next to the beauty image
+	pub aovs: bool,
+	// Denoises the texture after every pass, renders the AOVs it uses as guides even without `aovs`
+	pub denoiser: Option<Denoiser>
+}
+
+impl RaytracerSettings {
+	pub fn samples_per_pass(&self) -> usize {
+		let samples_per_pixel = self.samples_per_pixel.max(1);
+
+		match &self.progressive {
+			None => samples_per_pixel,
+			Some(progressive) => progressive.samples_per_pass.clamp(1, samples_per_pixel)
+		}
+	}
+
+	pub fn pass_count(&self) -> usize {
+		let samples_per_pass = self.samples_per_pass();
+
+		self.samples_per_pixel.max(1).div_ceil(samples_per_pass)
+	}
+
+	pub fn first_sample_in_pass(&self, pass: usize) -> usize {
+		pass * self.samples_per_pass()
+	}
+
+	pub fn samples_in_pass(&self, pass: usize) -> usize {
+		let samples_per_pass = self.samples_per_pass();
+		let remaining = self.samples_per_pixel.max(1).saturating_sub(pass * samples_per_pass);
+
+		remaining.min(samples_per_pass)
+	}
+
+	pub fn renders_aovs(&self) -> bool {
+		self.aovs || self.denoiser.is_some()
+	}
+}
+
+impl Default for RaytracerSettings {
+	fn default() -> Self {
+		Self {
+			width: 1200,
+			height: 800,
+			samples_per_pixel: 100,
+			max_bounces: 64,
+			russian_roulette_depth: 3,
+			integrator: Integrator::PathTracer,
+			generation_mode: GenerationMode::PixelRandom,
+			sampler: SamplerType::Independent,
+			filter: Filter::new(),
+			display: DisplaySettings::new(),
+			seed: 0,
+			progressive: None,
+			adaptive: None,
+			aovs: false,
+			denoiser: None
+		}
+	}
 }
\ No newline at end of file
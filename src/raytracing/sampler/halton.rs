@@ -0,0 +1,161 @@
+use std::sync::OnceLock;
+use crate::raytracing::sampler::{hash_seed, permutation_element, Sampler, SequenceState};
+
+// Dimensions past this many primes fall back to independent random numbers
+const MAX_HALTON_DIMENSIONS: usize = 1000;
+
+// One pixel's samples are the Halton sequence itself, every dimension Owen scrambled with a per pixel seed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HaltonSampler {
+	state: SequenceState
+}
+
+impl HaltonSampler {
+	pub fn new() -> Self {
+		Self::create(0)
+	}
+
+	pub fn create(seed: u64) -> Self {
+		Self {
+			state: SequenceState::create(seed)
+		}
+	}
+
+	fn sample(&mut self, dimension: Option<usize>) -> f64 {
+		let base = match dimension.and_then(|dimension| primes().get(dimension)) {
+			None => return self.state.fallback.next_f64(),
+			Some(base) => *base
+		};
+
+		let seed = self.state.dimension_seed(dimension.unwrap());
+
+		owen_scrambled_radical_inverse(base, self.state.sample_index as u64, seed)
+	}
+}
+
+impl Default for HaltonSampler {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Sampler for HaltonSampler {
+	fn start_sample(&mut self, x: u32, y: u32, sample_index: usize) {
+		self.state.start_sample(x, y, sample_index);
+	}
+
+	fn start_bounce(&mut self, bounce: usize) {
+		self.state.dimensions.start_bounce(bounce);
+	}
+
+	fn next_1d(&mut self) -> f64 {
+		let dimension = self.state.dimensions.next_1d();
+
+		self.sample(dimension)
+	}
+
+	fn next_2d(&mut self) -> (f64, f64) {
+		let dimension = self.state.dimensions.next_2d();
+
+		let x = self.sample(dimension);
+		let y = self.sample(dimension.map(|dimension| dimension + 1));
+
+		(x, y)
+	}
+}
+
+fn primes() -> &'static [u32] {
+	static PRIMES: OnceLock<Vec<u32>> = OnceLock::new();
+
+	PRIMES.get_or_init(|| {
+		let mut primes: Vec<u32> = Vec::with_capacity(MAX_HALTON_DIMENSIONS);
+		let mut candidate = 2;
+
+		while primes.len() < MAX_HALTON_DIMENSIONS {
+			if primes.iter().take_while(|prime| *prime * *prime <= candidate).all(|prime| candidate % prime != 0) {
+				primes.push(candidate);
+			}
+			candidate += 1;
+		}
+
+		primes
+	})
+}
+
+// Radical inverse where every digit is permuted depending on the digits before it, see Owen, "Randomly permuted (t,m,s)-nets and (t,s)-sequences".
+// All digits down to double precision are scrambled, the zero digits past the end of the index included, like pbrt's OwenScrambledRadicalInverse.
+fn owen_scrambled_radical_inverse(base: u32, index: u64, seed: u64) -> f64 {
+	let inverse_base = 1.0 / base as f64;
+	let mut inverse_base_power = 1.0;
+	let mut value = 0.0;
+	let mut prefix_seed = seed;
+	let mut remaining = index;
+
+	while 1.0 - inverse_base_power < 1.0 {
+		let next = remaining / base as u64;
+		let digit = (remaining - next * base as u64) as u32;
+		let digit = permutation_element(digit, base, prefix_seed as u32);
+
+		inverse_base_power *= inverse_base;
+		value += digit as f64 * inverse_base_power;
+		prefix_seed = hash_seed(prefix_seed, &[digit as u64]);
+		remaining = next;
+	}
+
+	value.min(1.0 - f64::EPSILON / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::raytracing::sampler::CAMERA_DIMENSIONS;
+
+	fn samples(sampler: &mut HaltonSampler, x: u32, y: u32, count: usize, dimensions: usize) -> Vec<Vec<f64>> {
+		(0..count).map(|index| {
+			sampler.start_sample(x, y, index);
+			(0..dimensions).map(|_| sampler.next_1d()).collect()
+		}).collect()
+	}
+
+	#[test]
+	fn samples_are_in_the_unit_interval() {
+		let mut sampler = HaltonSampler::create(7);
+
+		for sample in samples(&mut sampler, 3, 5, 1024, CAMERA_DIMENSIONS) {
+			assert!(sample.iter().all(|value| (0.0..1.0).contains(value)), "{:?}", sample);
+		}
+
+		for base in [2, 3, 7919] {
+			for index in [0, 1, u32::MAX as u64, u64::MAX] {
+				let value = owen_scrambled_radical_inverse(base, index, 11);
+				assert!((0.0..1.0).contains(&value), "{}", value);
+			}
+		}
+	}
+
+	#[test]
+	fn first_points_fill_every_interval_once() {
+		let mut sampler = HaltonSampler::create(42);
+
+		// Powers of the first primes with a few hundred points each
+		for (dimension, (base, digits)) in [(2, 8), (3, 5), (5, 3), (7, 3)].into_iter().enumerate() {
+			let count = (base as usize).pow(digits);
+			let mut hits = vec![0; count];
+
+			for sample in samples(&mut sampler, 1, 2, count, dimension + 1) {
+				hits[(sample[dimension] * count as f64) as usize] += 1;
+			}
+
+			assert!(hits.iter().all(|hit| *hit == 1), "base {} with {} digits", base, digits);
+		}
+	}
+
+	#[test]
+	fn the_same_seed_gives_the_same_sequence() {
+		let first = samples(&mut HaltonSampler::create(9), 4, 8, 64, CAMERA_DIMENSIONS);
+
+		assert_eq!(first, samples(&mut HaltonSampler::create(9), 4, 8, 64, CAMERA_DIMENSIONS));
+		assert_ne!(first, samples(&mut HaltonSampler::create(10), 4, 8, 64, CAMERA_DIMENSIONS));
+		assert_ne!(first, samples(&mut HaltonSampler::create(9), 5, 8, 64, CAMERA_DIMENSIONS));
+	}
+}
@@ -0,0 +1,37 @@
+use crate::raytracing::sampler::{hash_seed, Pcg32, Sampler};
+
+// Independent uniform random numbers, every sample gets its own PCG stream seeded from (seed, pixel, sample index)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IndependentSampler {
+	seed: u64,
+	rng: Pcg32
+}
+
+impl IndependentSampler {
+	pub fn new() -> Self {
+		Self::create(0)
+	}
+
+	pub fn create(seed: u64) -> Self {
+		Self {
+			seed,
+			rng: Pcg32::create(seed)
+		}
+	}
+}
+
+impl Default for IndependentSampler {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Sampler for IndependentSampler {
+	fn start_sample(&mut self, x: u32, y: u32, sample_index: usize) {
+		self.rng = Pcg32::create(hash_seed(self.seed, &[x as u64, y as u64, sample_index as u64]));
+	}
+
+	fn next_1d(&mut self) -> f64 {
+		self.rng.next_f64()
+	}
+}
\ No newline at end of file
@@ -0,0 +1,296 @@
+pub mod independent;
+pub mod stratified;
+pub mod halton;
+pub mod sobol;
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde_derive::{Serialize, Deserialize};
+use independent::IndependentSampler;
+use stratified::StratifiedSampler;
+use halton::HaltonSampler;
+use sobol::SobolSampler;
+
+// Source of all random numbers used while tracing a sample.
+// A sampler is positioned at one sample of one pixel, so images don't depend on which worker traces which pixel.
+pub trait Sampler {
+	fn start_sample(&mut self, x: u32, y: u32, sample_index: usize);
+
+	// Moves on to the dimensions reserved for `bounce`, no matter how many numbers earlier bounces used
+	fn start_bounce(&mut self, _bounce: usize) {}
+
+	// Uniform in [0, 1)
+	fn next_1d(&mut self) -> f64;
+
+	fn next_2d(&mut self) -> (f64, f64) {
+		let first = self.next_1d();
+		let second = self.next_1d();
+
+		(first, second)
+	}
+
+	fn next_range(&mut self, min: f64, max: f64) -> f64 {
+		min + (max - min) * self.next_1d()
+	}
+
+	fn next_index(&mut self, count: usize) -> usize {
+		((self.next_1d() * count as f64) as usize).min(count.saturating_sub(1))
+	}
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplerType {
+	#[default]
+	Independent,
+	Stratified,
+	Halton,
+	Sobol
+}
+
+impl SamplerType {
+	pub fn create_sampler(&self, seed: u64, samples_per_pixel: usize) -> Box<dyn Sampler> {
+		match self {
+			SamplerType::Independent => Box::new(IndependentSampler::create(seed)),
+			SamplerType::Stratified => Box::new(StratifiedSampler::create(seed, samples_per_pixel)),
+			SamplerType::Halton => Box::new(HaltonSampler::create(seed)),
+			SamplerType::Sobol => Box::new(SobolSampler::create(seed))
+		}
+	}
+}
+
+impl FromStr for SamplerType {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.trim() {
+			"independent" => Ok(SamplerType::Independent),
+			"stratified" => Ok(SamplerType::Stratified),
+			"halton" => Ok(SamplerType::Halton),
+			"sobol" => Ok(SamplerType::Sobol),
+			other => Err(format!("unknown sampler '{}'", other))
+		}
+	}
+}
+
+impl Display for SamplerType {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			SamplerType::Independent => "independent",
+			SamplerType::Stratified => "stratified",
+			SamplerType::Halton => "halton",
+			SamplerType::Sobol => "sobol"
+		};
+
+		write!(f, "{}", name)
+	}
+}
+
+// Pixel jitter and the lens
+pub const CAMERA_DIMENSIONS: usize = 4;
+// BSDF sample, light selection and light sample with some room for materials that need more
+pub const DIMENSIONS_PER_BOUNCE: usize = 8;
+
+// Hands out the dimensions of a sample, the camera and every bounce get a fixed block of them.
+// Numbers requested past the end of a block don't have a dimension and come from an independent stream.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SampleDimensions {
+	next: usize,
+	end: usize
+}
+
+impl SampleDimensions {
+	pub fn start_sample(&mut self) {
+		self.next = 0;
+		self.end = CAMERA_DIMENSIONS;
+	}
+
+	pub fn start_bounce(&mut self, bounce: usize) {
+		self.next = CAMERA_DIMENSIONS + bounce * DIMENSIONS_PER_BOUNCE;
+		self.end = self.next + DIMENSIONS_PER_BOUNCE;
+	}
+
+	pub fn next_1d(&mut self) -> Option<usize> {
+		if self.next >= self.end {
+			return None;
+		}
+
+		let dimension = self.next;
+		self.next += 1;
+
+		Some(dimension)
+	}
+
+	// Pairs start on an even dimension, so they line up with the 2D projections samplers are good at
+	pub fn next_2d(&mut self) -> Option<usize> {
+		let dimension = self.next + self.next % 2;
+
+		if dimension + 2 > self.end {
+			self.next = self.end;
+			return None;
+		}
+
+		self.next = dimension + 2;
+
+		Some(dimension)
+	}
+}
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+const PCG_INCREMENT: u64 = 1442695040888963407;
+
+// PCG32 (XSH RR), small and with the same output on every platform
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Pcg32 {
+	state: u64
+}
+
+impl Pcg32 {
+	pub fn new() -> Self {
+		Self::create(0)
+	}
+
+	pub fn create(seed: u64) -> Self {
+		let mut rng = Self {
+			state: 0
+		};
+
+		rng.next_u32();
+		rng.state = rng.state.wrapping_add(seed);
+		rng.next_u32();
+
+		rng
+	}
+
+	pub fn next_u32(&mut self) -> u32 {
+		let old_state = self.state;
+		self.state = old_state.wrapping_mul(PCG_MULTIPLIER).wrapping_add(PCG_INCREMENT);
+
+		let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+		let rotation = (old_state >> 59) as u32;
+
+		xor_shifted.rotate_right(rotation)
+	}
+
+	// Uniform in [0, 1) with the full 53 bits of precision
+	pub fn next_f64(&mut self) -> f64 {
+		let bits = ((self.next_u32() as u64) << 32) | self.next_u32() as u64;
+
+		to_unit_float(bits)
+	}
+
+	pub fn next_range(&mut self, min: f64, max: f64) -> f64 {
+		min + (max - min) * self.next_f64()
+	}
+}
+
+impl Default for Pcg32 {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// Stateless mixing of a seed with some coordinates, small input changes flip about half the output bits
+pub fn hash_seed(seed: u64, values: &[u64]) -> u64 {
+	values.iter().fold(split_mix(seed), |hash, value| split_mix(hash ^ value))
+}
+
+fn split_mix(value: u64) -> u64 {
+	let mut value = value.wrapping_add(0x9e3779b97f4a7c15);
+	value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+	value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+	value ^ (value >> 31)
+}
+
+// Uniform in [0, 1) from the upper 53 bits
+pub fn to_unit_float(bits: u64) -> f64 {
+	(bits >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+// Element `index` of a random permutation of 0..length chosen by `seed`, see Kensler, "Correlated Multi-Jittered Sampling"
+pub fn permutation_element(index: u32, length: u32, seed: u32) -> u32 {
+	let mut mask = length.saturating_sub(1);
+	mask |= mask >> 1;
+	mask |= mask >> 2;
+	mask |= mask >> 4;
+	mask |= mask >> 8;
+	mask |= mask >> 16;
+
+	let mut value = index;
+
+	loop {
+		value ^= seed;
+		value = value.wrapping_mul(0xe170893d);
+		value ^= seed >> 16;
+		value ^= (value & mask) >> 4;
+		value ^= seed >> 8;
+		value = value.wrapping_mul(0x0929eb3f);
+		value ^= seed >> 23;
+		value ^= (value & mask) >> 1;
+		value = value.wrapping_mul(1 | seed >> 27);
+		value = value.wrapping_mul(0x6935fa69);
+		value ^= (value & mask) >> 11;
+		value = value.wrapping_mul(0x74dcb303);
+		value ^= (value & mask) >> 2;
+		value = value.wrapping_mul(0x9e501cc3);
+		value ^= (value & mask) >> 2;
+		value = value.wrapping_mul(0xc860a3df);
+		value &= mask;
+		value ^= value >> 5;
+
+		if value < length {
+			break;
+		}
+	}
+
+	value.wrapping_add(seed) % length.max(1)
+}
+
+// Shared state of the samplers that fill dimensions from a sequence
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SequenceState {
+	pub seed: u64,
+	pub pixel_seed: u64,
+	pub sample_index: usize,
+	pub dimensions: SampleDimensions,
+	pub fallback: Pcg32
+}
+
+impl SequenceState {
+	pub fn create(seed: u64) -> Self {
+		Self {
+			seed,
+			pixel_seed: seed,
+			sample_index: 0,
+			dimensions: SampleDimensions::default(),
+			fallback: Pcg32::create(seed)
+		}
+	}
+
+	pub fn start_sample(&mut self, x: u32, y: u32, sample_index: usize) {
+		self.pixel_seed = hash_seed(self.seed, &[x as u64, y as u64]);
+		self.sample_index = sample_index;
+		self.dimensions.start_sample();
+		self.fallback = Pcg32::create(hash_seed(self.pixel_seed, &[sample_index as u64]));
+	}
+
+	// Seed for everything about one dimension of this pixel
+	pub fn dimension_seed(&self, dimension: usize) -> u64 {
+		hash_seed(self.pixel_seed, &[dimension as u64])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sampler_types_round_trip_through_strings() {
+		for sampler in [SamplerType::Independent, SamplerType::Stratified, SamplerType::Halton, SamplerType::Sobol] {
+			assert_eq!(sampler.to_string().parse::<SamplerType>(), Ok(sampler));
+		}
+
+		assert!("random".parse::<SamplerType>().is_err());
+	}
+}
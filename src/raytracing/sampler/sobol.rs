@@ -0,0 +1,208 @@
+use std::sync::OnceLock;
+use crate::raytracing::sampler::{hash_seed, Sampler, SequenceState};
+
+// Sobol points are generated four dimensions at a time, higher dimensions are padded with independently shuffled copies
+const SOBOL_DIMENSIONS: usize = 4;
+
+// Owen scrambled and shuffled Sobol, see Burley, "Practical Hash-based Owen Scrambling"
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SobolSampler {
+	state: SequenceState,
+	block: Option<usize>,
+	point: [u32; SOBOL_DIMENSIONS]
+}
+
+impl SobolSampler {
+	pub fn new() -> Self {
+		Self::create(0)
+	}
+
+	pub fn create(seed: u64) -> Self {
+		Self {
+			state: SequenceState::create(seed),
+			block: None,
+			point: [0; SOBOL_DIMENSIONS]
+		}
+	}
+
+	fn sample(&mut self, dimension: Option<usize>) -> f64 {
+		let dimension = match dimension {
+			None => return self.state.fallback.next_f64(),
+			Some(dimension) => dimension
+		};
+
+		let block = dimension / SOBOL_DIMENSIONS;
+
+		if self.block != Some(block) {
+			self.point = shuffled_scrambled_sobol(self.state.sample_index as u32, self.state.dimension_seed(block));
+			self.block = Some(block);
+		}
+
+		self.point[dimension % SOBOL_DIMENSIONS] as f64 * (1.0 / (1u64 << 32) as f64)
+	}
+}
+
+impl Default for SobolSampler {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Sampler for SobolSampler {
+	fn start_sample(&mut self, x: u32, y: u32, sample_index: usize) {
+		self.state.start_sample(x, y, sample_index);
+		self.block = None;
+	}
+
+	fn start_bounce(&mut self, bounce: usize) {
+		self.state.dimensions.start_bounce(bounce);
+	}
+
+	fn next_1d(&mut self) -> f64 {
+		let dimension = self.state.dimensions.next_1d();
+
+		self.sample(dimension)
+	}
+
+	fn next_2d(&mut self) -> (f64, f64) {
+		let dimension = self.state.dimensions.next_2d();
+
+		let x = self.sample(dimension);
+		let y = self.sample(dimension.map(|dimension| dimension + 1));
+
+		(x, y)
+	}
+}
+
+fn shuffled_scrambled_sobol(index: u32, seed: u64) -> [u32; SOBOL_DIMENSIONS] {
+	let index = nested_uniform_scramble(index, seed as u32);
+	let mut point = sobol(index);
+
+	for (dimension, value) in point.iter_mut().enumerate() {
+		*value = nested_uniform_scramble(*value, hash_seed(seed, &[dimension as u64]) as u32);
+	}
+
+	point
+}
+
+fn sobol(index: u32) -> [u32; SOBOL_DIMENSIONS] {
+	let directions = direction_numbers();
+	let mut point = [0; SOBOL_DIMENSIONS];
+
+	for bit in 0..32 {
+		if index & (1 << bit) == 0 {
+			continue;
+		}
+
+		for (value, dimension_directions) in point.iter_mut().zip(directions) {
+			*value ^= dimension_directions[bit];
+		}
+	}
+
+	point
+}
+
+// Owen scrambling of all 32 bits at once
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+	laine_karras_permutation(value.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(value: u32, seed: u32) -> u32 {
+	let mut value = value.wrapping_add(seed);
+	value ^= value.wrapping_mul(0x6c50b47c);
+	value ^= value.wrapping_mul(0xb82f1e52);
+	value ^= value.wrapping_mul(0xc7afe638);
+	value ^= value.wrapping_mul(0x8d22f6e6);
+
+	value
+}
+
+// Primitive polynomial degree, coefficients and initial direction numbers of the first dimensions, from Joe and Kuo
+const SOBOL_PARAMETERS: [(usize, u32, [u32; 3]); SOBOL_DIMENSIONS - 1] = [
+	(1, 0, [1, 0, 0]),
+	(2, 1, [1, 3, 0]),
+	(3, 1, [1, 3, 1])
+];
+
+fn direction_numbers() -> &'static [[u32; 32]; SOBOL_DIMENSIONS] {
+	static DIRECTIONS: OnceLock<[[u32; 32]; SOBOL_DIMENSIONS]> = OnceLock::new();
+
+	DIRECTIONS.get_or_init(|| {
+		let mut directions = [[0; 32]; SOBOL_DIMENSIONS];
+
+		// The first dimension is the van der Corput sequence
+		for (bit, direction) in directions[0].iter_mut().enumerate() {
+			*direction = 1 << (31 - bit);
+		}
+
+		for (dimension, (degree, coefficients, initial)) in SOBOL_PARAMETERS.iter().enumerate() {
+			let numbers = &mut directions[dimension + 1];
+
+			for bit in 0..32 {
+				if bit < *degree {
+					numbers[bit] = initial[bit] << (31 - bit);
+					continue;
+				}
+
+				let mut number = numbers[bit - degree] ^ (numbers[bit - degree] >> degree);
+
+				for term in 1..*degree {
+					if (coefficients >> (degree - 1 - term)) & 1 == 1 {
+						number ^= numbers[bit - term];
+					}
+				}
+
+				numbers[bit] = number;
+			}
+		}
+
+		directions
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::raytracing::sampler::CAMERA_DIMENSIONS;
+
+	fn samples(sampler: &mut SobolSampler, x: u32, y: u32, count: usize) -> Vec<Vec<f64>> {
+		(0..count).map(|index| {
+			sampler.start_sample(x, y, index);
+			(0..CAMERA_DIMENSIONS).map(|_| sampler.next_1d()).collect()
+		}).collect()
+	}
+
+	#[test]
+	fn samples_are_in_the_unit_interval() {
+		let mut sampler = SobolSampler::create(7);
+
+		for sample in samples(&mut sampler, 3, 5, 1024) {
+			assert!(sample.iter().all(|value| (0.0..1.0).contains(value)), "{:?}", sample);
+		}
+	}
+
+	#[test]
+	fn first_points_fill_every_interval_once() {
+		let mut sampler = SobolSampler::create(42);
+		let points = samples(&mut sampler, 1, 2, 256);
+
+		for dimension in 0..CAMERA_DIMENSIONS {
+			let mut hits = [0; 256];
+
+			for point in &points {
+				hits[(point[dimension] * 256.0) as usize] += 1;
+			}
+
+			assert!(hits.iter().all(|hit| *hit == 1), "dimension {}", dimension);
+		}
+	}
+
+	#[test]
+	fn the_same_seed_gives_the_same_sequence() {
+		let first = samples(&mut SobolSampler::create(9), 4, 8, 64);
+
+		assert_eq!(first, samples(&mut SobolSampler::create(9), 4, 8, 64));
+		assert_ne!(first, samples(&mut SobolSampler::create(10), 4, 8, 64));
+		assert_ne!(first, samples(&mut SobolSampler::create(9), 5, 8, 64));
+	}
+}
@@ -0,0 +1,84 @@
+use crate::raytracing::sampler::{hash_seed, permutation_element, Sampler, SequenceState, to_unit_float};
+
+// Jittered strata, every dimension of a pixel visits its strata in its own random order.
+// Only stratifies well when all `samples_per_pixel` samples of a pixel get taken.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StratifiedSampler {
+	state: SequenceState,
+	samples_per_pixel: usize,
+	columns: usize,
+	rows: usize
+}
+
+impl StratifiedSampler {
+	pub fn new() -> Self {
+		Self::create(0, 1)
+	}
+
+	pub fn create(seed: u64, samples_per_pixel: usize) -> Self {
+		let samples_per_pixel = samples_per_pixel.max(1);
+		let columns = ((samples_per_pixel as f64).sqrt().ceil() as usize).max(1);
+		let rows = samples_per_pixel.div_ceil(columns);
+
+		Self {
+			state: SequenceState::create(seed),
+			samples_per_pixel,
+			columns,
+			rows
+		}
+	}
+
+	fn stratum(&self, dimension: usize, stratum_count: usize) -> usize {
+		let seed = self.state.dimension_seed(dimension) as u32;
+		let index = self.state.sample_index % stratum_count;
+
+		permutation_element(index as u32, stratum_count as u32, seed) as usize
+	}
+
+	fn jitter(&self, dimension: usize) -> f64 {
+		to_unit_float(hash_seed(self.state.dimension_seed(dimension), &[self.state.sample_index as u64]))
+	}
+}
+
+impl Default for StratifiedSampler {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Sampler for StratifiedSampler {
+	fn start_sample(&mut self, x: u32, y: u32, sample_index: usize) {
+		self.state.start_sample(x, y, sample_index);
+	}
+
+	fn start_bounce(&mut self, bounce: usize) {
+		self.state.dimensions.start_bounce(bounce);
+	}
+
+	fn next_1d(&mut self) -> f64 {
+		let dimension = match self.state.dimensions.next_1d() {
+			None => return self.state.fallback.next_f64(),
+			Some(dimension) => dimension
+		};
+
+		let stratum = self.stratum(dimension, self.samples_per_pixel);
+
+		(stratum as f64 + self.jitter(dimension)) / self.samples_per_pixel as f64
+	}
+
+	fn next_2d(&mut self) -> (f64, f64) {
+		let dimension = match self.state.dimensions.next_2d() {
+			None => return (self.state.fallback.next_f64(), self.state.fallback.next_f64()),
+			Some(dimension) => dimension
+		};
+
+		let stratum = self.stratum(dimension, self.columns * self.rows);
+		let column = stratum % self.columns;
+		let row = stratum / self.columns;
+
+		let x = (column as f64 + self.jitter(dimension)) / self.columns as f64;
+		let y = (row as f64 + self.jitter(dimension + 1)) / self.rows as f64;
+
+		(x, y)
+	}
+}
\ No newline at end of file
@@ -1,122 +1,484 @@
-use rand::{Rng, thread_rng};
-use std::sync::Arc;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use crate::raytracing::aabb::Aabb;
+use crate::raytracing::background::Background;
+use crate::raytracing::bokeh::{ApertureImage, Bokeh};
 use crate::raytracing::camera::Camera;
-use crate::raytracing::color::Color;
+use crate::raytracing::environment::EnvironmentMap;
 use crate::raytracing::hit_record::HitRecord;
+use crate::raytracing::hittable::bvh::{Bvh, BvhStatistics};
 use crate::raytracing::hittable::Hittable;
-use crate::raytracing::hittable::sphere::Sphere;
-use crate::raytracing::materials::dielectric::Dielectric;
-use crate::raytracing::materials::lambertian::Lambertian;
-use crate::raytracing::materials::metal::Metal;
+use crate::raytracing::hittable::triangle_mesh::{MeshTriangle, TriangleMesh};
+use crate::raytracing::materials::Material;
 use crate::raytracing::ray::Ray;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::scene_description::{BackgroundDescription, BokehDescription, CameraDescription, SceneDescription, SceneError};
+use crate::raytracing::vector_2d::Vec2;
 use crate::raytracing::vector_3d::Vec3;
 
+// Direction from the shaded point towards a light, background samples are infinitely far away
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LightSample {
+    pub direction: Vec3,
+    pub distance: f64,
+    pub pdf: f64
+}
+
 #[derive(Default, Clone)]
 pub struct RaytracingScene {
     pub camera: Camera,
-    pub hittables: Vec<Arc<dyn Hittable + 'static + Send + Sync>>
+    pub background: Background,
+    // Private so the caches below can't go stale, changed through `add_hittable` and `remove_hittable`
+    hittables: Vec<Arc<dyn Hittable + 'static + Send + Sync>>,
+    // Makes the scene's BVH count traced rays for `bvh_statistics`, off by default since every ray would write to it
+    collect_bvh_statistics: bool,
+    bvh: OnceLock<Arc<Bvh>>,
+    lights: OnceLock<Vec<usize>>,
+    // Material IDs by the address of the shared material, numbered in the order the hittables use them
+    material_ids: OnceLock<HashMap<usize, usize>>,
+    // What the scene was built from, the names keep the materials of the description apart from ones added later
+    description: Option<SceneDescription>,
+    material_names: Vec<(Arc<dyn Material + Send + Sync>, String)>,
+    hittables_changed: bool,
+    // The images the description loaded, only their paths in it can be written back
+    loaded_bokeh_image: Option<Arc<ApertureImage>>,
+    loaded_environment: Option<Arc<EnvironmentMap>>
 }
 
 impl RaytracingScene {
     pub fn new() -> Self {
         Self {
             camera: Camera::new(),
-            hittables: vec![]
+            background: Background::sky(),
+            hittables: vec![],
+            collect_bvh_statistics: false,
+            bvh: OnceLock::new(),
+            lights: OnceLock::new(),
+            material_ids: OnceLock::new(),
+            description: None,
+            material_names: vec![],
+            hittables_changed: false,
+            loaded_bokeh_image: None,
+            loaded_environment: None
         }
     }
-    
-    pub fn create_scene(aspect_ratio: f64) -> Self {
-        let mut rand = thread_rng();
-
-        let mut hittables: Vec<Arc<dyn Hittable + Send + Sync + 'static>> = vec![];
-
-        let ground_material = Arc::new(Lambertian::create(Color::create(0.5,0.5,0.5,1.0)));
-        let ground_sphere = Arc::new(Sphere::create(Vec3::create(0.0, -1000.0, 0.0), 1000.0, ground_material));
-        hittables.push(ground_sphere);
-
-        let center_material = Arc::new(Dielectric::create(1.5));
-        let center_sphere = Arc::new(Sphere::create(Vec3::create(0.0, 1.0, 0.0), 1.0, center_material));
-        hittables.push(center_sphere);
-
-        let left_material = Arc::new(Lambertian::create(Color::create(0.4,0.2,0.1,1.0)));
-        let left_sphere = Arc::new(Sphere::create(Vec3::create(-4.0, 1.0, 0.0), 1.0, left_material));
-        hittables.push(left_sphere);
-
-        let right_material = Arc::new(Metal::create(Color::create(0.7,0.6,0.5,1.0), 0.0));
-        let right_sphere = Arc::new(Sphere::create(Vec3::create(4.0, 1.0, 0.0), 1.0, right_material));
-        hittables.push(right_sphere);
-
-        for a in -11..11 {
-            for b in -11..11 {
-                let choose_mat = rand.gen_range(0.0..1.0);
-                let center = Vec3::create(a as f64 + 0.9 * rand.gen_range(0.0..1.0), 0.2, b as f64 + 0.9 * rand.gen_range(0.0..1.0));
-
-                if (center - Vec3::create(4.0,0.2,0.0)).length() > 0.9 {
-                    if choose_mat < 0.8 {
-                        let albedo = Color {
-                            r: rand.gen_range(0.0..=1.0) * rand.gen_range(0.0..1.0),
-                            g: rand.gen_range(0.0..=1.0) * rand.gen_range(0.0..1.0),
-                            b: rand.gen_range(0.0..=1.0) * rand.gen_range(0.0..1.0),
-                            a: 1.0
-                        };
-                        let sphere_material = Arc::new(Lambertian::create(albedo));
-                        let sphere = Arc::new(Sphere::create(center, 0.2, sphere_material));
-                        hittables.push(sphere);
-                    }
-                    else if choose_mat < 0.95 {
-                        let albedo = Color {
-                            r: rand.gen_range(0.5..=1.0),
-                            g: rand.gen_range(0.5..=1.0),
-                            b: rand.gen_range(0.5..=1.0),
-                            a: 1.0,
-                        };
-                        let fuzz = rand.gen_range(0.0..=0.5);
-                        let sphere_material = Arc::new(Metal::create(albedo, fuzz));
-                        let sphere = Arc::new(Sphere::create(center, 0.2, sphere_material));
-                        hittables.push(sphere);
-                    }
-                    else {
-                        let sphere_material = Arc::new(Dielectric::create(1.5));
-                        let sphere = Arc::new(Sphere::create(center, 0.2, sphere_material));
-                        hittables.push(sphere);
+
+    pub fn hittables(&self) -> &[Arc<dyn Hittable + 'static + Send + Sync>] {
+        &self.hittables
+    }
+
+    pub fn add_hittable(&mut self, hittable: Arc<dyn Hittable + 'static + Send + Sync>) {
+        self.hittables.push(hittable);
+        self.invalidate_bvh();
+    }
+
+    pub fn remove_hittable(&mut self, index: usize) -> Arc<dyn Hittable + 'static + Send + Sync> {
+        let hittable = self.hittables.remove(index);
+        self.invalidate_bvh();
+
+        hittable
+    }
+
+    pub fn set_collect_bvh_statistics(&mut self, collect_bvh_statistics: bool) {
+        self.collect_bvh_statistics = collect_bvh_statistics;
+        self.bvh = OnceLock::new();
+    }
+
+    fn invalidate_bvh(&mut self) {
+        self.bvh = OnceLock::new();
+        self.lights = OnceLock::new();
+        self.material_ids = OnceLock::new();
+        self.hittables_changed = true;
+    }
+
+    pub fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let bounds: Vec<Aabb> = self.hittables.iter()
+                .map(|hittable| hittable.bounding_box())
+                .collect();
+
+            Arc::new(Bvh::build_with_statistics(&bounds, self.collect_bvh_statistics))
+        })
+    }
+
+    // Rays are only counted after `set_collect_bvh_statistics(true)`
+    pub fn bvh_statistics(&self) -> BvhStatistics {
+        self.bvh().statistics()
+    }
+
+    pub fn lights(&self) -> &[usize] {
+        self.lights.get_or_init(|| {
+            self.hittables.iter()
+                .enumerate()
+                .filter(|(_, hittable)| hittable.is_emissive())
+                .map(|(index, _)| index)
+                .collect()
+        })
+    }
+
+    pub fn material_id(&self, material: &Arc<dyn Material + Send + Sync>) -> Option<usize> {
+        let material_ids = self.material_ids.get_or_init(|| {
+            let mut material_ids = HashMap::new();
+
+            for material in self.hittables.iter().flat_map(|hittable| hittable.materials()) {
+                let next_id = material_ids.len();
+                material_ids.entry(material_address(&material)).or_insert(next_id);
+            }
+
+            material_ids
+        });
+
+        material_ids.get(&material_address(material)).copied()
+    }
+
+    // Picks one of the emissive hittables or the background uniformly, then a direction towards it
+    pub fn sample_light(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let lights = self.lights();
+
+        let light_count = self.light_count();
+        if light_count == 0 {
+            return None;
+        }
+
+        let light = sampler.next_index(light_count);
+
+        if light == lights.len() {
+            let (direction, pdf) = self.background.sample(sampler)?;
+
+            return Some(LightSample {
+                direction,
+                distance: f64::INFINITY,
+                pdf: pdf / light_count as f64
+            });
+        }
+
+        let sample = self.hittables[lights[light]].sample_surface(origin, sampler)?;
+
+        let to_light = sample.point - origin;
+        let distance = to_light.length();
+
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            pdf: sample.pdf / light_count as f64
+        })
+    }
+
+    pub fn light_pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        let lights = self.lights();
+        if lights.is_empty() {
+            return 0.0;
+        }
+
+        let pdf_sum: f64 = lights.iter()
+            .map(|light| self.hittables[*light].pdf_value(origin, direction))
+            .sum();
+
+        pdf_sum / self.light_count() as f64
+    }
+
+    // Pdf of `sample_light` picking `direction` towards the background, 0 if it isn't sampled
+    pub fn background_pdf(&self, direction: Vec3) -> f64 {
+        if self.background.can_be_sampled() {
+            self.background.pdf(direction) / self.light_count() as f64
+        }
+        else {
+            0.0
+        }
+    }
+
+    fn light_count(&self) -> usize {
+        self.lights().len() + usize::from(self.background.can_be_sampled())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SceneError> {
+        SceneDescription::load(path)?.build()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SceneError> {
+        self.to_description()?.save(path)
+    }
+
+    // The description the scene was built from, without the hittables added or removed since, see `to_description`
+    pub fn description(&self) -> Option<&SceneDescription> {
+        self.description.as_ref()
+    }
+
+    // Called once the camera and background are built from `description`
+    pub(crate) fn set_description(&mut self, description: SceneDescription, material_names: Vec<(Arc<dyn Material + Send + Sync>, String)>) {
+        self.loaded_bokeh_image = match self.camera.bokeh() {
+            Bokeh::Image(image) => Some(image.clone()),
+            _ => None
+        };
+        self.loaded_environment = match &self.background {
+            Background::Environment(environment) => Some(environment.clone()),
+            _ => None
+        };
+
+        self.description = Some(description);
+        self.material_names = material_names;
+        self.hittables_changed = false;
+    }
+
+    // The camera and background are always written as they are now. The objects come from the description the scene
+    // was built from while no hittables were added or removed, so file paths survive, and from the hittables otherwise.
+    // The render settings are the ones of the description.
+    pub fn to_description(&self) -> Result<SceneDescription, SceneError> {
+        let mut description = self.description.clone().unwrap_or_default();
+
+        description.camera = CameraDescription::from_camera(&self.camera, self.bokeh_description()?);
+        description.background = self.background_description()?;
+
+        if self.description.is_some() && !self.hittables_changed {
+            return Ok(description);
+        }
+
+        let mut names: HashMap<usize, String> = self.material_names.iter()
+            .map(|(material, name)| (material_address(material), name.clone()))
+            .collect();
+
+        description.objects = Vec::with_capacity(self.hittables.len());
+
+        for (index, hittable) in self.hittables.iter().enumerate() {
+            let not_describable = || SceneError::NotDescribable {
+                message: format!("object {} has no scene file equivalent", index)
+            };
+
+            let material = hittable.materials().into_iter().next().ok_or_else(not_describable)?;
+
+            let name = match names.get(&material_address(&material)) {
+                Some(name) => name.clone(),
+                None => {
+                    let material_description = material.to_description().ok_or_else(|| SceneError::NotDescribable {
+                        message: format!("the material of object {} has no scene file equivalent", index)
+                    })?;
+
+                    let mut name = format!("material_{}", names.len());
+                    while description.materials.contains_key(&name) {
+                        name.push('_');
                     }
+
+                    description.materials.insert(name.clone(), material_description);
+                    names.insert(material_address(&material), name.clone());
+                    name
                 }
-            }
+            };
+
+            description.objects.push(hittable.to_description(name).ok_or_else(not_describable)?);
         }
 
-        let look_from = Vec3::create(13.0,2.0,3.0);
-        let look_at = Vec3::create(0.0,0.0,0.0);
-        let focus_distance = 10.0;
-
-        let camera = Camera::create(
-            look_from,
-            look_at,
-            Vec3::create(0.0, 1.0, 0.0),
-            20.0,
-            aspect_ratio,
-            focus_distance,
-            0.01
-        );
-
-        RaytracingScene {
-            camera,
-            hittables
+        Ok(description)
+    }
+
+    // Images only keep their pixels, the ones still in use since loading the description are written with its paths
+    fn bokeh_description(&self) -> Result<BokehDescription, SceneError> {
+        match (self.camera.bokeh(), &self.loaded_bokeh_image, &self.description) {
+            (Bokeh::Image(image), Some(loaded), Some(description)) if Arc::ptr_eq(image, loaded) => Ok(description.camera.bokeh.clone()),
+            (bokeh, _, _) => BokehDescription::from_bokeh(bokeh)
         }
     }
+
+    fn background_description(&self) -> Result<BackgroundDescription, SceneError> {
+        match (&self.background, &self.loaded_environment, &self.description) {
+            (Background::Environment(environment), Some(loaded), Some(description)) if Arc::ptr_eq(environment, loaded) => Ok(description.background.clone()),
+            (background, _, _) => BackgroundDescription::from_background(background).ok_or_else(|| SceneError::NotDescribable {
+                message: "the environment map wasn't loaded from a scene file".to_string()
+            })
+        }
+    }
+
+    pub fn create_scene(aspect_ratio: f64) -> Self {
+        let mut description = SceneDescription::random_spheres();
+        description.camera.aspect_ratio = Some(aspect_ratio);
+
+        description.build().unwrap()
+    }
+
+    pub fn create_cornell_box(aspect_ratio: f64) -> Self {
+        let mut description = SceneDescription::cornell_box();
+        description.camera.aspect_ratio = Some(aspect_ratio);
+
+        description.build().unwrap()
+    }
+}
+
+fn material_address(material: &Arc<dyn Material + Send + Sync>) -> usize {
+    Arc::as_ptr(material) as *const () as usize
+}
+
+pub fn create_quad(corner: Vec3, u: Vec3, v: Vec3, material: Arc<dyn Material + Send + Sync>) -> TriangleMesh {
+    let vertices = vec![corner, corner + u, corner + u + v, corner + v];
+    let triangles = vec![
+        MeshTriangle { vertices: [0, 1, 2], normals: None, uvs: Some([0, 1, 2]) },
+        MeshTriangle { vertices: [0, 2, 3], normals: None, uvs: Some([0, 2, 3]) }
+    ];
+
+    TriangleMesh::create(vertices, vec![], quad_uvs(), triangles, material)
+}
+
+pub fn create_box(size: Vec3, rotation_y: f64, offset: Vec3, material: Arc<dyn Material + Send + Sync>) -> TriangleMesh {
+    let (sin, cos) = rotation_y.to_radians().sin_cos();
+
+    let mut vertices = Vec::with_capacity(8);
+    for corner in 0..8 {
+        let x = if corner & 1 == 0 { 0.0 } else { size.x };
+        let y = if corner & 2 == 0 { 0.0 } else { size.y };
+        let z = if corner & 4 == 0 { 0.0 } else { size.z };
+
+        vertices.push(Vec3::create(cos * x + sin * z, y, -sin * x + cos * z) + offset);
+    }
+
+    let faces: [[usize; 4]; 6] = [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3]
+    ];
+
+    // Every face shows the whole texture
+    let mut triangles = Vec::with_capacity(12);
+    for [a, b, c, d] in faces {
+        triangles.push(MeshTriangle { vertices: [a, b, c], normals: None, uvs: Some([0, 1, 2]) });
+        triangles.push(MeshTriangle { vertices: [a, c, d], normals: None, uvs: Some([0, 2, 3]) });
+    }
+
+    TriangleMesh::create(vertices, vec![], quad_uvs(), triangles, material)
+}
+
+fn quad_uvs() -> Vec<Vec2> {
+    vec![Vec2::create(0.0, 0.0), Vec2::create(1.0, 0.0), Vec2::create(1.0, 1.0), Vec2::create(0.0, 1.0)]
 }
 
 impl Hittable for RaytracingScene {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let mut hit_record = None;
-        let mut closest_t = t_max;
+        self.bvh().hit(ray, t_min, t_max, |index, ray, t_min, t_max| {
+            self.hittables[index].hit(ray, t_min, t_max).map(|hit_record| HitRecord {
+                object_index: Some(index),
+                ..hit_record
+            })
+        })
+    }
 
-        for hittable in &self.hittables {
-            if let Some(new_record) = hittable.hit(ray, t_min, closest_t) {
-                closest_t = new_record.t;
-                hit_record = Some(new_record);
-            }
-        }
+    fn bounding_box(&self) -> Aabb {
+        self.bvh().bounds()
+    }
+
+    fn hit_counted(&self, ray: &Ray, t_min: f64, t_max: f64, nodes_visited: &mut usize) -> Option<HitRecord> {
+        // The closure can't borrow `nodes_visited` while the BVH counts into it
+        let primitive_nodes_visited = Cell::new(0);
 
+        let hit_record = self.bvh().hit_counted(ray, t_min, t_max, nodes_visited, |index, ray, t_min, t_max| {
+            let mut nodes_visited = 0;
+
+            let hit_record = self.hittables[index].hit_counted(ray, t_min, t_max, &mut nodes_visited).map(|hit_record| HitRecord {
+                object_index: Some(index),
+                ..hit_record
+            });
+
+            primitive_nodes_visited.set(primitive_nodes_visited.get() + nodes_visited);
+            hit_record
+        });
+
+        *nodes_visited += primitive_nodes_visited.get();
         hit_record
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::raytracing::color::Color;
+    use crate::raytracing::hittable::sphere::Sphere;
+    use crate::raytracing::hittable::triangle::Triangle;
+    use crate::raytracing::materials::dielectric::Dielectric;
+    use crate::raytracing::materials::lambertian::Lambertian;
+    use crate::raytracing::sampler::independent::IndependentSampler;
+    use crate::raytracing::sampler::Pcg32;
+    use crate::raytracing::scene_description::ObjectDescription;
+    use super::*;
+
+    fn random_vec3(rng: &mut Pcg32, min: f64, max: f64) -> Vec3 {
+        Vec3::create(rng.next_range(min, max), rng.next_range(min, max), rng.next_range(min, max))
+    }
+
+    #[test]
+    fn scenes_without_a_description_round_trip() {
+        let glass: Arc<dyn Material + Send + Sync> = Arc::new(Dielectric::create(1.5));
+        let red: Arc<dyn Material + Send + Sync> = Arc::new(Lambertian::create(Color::create(0.8, 0.1, 0.1, 1.0)));
+
+        let mut scene = RaytracingScene::new();
+        scene.camera = Camera::create(Vec3::create(1.0, 2.0, 5.0), Vec3::create(0.0, 0.5, 0.0), Vec3::create(0.0, 1.0, 0.0), 35.0, 1.5, 4.0, 0.1);
+        scene.add_hittable(Arc::new(Sphere::create(Vec3::create(0.0, 1.0, 0.0), 1.0, glass.clone())));
+        scene.add_hittable(Arc::new(Sphere::create(Vec3::create(2.0, 0.5, 0.0), 0.5, glass)));
+        scene.add_hittable(Arc::new(Triangle::create([Vec3::create(-3.0, 0.0, -3.0), Vec3::create(3.0, 0.0, -3.0), Vec3::create(0.0, 3.0, -3.0)], red.clone())));
+        scene.add_hittable(Arc::new(create_box(Vec3::create(1.0, 1.0, 1.0), 30.0, Vec3::create(-2.0, 0.0, 1.0), red)));
+
+        let description = scene.to_description().unwrap();
+        assert_eq!(description.materials.len(), 2);
+        assert!(matches!(description.objects[3], ObjectDescription::Mesh { .. }));
+
+        let rebuilt = description.build().unwrap();
+        assert_eq!(rebuilt.hittables().len(), scene.hittables().len());
+        // The camera is compared by its rays below
+        let rewritten = rebuilt.to_description().unwrap();
+        assert_eq!(rewritten.materials, description.materials);
+        assert_eq!(rewritten.objects, description.objects);
+
+        let mut rng = Pcg32::create(11);
+        for _ in 0..500 {
+            let ray = Ray::create(random_vec3(&mut rng, -5.0, 5.0), random_vec3(&mut rng, -1.0, 1.0));
+
+            let expected = scene.hit(&ray, 0.001, f64::INFINITY).map(|hit_record| hit_record.t);
+            let actual = rebuilt.hit(&ray, 0.001, f64::INFINITY).map(|hit_record| hit_record.t);
+            assert_eq!(expected, actual);
+        }
+
+        for (u, v) in [(0.0, 0.0), (0.5, 0.5), (1.0, 0.25)] {
+            let expected = scene.camera.cast_ray(u, v, &mut IndependentSampler::create(1));
+            let actual = rebuilt.camera.cast_ray(u, v, &mut IndependentSampler::create(1));
+            assert!((expected.origin - actual.origin).length() < 1e-9);
+            assert!((expected.direction - actual.direction).length() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn adding_hittables_keeps_the_description() {
+        let mut scene = RaytracingScene::create_cornell_box(1.0);
+        let original = scene.description().unwrap().clone();
+
+        scene.add_hittable(Arc::new(Sphere::create(Vec3::create(190.0, 90.0, 190.0), 90.0, Arc::new(Dielectric::create(1.5)))));
+
+        assert_eq!(scene.description(), Some(&original));
+
+        let description = scene.to_description().unwrap();
+        assert_eq!(description.camera.to_camera(1.0, Path::new("")).unwrap(), scene.camera);
+        assert_eq!(description.objects.len(), scene.hittables().len());
+        assert_eq!(description.materials.len(), original.materials.len() + 1);
+        assert!(original.materials.iter().all(|(name, material)| description.materials.get(name) == Some(material)));
+
+        scene.remove_hittable(scene.hittables().len() - 1);
+        assert_eq!(scene.to_description().unwrap().materials, original.materials);
+    }
+
+    #[test]
+    fn saving_writes_the_current_camera_and_background() {
+        let path = std::env::temp_dir().join(format!("raytrayce_{}_moved_camera.json", std::process::id()));
+        SceneDescription::cornell_box().save(&path).unwrap();
+
+        let mut scene = RaytracingScene::load(&path).unwrap();
+        scene.camera = Camera::create(Vec3::create(100.0, 300.0, -600.0), Vec3::create(278.0, 200.0, 0.0), Vec3::create(0.0, 1.0, 0.0), 50.0, 1.0, 600.0, 0.0);
+        scene.background = Background::sky();
+        scene.save(&path).unwrap();
+
+        let saved = SceneDescription::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved.camera.position, [100.0, 300.0, -600.0]);
+        assert!((saved.camera.to_camera(1.0, Path::new("")).unwrap().vertical_field_of_view() - 50.0).abs() < 1e-9);
+        assert_eq!(saved.background, BackgroundDescription::default());
+        assert_eq!(saved.objects, SceneDescription::cornell_box().objects);
+    }
+}
@@ -0,0 +1,1032 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use image::ImageError;
+use serde_derive::{Serialize, Deserialize};
+use crate::image::load_texture;
+use crate::raytracing::background::Background;
+use crate::raytracing::bokeh::{ApertureImage, Bokeh};
+use crate::raytracing::camera::{Camera, Lens};
+use crate::raytracing::color::Color;
+use crate::raytracing::environment::EnvironmentMap;
+use crate::raytracing::filter::Filter;
+use crate::raytracing::hittable::sphere::Sphere;
+use crate::raytracing::hittable::triangle::Triangle;
+use crate::raytracing::hittable::triangle_mesh::{MeshTriangle, TriangleMesh};
+use crate::raytracing::integrator::Integrator;
+use crate::raytracing::materials::dielectric::Dielectric;
+use crate::raytracing::materials::diffuse_light::DiffuseLight;
+use crate::raytracing::materials::lambertian::Lambertian;
+use crate::raytracing::materials::Material;
+use crate::raytracing::materials::metal::Metal;
+use crate::raytracing::raytracer::{AdaptiveSettings, ProgressiveSettings, RaytracerSettings};
+use crate::raytracing::sampler::{Pcg32, SamplerType};
+use crate::raytracing::scene::{create_box, create_quad, RaytracingScene};
+use crate::raytracing::sky::PhysicalSky;
+use crate::raytracing::texture::TextureWrapMode;
+use crate::raytracing::texture_sampling::{MipMap, TextureFilter};
+use crate::raytracing::tone_mapping::{DisplaySettings, ToneMapping};
+use crate::raytracing::vector_2d::Vec2;
+use crate::raytracing::vector_3d::Vec3;
+use crate::raytracing::wavefront::obj::load_obj;
+use crate::raytracing::wavefront::WavefrontError;
+use crate::raytracing::work::generator::GenerationMode;
+
+const RANDOM_SPHERES_SEED: u64 = 42;
+
+#[derive(Debug)]
+pub enum SceneError {
+	Io {
+		path: PathBuf,
+		error: std::io::Error
+	},
+	Json {
+		path: PathBuf,
+		error: serde_json::Error
+	},
+	Wavefront(WavefrontError),
+	Texture {
+		path: PathBuf,
+		error: ImageError
+	},
+	UnknownMaterial {
+		name: String
+	},
+	InvalidRenderSettings {
+		message: String
+	},
+	InvalidCamera {
+		message: String
+	},
+	InvalidObject {
+		message: String
+	},
+	// Parts of a scene that a scene file can't hold, like textures or environment maps that weren't loaded from one
+	NotDescribable {
+		message: String
+	}
+}
+
+impl Display for SceneError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SceneError::Io { path, error } => {
+				write!(f, "{}: {}", path.display(), error)
+			}
+			SceneError::Json { path, error } => {
+				write!(f, "{}: {}", path.display(), error)
+			}
+			SceneError::Wavefront(error) => {
+				write!(f, "{}", error)
+			}
+			SceneError::Texture { path, error } => {
+				write!(f, "{}: {}", path.display(), error)
+			}
+			SceneError::UnknownMaterial { name } => {
+				write!(f, "unknown material '{}'", name)
+			}
+			SceneError::InvalidRenderSettings { message } => {
+				write!(f, "invalid render settings: {}", message)
+			}
+			SceneError::InvalidCamera { message } => {
+				write!(f, "invalid camera: {}", message)
+			}
+			SceneError::InvalidObject { message } => {
+				write!(f, "invalid object: {}", message)
+			}
+			SceneError::NotDescribable { message } => {
+				write!(f, "can't write the scene to a scene file: {}", message)
+			}
+		}
+	}
+}
+
+impl std::error::Error for SceneError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			SceneError::Io { error, .. } => Some(error),
+			SceneError::Json { error, .. } => Some(error),
+			SceneError::Wavefront(error) => Some(error),
+			SceneError::Texture { error, .. } => Some(error),
+			_ => None
+		}
+	}
+}
+
+impl From<WavefrontError> for SceneError {
+	fn from(error: WavefrontError) -> Self {
+		SceneError::Wavefront(error)
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraDescription {
+	pub position: [f64; 3],
+	pub look_at: [f64; 3],
+	pub up: [f64; 3],
+	// In millimeters, see `Lens`. Without it or `vertical_field_of_view` the camera sees 90° vertically, like it always did
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub focal_length: Option<f64>,
+	pub sensor_width: f64,
+	pub sensor_height: f64,
+	// 0, or leaving it out, keeps everything in focus
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub f_stop: Option<f64>,
+	// The distance to `look_at` when not set
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub focus_distance: Option<f64>,
+	pub bokeh: BokehDescription,
+	// Taken from the render size when not set
+	pub aspect_ratio: Option<f64>,
+	// Older scene files set these instead of the focal length and f-stop, the aperture is in scene units.
+	// Setting both a field of view and a focal length, or an aperture and an f-stop, is an error
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub vertical_field_of_view: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub aperture: Option<f64>
+}
+
+impl CameraDescription {
+	// Image paths are relative to `base_directory`
+	pub fn to_camera(&self, aspect_ratio: f64, base_directory: &Path) -> Result<Camera, SceneError> {
+		let position = to_vec3(self.position);
+		let look_at = to_vec3(self.look_at);
+		let aspect_ratio = self.aspect_ratio.unwrap_or(aspect_ratio);
+
+		if !(self.focal_length.is_none_or(|focal_length| focal_length > 0.0) && self.sensor_width > 0.0 && self.sensor_height > 0.0) {
+			return Err(SceneError::InvalidCamera {
+				message: "focal_length, sensor_width and sensor_height must be greater than 0".to_string()
+			});
+		}
+
+		if self.f_stop.is_some_and(|f_stop| f_stop < 0.0 || f_stop.is_nan()) {
+			return Err(SceneError::InvalidCamera {
+				message: "f_stop must not be negative".to_string()
+			});
+		}
+
+		if self.vertical_field_of_view.is_some() && self.focal_length.is_some() {
+			return Err(SceneError::InvalidCamera {
+				message: "set either vertical_field_of_view or focal_length, not both".to_string()
+			});
+		}
+
+		if self.aperture.is_some() && self.f_stop.is_some() {
+			return Err(SceneError::InvalidCamera {
+				message: "set either aperture or f_stop, not both".to_string()
+			});
+		}
+
+		let focus_distance = self.focus_distance.unwrap_or_else(|| (look_at - position).length());
+
+		if focus_distance.is_nan() || focus_distance <= 0.0 {
+			return Err(SceneError::InvalidCamera {
+				message: "focus_distance must be greater than 0, look_at can't be the camera position without it".to_string()
+			});
+		}
+
+		let mut lens = Lens {
+			sensor_width: self.sensor_width,
+			sensor_height: self.sensor_height,
+			focus_distance,
+			bokeh: self.bokeh.to_bokeh(base_directory)?,
+			..Lens::new()
+		};
+
+		// The older field of view and aperture are turned into the lens that has them
+		lens.focal_length = match self.focal_length {
+			Some(focal_length) => focal_length,
+			None => lens.focal_length_for(self.vertical_field_of_view.unwrap_or(90.0), aspect_ratio)
+		};
+
+		lens.f_stop = match self.aperture {
+			Some(aperture) => lens.f_stop_for(aperture),
+			None => self.f_stop.unwrap_or(lens.f_stop)
+		};
+
+		Ok(Camera::create_with_lens(position, look_at, to_vec3(self.up), &lens, aspect_ratio))
+	}
+
+	// Written with the camera's lens, `bokeh` stands in for the lens' own since image bokeh doesn't know its path
+	pub fn from_camera(camera: &Camera, bokeh: BokehDescription) -> Self {
+		let lens = camera.lens();
+
+		Self {
+			position: from_vec3(camera.position()),
+			look_at: from_vec3(camera.look_at()),
+			up: from_vec3(camera.up()),
+			focal_length: Some(lens.focal_length),
+			sensor_width: lens.sensor_width,
+			sensor_height: lens.sensor_height,
+			f_stop: Some(lens.f_stop),
+			focus_distance: Some(lens.focus_distance),
+			bokeh,
+			aspect_ratio: Some(camera.aspect_ratio()),
+			vertical_field_of_view: None,
+			aperture: None
+		}
+	}
+}
+
+impl Default for CameraDescription {
+	fn default() -> Self {
+		let lens = Lens::new();
+
+		Self {
+			position: [0.0, 0.0, 0.0],
+			look_at: [0.0, 0.0, -1.0],
+			up: [0.0, 1.0, 0.0],
+			focal_length: None,
+			sensor_width: lens.sensor_width,
+			sensor_height: lens.sensor_height,
+			f_stop: None,
+			focus_distance: None,
+			bokeh: BokehDescription::Circle,
+			aspect_ratio: None,
+			vertical_field_of_view: None,
+			aperture: None
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BokehDescription {
+	Circle,
+	// Rotation in degrees
+	Polygon {
+		blades: u32,
+		#[serde(default)]
+		rotation: f64
+	},
+	// Brighter parts of the image let more light through
+	Image {
+		path: String
+	}
+}
+
+impl BokehDescription {
+	// Images only keep their pixels, so they can't be written without the description they were loaded from
+	pub fn from_bokeh(bokeh: &Bokeh) -> Result<Self, SceneError> {
+		let description = match bokeh {
+			Bokeh::Circle => BokehDescription::Circle,
+			Bokeh::Polygon { blades, rotation } => BokehDescription::Polygon {
+				blades: *blades,
+				rotation: *rotation
+			},
+			Bokeh::Image(_) => return Err(SceneError::NotDescribable {
+				message: "the camera's bokeh image wasn't loaded from a scene file".to_string()
+			})
+		};
+
+		Ok(description)
+	}
+
+	pub fn to_bokeh(&self, base_directory: &Path) -> Result<Bokeh, SceneError> {
+		let bokeh = match self {
+			BokehDescription::Circle => Bokeh::Circle,
+			BokehDescription::Polygon { blades, rotation } => {
+				if *blades < 3 {
+					return Err(SceneError::InvalidCamera {
+						message: "bokeh polygons need at least 3 blades".to_string()
+					});
+				}
+
+				Bokeh::Polygon {
+					blades: *blades,
+					rotation: *rotation
+				}
+			}
+			BokehDescription::Image { path } => {
+				let path = base_directory.join(path);
+
+				let texture = load_texture(&path, TextureWrapMode::Clamp).map_err(|error| SceneError::Texture {
+					path,
+					error
+				})?;
+
+				match ApertureImage::create(&texture) {
+					None => return Err(SceneError::InvalidCamera {
+						message: "the bokeh image is black and lets no light through".to_string()
+					}),
+					Some(image) => Bokeh::Image(Arc::new(image))
+				}
+			}
+		};
+
+		Ok(bokeh)
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BackgroundDescription {
+	Gradient {
+		bottom: [f32; 3],
+		top: [f32; 3]
+	},
+	Constant {
+		color: [f32; 3]
+	},
+	// Equirectangular image, usually OpenEXR or Radiance, with +y at the top
+	Environment {
+		path: String,
+		// Around +y, in degrees
+		#[serde(default)]
+		rotation: f64,
+		#[serde(default = "default_intensity")]
+		intensity: f32
+	},
+	// Daylight sky with a sun, angles in degrees, azimuth 0 is towards -z and 90 towards +x
+	Sky {
+		#[serde(default = "default_sun_elevation")]
+		sun_elevation: f64,
+		#[serde(default)]
+		sun_azimuth: f64,
+		#[serde(default = "default_turbidity")]
+		turbidity: f64,
+		#[serde(default = "default_intensity")]
+		intensity: f32
+	}
+}
+
+impl BackgroundDescription {
+	// Environment paths are relative to `base_directory`
+	pub fn to_background(&self, base_directory: &Path) -> Result<Background, SceneError> {
+		let background = match self {
+			BackgroundDescription::Gradient { bottom, top } => {
+				Background::Gradient {
+					bottom: to_color(*bottom),
+					top: to_color(*top)
+				}
+			}
+			BackgroundDescription::Constant { color } => {
+				Background::Constant(to_color(*color))
+			}
+			BackgroundDescription::Environment { path, rotation, intensity } => {
+				let path = base_directory.join(path);
+
+				let texture = load_texture(&path, TextureWrapMode::Repeat).map_err(|error| SceneError::Texture {
+					path,
+					error
+				})?;
+
+				Background::Environment(Arc::new(EnvironmentMap::create(texture, *rotation, *intensity)))
+			}
+			BackgroundDescription::Sky { sun_elevation, sun_azimuth, turbidity, intensity } => {
+				Background::Sky(Arc::new(PhysicalSky::create(*sun_elevation, *sun_azimuth, *turbidity, *intensity)))
+			}
+		};
+
+		Ok(background)
+	}
+
+	// None for environment maps, which don't keep the path of their image
+	pub fn from_background(background: &Background) -> Option<Self> {
+		let description = match background {
+			Background::Gradient { bottom, top } => BackgroundDescription::Gradient {
+				bottom: from_color(*bottom),
+				top: from_color(*top)
+			},
+			Background::Constant(color) => BackgroundDescription::Constant {
+				color: from_color(*color)
+			},
+			Background::Environment(_) => return None,
+			Background::Sky(sky) => BackgroundDescription::Sky {
+				sun_elevation: sky.sun_elevation(),
+				sun_azimuth: sky.sun_azimuth(),
+				turbidity: sky.turbidity(),
+				intensity: sky.intensity()
+			}
+		};
+
+		Some(description)
+	}
+}
+
+fn default_intensity() -> f32 {
+	1.0
+}
+
+fn default_sun_elevation() -> f64 {
+	45.0
+}
+
+fn default_turbidity() -> f64 {
+	3.0
+}
+
+impl Default for BackgroundDescription {
+	fn default() -> Self {
+		Self::Gradient {
+			bottom: [1.0, 1.0, 1.0],
+			top: [0.5, 0.7, 1.0]
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MaterialDescription {
+	Lambertian {
+		#[serde(default = "default_albedo")]
+		albedo: [f32; 3],
+		// Replaces `albedo`
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		texture: Option<TextureDescription>
+	},
+	Metal {
+		#[serde(default = "default_albedo")]
+		albedo: [f32; 3],
+		#[serde(default)]
+		fuzz: f64,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		texture: Option<TextureDescription>
+	},
+	Dielectric {
+		index_of_refraction: f64
+	},
+	DiffuseLight {
+		emit: [f32; 3],
+		#[serde(default = "default_two_sided")]
+		two_sided: bool
+	}
+}
+
+impl MaterialDescription {
+	// Texture paths are relative to `base_directory`
+	pub fn to_material(&self, base_directory: &Path) -> Result<Arc<dyn Material + Send + Sync>, SceneError> {
+		let material: Arc<dyn Material + Send + Sync> = match self {
+			MaterialDescription::Lambertian { albedo, texture: None } => {
+				Arc::new(Lambertian::create(to_color(*albedo)))
+			}
+			MaterialDescription::Lambertian { texture: Some(texture), .. } => {
+				Arc::new(Lambertian::create_textured(texture.load(base_directory)?, texture.filter))
+			}
+			MaterialDescription::Metal { albedo, fuzz, texture: None } => {
+				Arc::new(Metal::create(to_color(*albedo), *fuzz))
+			}
+			MaterialDescription::Metal { fuzz, texture: Some(texture), .. } => {
+				Arc::new(Metal::create_textured(texture.load(base_directory)?, texture.filter, *fuzz))
+			}
+			MaterialDescription::Dielectric { index_of_refraction } => {
+				Arc::new(Dielectric::create(*index_of_refraction))
+			}
+			MaterialDescription::DiffuseLight { emit, two_sided } => {
+				if *two_sided {
+					Arc::new(DiffuseLight::create(to_color(*emit)))
+				}
+				else {
+					Arc::new(DiffuseLight::create_one_sided(to_color(*emit)))
+				}
+			}
+		};
+
+		Ok(material)
+	}
+}
+
+fn default_two_sided() -> bool {
+	true
+}
+
+fn default_albedo() -> [f32; 3] {
+	[0.8, 0.8, 0.8]
+}
+
+// Image file with linear (OpenEXR, Radiance) or sRGB encoded (everything else) colors, repeated outside of [0, 1]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextureDescription {
+	pub path: String,
+	#[serde(default)]
+	pub filter: TextureFilter
+}
+
+impl TextureDescription {
+	pub fn load(&self, base_directory: &Path) -> Result<Arc<MipMap>, SceneError> {
+		let path = base_directory.join(&self.path);
+
+		let texture = load_texture(&path, TextureWrapMode::Repeat).map_err(|error| SceneError::Texture {
+			path,
+			error
+		})?;
+
+		Ok(Arc::new(MipMap::create(texture)))
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObjectDescription {
+	Sphere {
+		center: [f64; 3],
+		radius: f64,
+		material: String
+	},
+	Triangle {
+		vertices: [[f64; 3]; 3],
+		material: String
+	},
+	Quad {
+		corner: [f64; 3],
+		u: [f64; 3],
+		v: [f64; 3],
+		material: String
+	},
+	Cuboid {
+		size: [f64; 3],
+		#[serde(default)]
+		rotation_y: f64,
+		#[serde(default)]
+		offset: [f64; 3],
+		material: String
+	},
+	// Relative paths are resolved against the scene file, `material` replaces the MTL materials
+	Obj {
+		path: PathBuf,
+		#[serde(default)]
+		material: Option<String>
+	},
+	// Triangles index into `vertices`, `normals` and `uvs`, used when saving meshes that weren't read from a file
+	Mesh {
+		vertices: Vec<[f64; 3]>,
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		normals: Vec<[f64; 3]>,
+		#[serde(default, skip_serializing_if = "Vec::is_empty")]
+		uvs: Vec<[f64; 2]>,
+		triangles: Vec<MeshTriangle>,
+		material: String
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderDescription {
+	pub width: u32,
+	pub height: u32,
+	pub samples_per_pixel: usize,
+	// Safety cap on the path length, Russian roulette ends paths long before it
+	pub max_bounces: usize,
+	// Bounces before Russian roulette can end paths
+	pub russian_roulette_depth: usize,
+	pub integrator: Integrator,
+	// Same syntax as the command line renderer, e.g. "tile,32x32/line"
+	pub generation_mode: String,
+	pub sampler: SamplerType,
+	pub filter: Filter,
+	// Exposure in stops and tone mapping of the displayed and exported image
+	pub exposure: f64,
+	pub tone_mapping: ToneMapping,
+	pub white_point: f64,
+	pub seed: u64,
+	// Renders progressively when set
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub samples_per_pass: Option<usize>,
+	// Samples adaptively when set, samples_per_pixel is then the maximum
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error_threshold: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub min_samples_per_pixel: Option<usize>
+}
+
+impl RenderDescription {
+	pub fn aspect_ratio(&self) -> f64 {
+		self.width as f64 / self.height as f64
+	}
+
+	pub fn to_settings(&self) -> Result<RaytracerSettings, SceneError> {
+		if self.width == 0 || self.height == 0 {
+			return Err(SceneError::InvalidRenderSettings {
+				message: "width and height must be at least 1".to_string()
+			});
+		}
+
+		if self.white_point.is_nan() || self.white_point <= 0.0 {
+			return Err(SceneError::InvalidRenderSettings {
+				message: "white_point must be greater than 0".to_string()
+			});
+		}
+
+		// Same limits as the command line renderer
+		if self.samples_per_pixel == 0 || self.max_bounces == 0 {
+			return Err(SceneError::InvalidRenderSettings {
+				message: "samples_per_pixel and max_bounces must be at least 1".to_string()
+			});
+		}
+
+		let generation_mode = self.generation_mode.parse::<GenerationMode>()
+			.map_err(|message| SceneError::InvalidRenderSettings { message })?;
+
+		Ok(RaytracerSettings {
+			width: self.width,
+			height: self.height,
+			samples_per_pixel: self.samples_per_pixel,
+			max_bounces: self.max_bounces,
+			russian_roulette_depth: self.russian_roulette_depth,
+			integrator: self.integrator,
+			generation_mode,
+			sampler: self.sampler,
+			filter: self.filter,
+			display: DisplaySettings {
+				exposure: self.exposure,
+				tone_mapping: self.tone_mapping,
+				white_point: self.white_point
+			},
+			seed: self.seed,
+			progressive: self.samples_per_pass.map(|samples_per_pass| ProgressiveSettings {
+				samples_per_pass
+			}),
+			adaptive: self.error_threshold.map(|error_threshold| AdaptiveSettings {
+				min_samples_per_pixel: self.min_samples_per_pixel.unwrap_or(AdaptiveSettings::new().min_samples_per_pixel),
+				error_threshold
+			}),
+			// What gets written out is up to the caller
+			aovs: false,
+			denoiser: None
+		})
+	}
+}
+
+impl Default for RenderDescription {
+	fn default() -> Self {
+		Self {
+			width: 1200,
+			height: 800,
+			samples_per_pixel: 100,
+			max_bounces: 64,
+			russian_roulette_depth: 3,
+			integrator: Integrator::PathTracer,
+			generation_mode: "pixel".to_string(),
+			sampler: SamplerType::Independent,
+			filter: Filter::new(),
+			exposure: DisplaySettings::new().exposure,
+			tone_mapping: DisplaySettings::new().tone_mapping,
+			white_point: DisplaySettings::new().white_point,
+			seed: 0,
+			samples_per_pass: None,
+			error_threshold: None,
+			min_samples_per_pixel: None
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneDescription {
+	pub camera: CameraDescription,
+	pub background: BackgroundDescription,
+	pub materials: BTreeMap<String, MaterialDescription>,
+	pub objects: Vec<ObjectDescription>,
+	pub render: RenderDescription,
+	#[serde(skip)]
+	pub base_directory: PathBuf
+}
+
+impl SceneDescription {
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SceneError> {
+		let path = path.as_ref().to_path_buf();
+
+		let file = File::open(&path).map_err(|error| SceneError::Io {
+			path: path.clone(),
+			error
+		})?;
+
+		let mut description: SceneDescription = serde_json::from_reader(BufReader::new(file))
+			.map_err(|error| SceneError::Json {
+				path: path.clone(),
+				error
+			})?;
+
+		description.base_directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+		Ok(description)
+	}
+
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SceneError> {
+		let path = path.as_ref().to_path_buf();
+
+		let file = File::create(&path).map_err(|error| SceneError::Io {
+			path: path.clone(),
+			error
+		})?;
+
+		serde_json::to_writer_pretty(BufWriter::new(file), self).map_err(|error| SceneError::Json {
+			path,
+			error
+		})
+	}
+
+	pub fn built_in(name: &str) -> Option<Self> {
+		match name {
+			"random-spheres" => Some(Self::random_spheres()),
+			"cornell-box" => Some(Self::cornell_box()),
+			_ => None
+		}
+	}
+
+	pub fn build(&self) -> Result<RaytracingScene, SceneError> {
+		let materials: HashMap<&str, Arc<dyn Material + Send + Sync>> = self.materials.iter()
+			.map(|(name, material)| Ok((name.as_str(), material.to_material(&self.base_directory)?)))
+			.collect::<Result<_, SceneError>>()?;
+
+		let material = |name: &String| match materials.get(name.as_str()) {
+			None => Err(SceneError::UnknownMaterial { name: name.clone() }),
+			Some(material) => Ok(material.clone())
+		};
+
+		let mut scene = RaytracingScene::new();
+
+		for object in &self.objects {
+			match object {
+				ObjectDescription::Sphere { center, radius, material: name } => {
+					scene.add_hittable(Arc::new(Sphere::create(to_vec3(*center), *radius, material(name)?)));
+				}
+				ObjectDescription::Triangle { vertices, material: name } => {
+					scene.add_hittable(Arc::new(Triangle::create(vertices.map(to_vec3), material(name)?)));
+				}
+				ObjectDescription::Quad { corner, u, v, material: name } => {
+					scene.add_hittable(Arc::new(create_quad(to_vec3(*corner), to_vec3(*u), to_vec3(*v), material(name)?)));
+				}
+				ObjectDescription::Cuboid { size, rotation_y, offset, material: name } => {
+					scene.add_hittable(Arc::new(create_box(to_vec3(*size), *rotation_y, to_vec3(*offset), material(name)?)));
+				}
+				ObjectDescription::Obj { path, material: name } => {
+					let override_material = match name {
+						None => None,
+						Some(name) => Some(material(name)?)
+					};
+
+					for mut mesh in load_obj(self.base_directory.join(path))? {
+						if let Some(material) = &override_material {
+							mesh.set_material(material.clone());
+						}
+						scene.add_hittable(Arc::new(mesh));
+					}
+				}
+				ObjectDescription::Mesh { vertices, normals, uvs, triangles, material: name } => {
+					let in_range = |indices: Option<[usize; 3]>, count: usize| indices.is_none_or(|indices| indices.iter().all(|index| *index < count));
+
+					if !triangles.iter().all(|triangle| in_range(Some(triangle.vertices), vertices.len()) && in_range(triangle.normals, normals.len()) && in_range(triangle.uvs, uvs.len())) {
+						return Err(SceneError::InvalidObject {
+							message: "mesh triangle indices must be smaller than the number of vertices, normals and uvs".to_string()
+						});
+					}
+
+					let vertices = vertices.iter().copied().map(to_vec3).collect();
+					let normals = normals.iter().copied().map(|normal| to_vec3(normal).normalized()).collect();
+					let uvs = uvs.iter().map(|uv| Vec2::create(uv[0], uv[1])).collect();
+
+					scene.add_hittable(Arc::new(TriangleMesh::create(vertices, normals, uvs, triangles.clone(), material(name)?)));
+				}
+			}
+		}
+
+		scene.camera = self.camera.to_camera(self.render.aspect_ratio(), &self.base_directory)?;
+		scene.background = self.background.to_background(&self.base_directory)?;
+		let material_names = materials.into_iter()
+			.map(|(name, material)| (material, name.to_string()))
+			.collect();
+
+		scene.set_description(self.clone(), material_names);
+
+		Ok(scene)
+	}
+
+	// Always the same arrangement, so renders of it can be compared
+	pub fn random_spheres() -> Self {
+		let mut rand = Pcg32::create(RANDOM_SPHERES_SEED);
+
+		let mut materials = BTreeMap::new();
+		let mut objects = vec![];
+
+		let mut add_sphere = |name: String, material: MaterialDescription, center: [f64; 3], radius: f64| {
+			materials.insert(name.clone(), material);
+			objects.push(ObjectDescription::Sphere {
+				center,
+				radius,
+				material: name
+			});
+		};
+
+		add_sphere("ground".to_string(), MaterialDescription::Lambertian { albedo: [0.5, 0.5, 0.5], texture: None }, [0.0, -1000.0, 0.0], 1000.0);
+		add_sphere("center".to_string(), MaterialDescription::Dielectric { index_of_refraction: 1.5 }, [0.0, 1.0, 0.0], 1.0);
+		add_sphere("left".to_string(), MaterialDescription::Lambertian { albedo: [0.4, 0.2, 0.1], texture: None }, [-4.0, 1.0, 0.0], 1.0);
+		add_sphere("right".to_string(), MaterialDescription::Metal { albedo: [0.7, 0.6, 0.5], fuzz: 0.0, texture: None }, [4.0, 1.0, 0.0], 1.0);
+
+		for a in -11..11 {
+			for b in -11..11 {
+				let choose_mat = rand.next_f64();
+				let center = Vec3::create(a as f64 + 0.9 * rand.next_f64(), 0.2, b as f64 + 0.9 * rand.next_f64());
+
+				if (center - Vec3::create(4.0, 0.2, 0.0)).length() <= 0.9 {
+					continue;
+				}
+
+				let material = if choose_mat < 0.8 {
+					let albedo = [
+						(rand.next_f64() * rand.next_f64()) as f32,
+						(rand.next_f64() * rand.next_f64()) as f32,
+						(rand.next_f64() * rand.next_f64()) as f32
+					];
+					MaterialDescription::Lambertian { albedo, texture: None }
+				}
+				else if choose_mat < 0.95 {
+					let albedo = [
+						rand.next_range(0.5, 1.0) as f32,
+						rand.next_range(0.5, 1.0) as f32,
+						rand.next_range(0.5, 1.0) as f32
+					];
+					let fuzz = rand.next_range(0.0, 0.5);
+					MaterialDescription::Metal { albedo, fuzz, texture: None }
+				}
+				else {
+					MaterialDescription::Dielectric { index_of_refraction: 1.5 }
+				};
+
+				add_sphere(format!("sphere_{}_{}", a, b), material, [center.x, center.y, center.z], 0.2);
+			}
+		}
+
+		Self {
+			camera: CameraDescription {
+				position: [13.0, 2.0, 3.0],
+				look_at: [0.0, 0.0, 0.0],
+				up: [0.0, 1.0, 0.0],
+				vertical_field_of_view: Some(20.0),
+				focus_distance: Some(10.0),
+				aperture: Some(0.01),
+				..CameraDescription::default()
+			},
+			background: BackgroundDescription::default(),
+			materials,
+			objects,
+			render: RenderDescription::default(),
+			base_directory: PathBuf::new()
+		}
+	}
+
+	pub fn cornell_box() -> Self {
+		let materials = BTreeMap::from([
+			("red".to_string(), MaterialDescription::Lambertian { albedo: [0.65, 0.05, 0.05], texture: None }),
+			("white".to_string(), MaterialDescription::Lambertian { albedo: [0.73, 0.73, 0.73], texture: None }),
+			("green".to_string(), MaterialDescription::Lambertian { albedo: [0.12, 0.45, 0.15], texture: None }),
+			("light".to_string(), MaterialDescription::DiffuseLight { emit: [15.0, 15.0, 15.0], two_sided: true })
+		]);
+
+		let quad = |corner: [f64; 3], u: [f64; 3], v: [f64; 3], material: &str| ObjectDescription::Quad {
+			corner,
+			u,
+			v,
+			material: material.to_string()
+		};
+
+		let cuboid = |size: [f64; 3], rotation_y: f64, offset: [f64; 3]| ObjectDescription::Cuboid {
+			size,
+			rotation_y,
+			offset,
+			material: "white".to_string()
+		};
+
+		let objects = vec![
+			quad([555.0, 0.0, 0.0], [0.0, 555.0, 0.0], [0.0, 0.0, 555.0], "green"),
+			quad([0.0, 0.0, 0.0], [0.0, 555.0, 0.0], [0.0, 0.0, 555.0], "red"),
+			quad([343.0, 554.0, 332.0], [-130.0, 0.0, 0.0], [0.0, 0.0, -105.0], "light"),
+			quad([0.0, 0.0, 0.0], [555.0, 0.0, 0.0], [0.0, 0.0, 555.0], "white"),
+			quad([555.0, 555.0, 555.0], [-555.0, 0.0, 0.0], [0.0, 0.0, -555.0], "white"),
+			quad([0.0, 0.0, 555.0], [555.0, 0.0, 0.0], [0.0, 555.0, 0.0], "white"),
+			cuboid([165.0, 330.0, 165.0], 15.0, [265.0, 0.0, 295.0]),
+			cuboid([165.0, 165.0, 165.0], -18.0, [130.0, 0.0, 65.0])
+		];
+
+		Self {
+			camera: CameraDescription {
+				position: [278.0, 278.0, -800.0],
+				look_at: [278.0, 278.0, 0.0],
+				up: [0.0, 1.0, 0.0],
+				vertical_field_of_view: Some(40.0),
+				focus_distance: Some(10.0),
+				..CameraDescription::default()
+			},
+			background: BackgroundDescription::Constant { color: [0.0, 0.0, 0.0] },
+			materials,
+			objects,
+			render: RenderDescription {
+				width: 600,
+				height: 600,
+				..RenderDescription::default()
+			},
+			base_directory: PathBuf::new()
+		}
+	}
+}
+
+fn to_vec3(value: [f64; 3]) -> Vec3 {
+	Vec3::create(value[0], value[1], value[2])
+}
+
+fn to_color(value: [f32; 3]) -> Color {
+	Color::create(value[0], value[1], value[2], 1.0)
+}
+
+pub(crate) fn from_vec3(value: Vec3) -> [f64; 3] {
+	[value.x, value.y, value.z]
+}
+
+pub(crate) fn from_vec2(value: Vec2) -> [f64; 2] {
+	[value.x, value.y]
+}
+
+pub(crate) fn from_color(value: Color) -> [f32; 3] {
+	[value.r, value.g, value.b]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rejects_zero_samples_and_bounces() {
+		let render = RenderDescription::default();
+		assert!(render.to_settings().is_ok());
+
+		let no_samples = RenderDescription { samples_per_pixel: 0, ..render.clone() };
+		assert!(matches!(no_samples.to_settings(), Err(SceneError::InvalidRenderSettings { .. })));
+
+		let no_bounces = RenderDescription { max_bounces: 0, ..render };
+		assert!(matches!(no_bounces.to_settings(), Err(SceneError::InvalidRenderSettings { .. })));
+	}
+
+	#[test]
+	fn rejects_mesh_indices_out_of_range() {
+		let mut description = SceneDescription::cornell_box();
+		description.objects.push(ObjectDescription::Mesh {
+			vertices: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+			normals: vec![],
+			uvs: vec![],
+			triangles: vec![MeshTriangle { vertices: [0, 1, 2], normals: Some([0, 1, 2]), uvs: None }],
+			material: "white".to_string()
+		});
+
+		assert!(matches!(description.build(), Err(SceneError::InvalidObject { .. })));
+	}
+
+	#[test]
+	fn cameras_keep_their_field_of_view() {
+		let camera = |description: CameraDescription| description.to_camera(1.5, Path::new("")).unwrap();
+
+		assert!((camera(CameraDescription::default()).vertical_field_of_view() - 90.0).abs() < 1e-9);
+		assert!((camera(SceneDescription::random_spheres().camera).vertical_field_of_view() - 20.0).abs() < 1e-9);
+
+		let lens = camera(CameraDescription { focal_length: Some(50.0), f_stop: Some(2.0), ..CameraDescription::default() });
+		assert!((lens.vertical_field_of_view() - Lens::new().vertical_field_of_view(1.5)).abs() < 1e-9);
+		assert!((lens.aperture() - 0.025).abs() < 1e-12);
+	}
+
+	#[test]
+	fn cameras_are_written_with_their_lens() {
+		let description = CameraDescription {
+			position: [0.0, 1.0, 4.0],
+			look_at: [0.0, 1.0, 0.0],
+			focal_length: Some(85.0),
+			sensor_width: 24.0,
+			sensor_height: 16.0,
+			f_stop: Some(1.4),
+			bokeh: BokehDescription::Polygon { blades: 7, rotation: 10.0 },
+			..CameraDescription::default()
+		};
+		let camera = description.to_camera(1.5, Path::new("")).unwrap();
+
+		let written = CameraDescription::from_camera(&camera, BokehDescription::from_bokeh(camera.bokeh()).unwrap());
+		assert_eq!(written.focal_length, Some(85.0));
+		assert_eq!((written.sensor_width, written.sensor_height), (24.0, 16.0));
+		assert_eq!(written.f_stop, Some(1.4));
+		assert_eq!(written.bokeh, description.bokeh);
+		assert_eq!(written.vertical_field_of_view, None);
+		assert_eq!(written.aperture, None);
+		assert_eq!(written.to_camera(1.5, Path::new("")).unwrap().lens(), camera.lens());
+	}
+
+	#[test]
+	fn rejects_cameras_with_both_lens_and_older_settings() {
+		let both_fields_of_view = CameraDescription { focal_length: Some(50.0), vertical_field_of_view: Some(40.0), ..CameraDescription::default() };
+		assert!(matches!(both_fields_of_view.to_camera(1.5, Path::new("")), Err(SceneError::InvalidCamera { .. })));
+
+		let both_apertures = CameraDescription { f_stop: Some(2.8), aperture: Some(0.1), ..CameraDescription::default() };
+		assert!(matches!(both_apertures.to_camera(1.5, Path::new("")), Err(SceneError::InvalidCamera { .. })));
+	}
+
+	#[test]
+	fn rejects_cameras_without_focus_distance() {
+		let at_look_at = CameraDescription { look_at: [0.0, 0.0, 0.0], ..CameraDescription::default() };
+		assert!(matches!(at_look_at.to_camera(1.5, Path::new("")), Err(SceneError::InvalidCamera { .. })));
+
+		for focus_distance in [0.0, -1.0, f64::NAN] {
+			let camera = CameraDescription { focus_distance: Some(focus_distance), ..CameraDescription::default() };
+			assert!(matches!(camera.to_camera(1.5, Path::new("")), Err(SceneError::InvalidCamera { .. })), "{}", focus_distance);
+		}
+	}
+}
\ No newline at end of file
@@ -0,0 +1,322 @@
+use std::f64::consts::PI;
+use crate::raytracing::color::Color;
+use crate::raytracing::sampler::Sampler;
+use crate::raytracing::vector_3d::Vec3;
+
+// Angular radius of the sun as seen from earth, in radians
+const SUN_ANGULAR_RADIUS: f64 = 0.004651;
+
+// Luminance of the sun outside of the atmosphere, in kcd/m²
+const SUN_LUMINANCE: f64 = 2.0e6;
+
+// Scene radiance of 1 corresponds to 20 kcd/m², which keeps a lit white surface around 1 in daylight
+const RADIANCE_SCALE: f64 = 0.05;
+
+// Wavelengths in micrometers the sun's transmittance is evaluated at for red, green and blue
+const WAVELENGTHS: [f64; 3] = [0.65, 0.55, 0.45];
+
+// Daylight sky after Preetham, Shirley and Smits' "A Practical Analytic Model for Daylight" (1999), with a sun disk.
+// The sun is dimmed and reddened by the same atmosphere, both depend on its position and the turbidity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicalSky {
+	// Above the horizon, in degrees
+	sun_elevation: f64,
+	// Around +y, in degrees, 0 is towards -z and 90 towards +x
+	sun_azimuth: f64,
+	// Haziness of the atmosphere, 2 is very clear and 10 hazy
+	turbidity: f64,
+	intensity: f32,
+	sun_direction: Vec3,
+	sun_radiance: Color,
+	// Luminance Y and chromaticity x and y at the zenith
+	zenith: [f64; 3],
+	// Perez distribution coefficients A to E for Y, x and y
+	coefficients: [[f64; 5]; 3],
+	// Distribution at the zenith, the model is relative to it
+	zenith_distribution: [f64; 3]
+}
+
+impl PhysicalSky {
+	pub fn create(sun_elevation: f64, sun_azimuth: f64, turbidity: f64, intensity: f32) -> Self {
+		let elevation = sun_elevation.to_radians();
+		let azimuth = sun_azimuth.to_radians();
+		let sun_direction = Vec3::create(elevation.cos() * azimuth.sin(), elevation.sin(), -elevation.cos() * azimuth.cos());
+
+		// The model only covers the sun above the horizon and a range of turbidities
+		let turbidity = turbidity.clamp(1.7, 10.0);
+		let sun_zenith = (PI / 2.0 - elevation).clamp(0.0, PI / 2.0);
+
+		let coefficients = perez_coefficients(turbidity);
+
+		let zenith = [
+			zenith_luminance(turbidity, sun_zenith),
+			zenith_chromaticity(turbidity, sun_zenith, [[0.00166, -0.00375, 0.00209, 0.0], [-0.02903, 0.06377, -0.03202, 0.00394], [0.11693, -0.21196, 0.06052, 0.25886]]),
+			zenith_chromaticity(turbidity, sun_zenith, [[0.00275, -0.00610, 0.00317, 0.0], [-0.04214, 0.08970, -0.04153, 0.00516], [0.15346, -0.26756, 0.06670, 0.26688]])
+		];
+
+		let zenith_distribution = [0, 1, 2].map(|channel| perez(coefficients[channel], 1.0, sun_zenith));
+
+		Self {
+			sun_elevation,
+			sun_azimuth,
+			turbidity,
+			intensity,
+			sun_direction,
+			sun_radiance: sun_radiance(turbidity, sun_zenith),
+			zenith,
+			coefficients,
+			zenith_distribution
+		}
+	}
+
+	pub fn sun_elevation(&self) -> f64 {
+		self.sun_elevation
+	}
+
+	pub fn sun_azimuth(&self) -> f64 {
+		self.sun_azimuth
+	}
+
+	pub fn turbidity(&self) -> f64 {
+		self.turbidity
+	}
+
+	pub fn intensity(&self) -> f32 {
+		self.intensity
+	}
+
+	// Normalized direction towards the center of the sun
+	pub fn sun_direction(&self) -> Vec3 {
+		self.sun_direction
+	}
+
+	// Only the sun is sampled, the rest of the sky is smooth enough for BSDF sampling
+	pub fn can_be_sampled(&self) -> bool {
+		self.intensity > 0.0 && self.sun_elevation > -SUN_ANGULAR_RADIUS.to_degrees()
+	}
+
+	pub fn color(&self, direction: Vec3) -> Color {
+		let direction = direction.normalized();
+		let sky = self.sky_color(direction);
+
+		if self.sun_covers(direction) {
+			sky + self.sun_radiance * self.intensity
+		}
+		else {
+			sky
+		}
+	}
+
+	// The sky without the sun
+	pub fn sky_color(&self, direction: Vec3) -> Color {
+		// Below the horizon the sky continues like at the horizon
+		let cos_theta = direction.normalized().y.max(0.0);
+		let gamma = Vec3::dot(direction.normalized(), self.sun_direction).clamp(-1.0, 1.0).acos();
+
+		let [luminance, x, y] = [0, 1, 2].map(|channel| {
+			self.zenith[channel] * perez(self.coefficients[channel], cos_theta, gamma) / self.zenith_distribution[channel]
+		});
+
+		if luminance <= 0.0 || y <= 0.0 {
+			return Color::black();
+		}
+
+		xyz_to_color(x * luminance / y, luminance, (1.0 - x - y) * luminance / y) * self.intensity
+	}
+
+	// Normalized direction towards the sun and its solid angle pdf. Directions in the part of the sun below the horizon
+	// give None, the ground hides it there, so only the visible part has a pdf and light sampling stays unbiased
+	pub fn sample(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+		let (u, v) = sampler.next_2d();
+
+		let one_minus_cos_theta_max = cone_size();
+		let cos_theta = 1.0 - u * one_minus_cos_theta_max;
+		let sin_theta = (1.0 - cos_theta.powi(2)).max(0.0).sqrt();
+		let phi = v * std::f64::consts::TAU;
+
+		let w = self.sun_direction;
+		let (tangent, bitangent) = w.orthonormal_basis();
+		let direction = tangent * (phi.cos() * sin_theta) + bitangent * (phi.sin() * sin_theta) + w * cos_theta;
+
+		if direction.y < 0.0 {
+			return None;
+		}
+
+		Some((direction, 1.0 / (2.0 * PI * one_minus_cos_theta_max)))
+	}
+
+	pub fn pdf(&self, direction: Vec3) -> f64 {
+		if self.sun_covers(direction.normalized()) {
+			1.0 / (2.0 * PI * cone_size())
+		}
+		else {
+			0.0
+		}
+	}
+
+	// Whether the normalized `direction` hits the part of the sun above the horizon
+	fn sun_covers(&self, direction: Vec3) -> bool {
+		Vec3::dot(direction, self.sun_direction) >= SUN_ANGULAR_RADIUS.cos() && direction.y >= 0.0
+	}
+}
+
+// 1 - cos of the sun's angular radius without losing precision
+fn cone_size() -> f64 {
+	2.0 * (SUN_ANGULAR_RADIUS / 2.0).sin().powi(2)
+}
+
+fn perez_coefficients(turbidity: f64) -> [[f64; 5]; 3] {
+	let linear = |slope: f64, offset: f64| slope * turbidity + offset;
+
+	[
+		[linear(0.1787, -1.4630), linear(-0.3554, 0.4275), linear(-0.0227, 5.3251), linear(0.1206, -2.5771), linear(-0.0670, 0.3703)],
+		[linear(-0.0193, -0.2592), linear(-0.0665, 0.0008), linear(-0.0004, 0.2125), linear(-0.0641, -0.8989), linear(-0.0033, 0.0452)],
+		[linear(-0.0167, -0.2608), linear(-0.0950, 0.0092), linear(-0.0079, 0.2102), linear(-0.0441, -1.6537), linear(-0.0109, 0.0529)]
+	]
+}
+
+// Relative distribution over the sky, `gamma` is the angle to the sun
+fn perez([a, b, c, d, e]: [f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+	// Towards the horizon b / cos_theta goes to -infinity for all valid turbidities, which is what the model intends
+	(1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+// In kcd/m²
+fn zenith_luminance(turbidity: f64, sun_zenith: f64) -> f64 {
+	let chi = (4.0 / 9.0 - turbidity / 120.0) * (PI - 2.0 * sun_zenith);
+
+	(4.0453 * turbidity - 4.9710) * chi.tan() - 0.2155 * turbidity + 2.4192
+}
+
+// `matrix` rows are multiplied by turbidity², turbidity and 1, columns by the sun's zenith angle³ to 1
+fn zenith_chromaticity(turbidity: f64, sun_zenith: f64, matrix: [[f64; 4]; 3]) -> f64 {
+	let angles = [sun_zenith.powi(3), sun_zenith.powi(2), sun_zenith, 1.0];
+	let turbidities = [turbidity.powi(2), turbidity, 1.0];
+
+	(0..3)
+		.map(|row| turbidities[row] * (0..4).map(|column| matrix[row][column] * angles[column]).sum::<f64>())
+		.sum()
+}
+
+// Sunlight left after Rayleigh and aerosol scattering along the way through the atmosphere
+fn sun_radiance(turbidity: f64, sun_zenith: f64) -> Color {
+	// Relative optical mass, Kasten's formula
+	let relative_mass = 1.0 / (sun_zenith.cos() + 0.15 * (93.885 - sun_zenith.to_degrees()).powf(-1.253));
+
+	// Ångström's turbidity coefficient and wavelength exponent
+	let beta = 0.04608365822050 * turbidity - 0.04586025928522;
+	let alpha = 1.3;
+
+	let [r, g, b] = WAVELENGTHS.map(|wavelength| {
+		let rayleigh = (-0.008735 * wavelength.powf(-4.08) * relative_mass).exp();
+		let aerosol = (-beta * wavelength.powf(-alpha) * relative_mass).exp();
+
+		(SUN_LUMINANCE * RADIANCE_SCALE * rayleigh * aerosol) as f32
+	});
+
+	Color::create(r, g, b, 1.0)
+}
+
+// CIE XYZ in kcd/m² to linear sRGB scene radiance
+fn xyz_to_color(x: f64, y: f64, z: f64) -> Color {
+	let r = 3.2406 * x - 1.5372 * y - 0.4986 * z;
+	let g = -0.9689 * x + 1.8758 * y + 0.0415 * z;
+	let b = 0.0557 * x - 0.2040 * y + 1.0570 * z;
+
+	Color::create((r.max(0.0) * RADIANCE_SCALE) as f32, (g.max(0.0) * RADIANCE_SCALE) as f32, (b.max(0.0) * RADIANCE_SCALE) as f32, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f64::consts::TAU;
+	use crate::raytracing::sampler::independent::IndependentSampler;
+	use super::*;
+
+	#[test]
+	fn sun_pdf_integrates_to_one() {
+		let sky = PhysicalSky::create(50.0, 30.0, 3.0, 1.0);
+		let w = sky.sun_direction();
+		let (tangent, bitangent) = w.orthonormal_basis();
+
+		// Midpoint rule over a cone twice the sun's size, solid angle is d(cos theta) d(phi)
+		let (cos_steps, phi_steps) = (4000, 16);
+		let one_minus_cos_max = 2.0 * SUN_ANGULAR_RADIUS.sin().powi(2);
+		let cell = one_minus_cos_max / cos_steps as f64 * TAU / phi_steps as f64;
+
+		let mut integral = 0.0;
+		for i in 0..cos_steps {
+			let cos_theta = 1.0 - (i as f64 + 0.5) / cos_steps as f64 * one_minus_cos_max;
+			let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+			for j in 0..phi_steps {
+				let phi = (j as f64 + 0.5) / phi_steps as f64 * TAU;
+				let direction = tangent * (phi.cos() * sin_theta) + bitangent * (phi.sin() * sin_theta) + w * cos_theta;
+				integral += sky.pdf(direction) * cell;
+			}
+		}
+
+		assert!((integral - 1.0).abs() < 1e-2, "{}", integral);
+	}
+
+	#[test]
+	fn samples_lie_on_the_sun_and_match_the_pdf() {
+		let sky = PhysicalSky::create(20.0, -75.0, 4.0, 1.0);
+		let mut sampler = IndependentSampler::create(3);
+
+		for _ in 0..10_000 {
+			let (direction, pdf) = sky.sample(&mut sampler).unwrap();
+
+			assert!((direction.length() - 1.0).abs() < 1e-9);
+			assert!(Vec3::dot(direction, sky.sun_direction()).clamp(-1.0, 1.0).acos() <= SUN_ANGULAR_RADIUS + 1e-9);
+			assert_eq!(sky.pdf(direction), pdf);
+		}
+	}
+
+	#[test]
+	fn the_sun_below_the_horizon_is_not_sampled() {
+		// Half of the sun is below the horizon
+		let sky = PhysicalSky::create(0.0, 0.0, 3.0, 1.0);
+		let mut sampler = IndependentSampler::create(5);
+
+		let mut visible = 0;
+		for _ in 0..10_000 {
+			if let Some((direction, pdf)) = sky.sample(&mut sampler) {
+				assert!(direction.y >= 0.0);
+				assert_eq!(sky.pdf(direction), pdf);
+				visible += 1;
+			}
+		}
+		assert!((4_000..6_000).contains(&visible), "{}", visible);
+
+		let below = Vec3::create(0.0, -SUN_ANGULAR_RADIUS / 2.0, -1.0).normalized();
+		assert_eq!(sky.pdf(below), 0.0);
+		assert_eq!(sky.color(below), sky.sky_color(below));
+	}
+
+	#[test]
+	fn zero_intensity_is_black() {
+		let sky = PhysicalSky::create(40.0, 10.0, 3.0, 0.0);
+
+		assert!(!sky.can_be_sampled());
+		for direction in [sky.sun_direction(), Vec3::create(0.0, 1.0, 0.0), Vec3::create(1.0, 0.2, 0.0), Vec3::create(0.0, -1.0, 0.0)] {
+			assert!(sky.color(direction).is_black(), "{}", direction);
+		}
+	}
+
+	// The haze scatters the sun's light into the sky, which makes the sky brighter but paler in the model
+	#[test]
+	fn haze_dims_the_sun_and_pales_the_zenith() {
+		let zenith = Vec3::create(0.0, 1.0, 0.0);
+		let skies: Vec<PhysicalSky> = [2.0, 4.0, 6.0, 8.0, 10.0].into_iter().map(|turbidity| PhysicalSky::create(45.0, 0.0, turbidity, 1.0)).collect();
+
+		for pair in skies.windows(2) {
+			let (clear, hazy) = (&pair[0], &pair[1]);
+
+			let sun = |sky: &PhysicalSky| sky.color(sky.sun_direction()).luminance() - sky.sky_color(sky.sun_direction()).luminance();
+			assert!(sun(hazy) < sun(clear));
+
+			let (clear_zenith, hazy_zenith) = (clear.sky_color(zenith), hazy.sky_color(zenith));
+			assert!(hazy_zenith.b / hazy_zenith.r < clear_zenith.b / clear_zenith.r);
+		}
+	}
+}
@@ -67,7 +67,7 @@ impl Texture {
 		self.values[index + 3] = color.a;
 	}
 
-	pub fn get_pixel(&self, x: u32, y: u32) -> Option<Pixel> {
+	pub fn get_pixel(&self, x: u32, y: u32) -> Option<Pixel<'_>> {
 		let (wrapped_x, wrapped_y) = self.get_wrapped_coordinates(x,y)?;
 
 		let index = self.get_index_from_coordinates(wrapped_x,wrapped_y);
@@ -77,7 +77,7 @@ impl Texture {
 		Some(values.into())
 	}
 
-	pub fn get_pixel_mut(&mut self, x: u32, y: u32) -> Option<MutPixel> {
+	pub fn get_pixel_mut(&mut self, x: u32, y: u32) -> Option<MutPixel<'_>> {
 		let (wrapped_x, wrapped_y) = self.get_wrapped_coordinates(x,y)?;
 
 		let index = self.get_index_from_coordinates(wrapped_x, wrapped_y);
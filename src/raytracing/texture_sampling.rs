@@ -0,0 +1,257 @@
+use std::fmt::{Display, Formatter};
+use std::num::NonZeroU32;
+use std::str::FromStr;
+use serde_derive::{Serialize, Deserialize};
+use crate::raytracing::color::Color;
+use crate::raytracing::texture::{Texture, TextureWrapMode};
+use crate::raytracing::vector_2d::Vec2;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureFilter {
+	Nearest,
+	Bilinear,
+	// Blends the two mip levels closest to the hit's footprint, only mip maps have more than one level
+	#[default]
+	Trilinear
+}
+
+impl FromStr for TextureFilter {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.trim() {
+			"nearest" => Ok(TextureFilter::Nearest),
+			"bilinear" => Ok(TextureFilter::Bilinear),
+			"trilinear" => Ok(TextureFilter::Trilinear),
+			other => Err(format!("unknown texture filter '{}'", other))
+		}
+	}
+}
+
+impl Display for TextureFilter {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			TextureFilter::Nearest => "nearest",
+			TextureFilter::Bilinear => "bilinear",
+			TextureFilter::Trilinear => "trilinear"
+		};
+
+		write!(f, "{}", name)
+	}
+}
+
+// Lookups by UV coordinates, (0, 0) is the bottom left corner of the image and (1, 1) the top right.
+// Coordinates outside of that follow the texture's wrap mode, `TextureWrapMode::None` reads transparent black.
+pub trait TextureSampling {
+	fn sample_nearest(&self, uv: Vec2) -> Color;
+	fn sample_bilinear(&self, uv: Vec2) -> Color;
+	// `footprint` is the width of the area that should be averaged, in UV units
+	fn sample_trilinear(&self, uv: Vec2, footprint: f64) -> Color;
+
+	fn sample(&self, uv: Vec2, footprint: f64, filter: TextureFilter) -> Color {
+		match filter {
+			TextureFilter::Nearest => self.sample_nearest(uv),
+			TextureFilter::Bilinear => self.sample_bilinear(uv),
+			TextureFilter::Trilinear => self.sample_trilinear(uv, footprint)
+		}
+	}
+}
+
+impl TextureSampling for Texture {
+	fn sample_nearest(&self, uv: Vec2) -> Color {
+		let (x, y) = texel_position(self, uv);
+
+		texel(self, x.floor() as i64, y.floor() as i64)
+	}
+
+	fn sample_bilinear(&self, uv: Vec2) -> Color {
+		let (x, y) = texel_position(self, uv);
+
+		// Texel centers sit at half coordinates
+		let x = x - 0.5;
+		let y = y - 0.5;
+
+		let x0 = x.floor();
+		let y0 = y.floor();
+		let fx = (x - x0) as f32;
+		let fy = (y - y0) as f32;
+
+		let x0 = x0 as i64;
+		let y0 = y0 as i64;
+
+		let top = lerp(texel(self, x0, y0), texel(self, x0 + 1, y0), fx);
+		let bottom = lerp(texel(self, x0, y0 + 1), texel(self, x0 + 1, y0 + 1), fx);
+
+		lerp(top, bottom, fy)
+	}
+
+	// A single texture has no coarser levels to blend with
+	fn sample_trilinear(&self, uv: Vec2, _footprint: f64) -> Color {
+		self.sample_bilinear(uv)
+	}
+}
+
+// A texture and successively halved copies of it, down to a single texel
+#[derive(Clone)]
+pub struct MipMap {
+	levels: Vec<Texture>
+}
+
+impl MipMap {
+	pub fn create(texture: Texture) -> Self {
+		let mut levels = vec![texture];
+
+		loop {
+			let previous = levels.last().unwrap();
+			let width = previous.get_width().get();
+			let height = previous.get_height().get();
+
+			if width == 1 && height == 1 {
+				break;
+			}
+
+			levels.push(downsample(previous));
+		}
+
+		Self {
+			levels
+		}
+	}
+
+	pub fn level_count(&self) -> usize {
+		self.levels.len()
+	}
+
+	pub fn level(&self, level: usize) -> &Texture {
+		&self.levels[level.min(self.levels.len() - 1)]
+	}
+
+	pub fn base(&self) -> &Texture {
+		&self.levels[0]
+	}
+}
+
+impl TextureSampling for MipMap {
+	fn sample_nearest(&self, uv: Vec2) -> Color {
+		self.base().sample_nearest(uv)
+	}
+
+	fn sample_bilinear(&self, uv: Vec2) -> Color {
+		self.base().sample_bilinear(uv)
+	}
+
+	fn sample_trilinear(&self, uv: Vec2, footprint: f64) -> Color {
+		let base = self.base();
+		let size = base.get_width().get().max(base.get_height().get()) as f64;
+
+		// One texel of level n covers 2^n texels of the base level
+		let texels = footprint * size;
+		let level = if texels > 1.0 {
+			texels.log2().min((self.levels.len() - 1) as f64)
+		}
+		else {
+			0.0
+		};
+
+		let lower = level.floor() as usize;
+		let blend = (level - lower as f64) as f32;
+
+		if blend == 0.0 || lower + 1 >= self.levels.len() {
+			return self.levels[lower].sample_bilinear(uv);
+		}
+
+		lerp(self.levels[lower].sample_bilinear(uv), self.levels[lower + 1].sample_bilinear(uv), blend)
+	}
+}
+
+// Position in texels, y grows downwards like the rows of the image
+fn texel_position(texture: &Texture, uv: Vec2) -> (f64, f64) {
+	let x = uv.x * texture.get_width().get() as f64;
+	let y = (1.0 - uv.y) * texture.get_height().get() as f64;
+
+	(x, y)
+}
+
+fn texel(texture: &Texture, x: i64, y: i64) -> Color {
+	let wrap_mode = texture.get_wrap_mode();
+	let x = wrap_coordinate(x, texture.get_width().get(), wrap_mode);
+	let y = wrap_coordinate(y, texture.get_height().get(), wrap_mode);
+
+	match x.zip(y) {
+		None => Color::create(0.0, 0.0, 0.0, 0.0),
+		Some((x, y)) => Color::from(&texture.get_pixel(x, y).unwrap())
+	}
+}
+
+fn wrap_coordinate(coordinate: i64, size: u32, wrap_mode: TextureWrapMode) -> Option<u32> {
+	let size = size as i64;
+
+	if (0..size).contains(&coordinate) {
+		return Some(coordinate as u32);
+	}
+
+	match wrap_mode {
+		TextureWrapMode::None => None,
+		TextureWrapMode::Clamp => Some(coordinate.clamp(0, size - 1) as u32),
+		TextureWrapMode::Repeat => Some(coordinate.rem_euclid(size) as u32),
+		TextureWrapMode::Mirror => {
+			let coordinate = coordinate.rem_euclid(size * 2);
+
+			if coordinate >= size {
+				Some((size * 2 - 1 - coordinate) as u32)
+			}
+			else {
+				Some(coordinate as u32)
+			}
+		}
+	}
+}
+
+fn lerp(a: Color, b: Color, t: f32) -> Color {
+	Color::create(
+		a.r + (b.r - a.r) * t,
+		a.g + (b.g - a.g) * t,
+		a.b + (b.b - a.b) * t,
+		a.a + (b.a - a.a) * t
+	)
+}
+
+// 2x2 box filter, odd sizes clamp the last row or column
+fn downsample(texture: &Texture) -> Texture {
+	let width = texture.get_width().get();
+	let height = texture.get_height().get();
+
+	let new_width = (width / 2).max(1);
+	let new_height = (height / 2).max(1);
+
+	let mut result = Texture::new(
+		NonZeroU32::new(new_width).unwrap(),
+		NonZeroU32::new(new_height).unwrap(),
+		texture.get_wrap_mode(),
+		Color::black()
+	);
+
+	for y in 0..new_height {
+		for x in 0..new_width {
+			let x0 = (x * 2).min(width - 1);
+			let x1 = (x * 2 + 1).min(width - 1);
+			let y0 = (y * 2).min(height - 1);
+			let y1 = (y * 2 + 1).min(height - 1);
+
+			let mut sum = [0.0f32; 4];
+
+			for (sample_x, sample_y) in [(x0, y0), (x1, y0), (x0, y1), (x1, y1)] {
+				let color = Color::from(&texture.get_pixel(sample_x, sample_y).unwrap());
+				sum[0] += color.r;
+				sum[1] += color.g;
+				sum[2] += color.b;
+				sum[3] += color.a;
+			}
+
+			result.set_pixel(x, y, Color::create(sum[0] * 0.25, sum[1] * 0.25, sum[2] * 0.25, sum[3] * 0.25));
+		}
+	}
+
+	result
+}
\ No newline at end of file
@@ -0,0 +1,206 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde_derive::{Serialize, Deserialize};
+use crate::raytracing::color::Color;
+
+// Maps linear radiance to [0, 1] before the sRGB transfer function
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneMapping {
+	#[default]
+	Clamp,
+	Reinhard,
+	// Reinhard that maps `white_point` to 1 instead of infinity
+	ExtendedReinhard,
+	// Stephen Hill's fit of the ACES reference rendering and sRGB output transforms
+	AcesFitted,
+	// Troy Sobotka's AgX with Benjamin Wrensch's polynomial fit of the contrast curve
+	Agx
+}
+
+impl ToneMapping {
+	fn apply(&self, rgb: [f32; 3], white_point: f32) -> [f32; 3] {
+		match self {
+			ToneMapping::Clamp => rgb,
+			ToneMapping::Reinhard => rgb.map(|value| value / (1.0 + value)),
+			ToneMapping::ExtendedReinhard => {
+				let white_point_square = white_point * white_point;
+
+				rgb.map(|value| value * (1.0 + value / white_point_square) / (1.0 + value))
+			}
+			ToneMapping::AcesFitted => {
+				let rgb = transform(&ACES_INPUT_MATRIX, rgb);
+				let rgb = rgb.map(|value| {
+					let a = value * (value + 0.0245786) - 0.000090537;
+					let b = value * (0.983729 * value + 0.432951) + 0.238081;
+
+					a / b
+				});
+
+				transform(&ACES_OUTPUT_MATRIX, rgb)
+			}
+			ToneMapping::Agx => {
+				let rgb = transform(&AGX_INSET_MATRIX, rgb);
+				let rgb = rgb.map(|value| {
+					let value = value.max(1e-10).log2().clamp(AGX_MIN_EV, AGX_MAX_EV);
+					let value = (value - AGX_MIN_EV) / (AGX_MAX_EV - AGX_MIN_EV);
+
+					agx_contrast(value)
+				});
+
+				// The curve ends up in a gamma 2.2 like encoding, linearize it for the sRGB transfer function
+				transform(&AGX_OUTSET_MATRIX, rgb).map(|value| value.max(0.0).powf(2.2))
+			}
+		}
+	}
+}
+
+impl FromStr for ToneMapping {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.trim() {
+			"clamp" => Ok(ToneMapping::Clamp),
+			"reinhard" => Ok(ToneMapping::Reinhard),
+			"extended_reinhard" => Ok(ToneMapping::ExtendedReinhard),
+			"aces_fitted" => Ok(ToneMapping::AcesFitted),
+			"agx" => Ok(ToneMapping::Agx),
+			other => Err(format!("unknown tone mapping '{}'", other))
+		}
+	}
+}
+
+impl Display for ToneMapping {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			ToneMapping::Clamp => "clamp",
+			ToneMapping::Reinhard => "reinhard",
+			ToneMapping::ExtendedReinhard => "extended_reinhard",
+			ToneMapping::AcesFitted => "aces_fitted",
+			ToneMapping::Agx => "agx"
+		};
+
+		write!(f, "{}", name)
+	}
+}
+
+// Turns the linear film into the displayed and exported image, can change without rendering again
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DisplaySettings {
+	// In stops, every step doubles the brightness
+	pub exposure: f64,
+	pub tone_mapping: ToneMapping,
+	// Radiance that extended Reinhard maps to white
+	pub white_point: f64
+}
+
+impl DisplaySettings {
+	pub fn new() -> Self {
+		Self::create(0.0, ToneMapping::Clamp)
+	}
+
+	pub fn create(exposure: f64, tone_mapping: ToneMapping) -> Self {
+		Self {
+			exposure,
+			tone_mapping,
+			white_point: 4.0
+		}
+	}
+
+	pub fn apply(&self, color: Color) -> Color {
+		let scale = 2.0f32.powf(self.exposure as f32);
+		let rgb = [color.r, color.g, color.b].map(|value| value.max(0.0) * scale);
+
+		let [r, g, b] = self.tone_mapping.apply(rgb, self.white_point.max(1e-3) as f32).map(srgb_encode);
+
+		Color {
+			r,
+			g,
+			b,
+			a: 1.0
+		}
+	}
+}
+
+impl Default for DisplaySettings {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+pub fn srgb_encode(value: f32) -> f32 {
+	let value = value.clamp(0.0, 1.0);
+
+	if value <= 0.0031308 {
+		value * 12.92
+	}
+	else {
+		1.055 * value.powf(1.0 / 2.4) - 0.055
+	}
+}
+
+pub fn srgb_decode(value: f32) -> f32 {
+	let value = value.clamp(0.0, 1.0);
+
+	if value <= 0.04045 {
+		value / 12.92
+	}
+	else {
+		((value + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+// Row major, applied to column vectors
+type Matrix3 = [[f32; 3]; 3];
+
+const ACES_INPUT_MATRIX: Matrix3 = [
+	[0.59719, 0.35458, 0.04823],
+	[0.07600, 0.90834, 0.01566],
+	[0.02840, 0.13383, 0.83777]
+];
+
+const ACES_OUTPUT_MATRIX: Matrix3 = [
+	[1.60475, -0.53108, -0.07367],
+	[-0.10208, 1.10813, -0.00605],
+	[-0.00327, -0.07276, 1.07602]
+];
+
+const AGX_INSET_MATRIX: Matrix3 = [
+	[0.8424791, 0.0784336, 0.07922375],
+	[0.04232824, 0.8784686, 0.07916613],
+	[0.04237565, 0.0784336, 0.879143]
+];
+
+const AGX_OUTSET_MATRIX: Matrix3 = [
+	[1.196879, -0.09802088, -0.09902974],
+	[-0.05289685, 1.151903, -0.09896118],
+	[-0.05297164, -0.09804345, 1.151074]
+];
+
+const AGX_MIN_EV: f32 = -12.47393;
+const AGX_MAX_EV: f32 = 4.026069;
+
+fn transform(matrix: &Matrix3, rgb: [f32; 3]) -> [f32; 3] {
+	matrix.map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2])
+}
+
+fn agx_contrast(x: f32) -> f32 {
+	let x2 = x * x;
+	let x4 = x2 * x2;
+
+	15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tone_mappings_round_trip_through_strings() {
+		for tone_mapping in [ToneMapping::Clamp, ToneMapping::Reinhard, ToneMapping::ExtendedReinhard, ToneMapping::AcesFitted, ToneMapping::Agx] {
+			assert_eq!(tone_mapping.to_string().parse::<ToneMapping>(), Ok(tone_mapping));
+		}
+
+		assert!("filmic".parse::<ToneMapping>().is_err());
+	}
+}
\ No newline at end of file
@@ -1 +1,77 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Mul, Sub};
 
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Vec2 {
+	pub x: f64,
+	pub y: f64,
+}
+
+impl Display for Vec2 {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} {}", self.x, self.y)
+	}
+}
+
+impl Vec2 {
+	pub fn new() -> Self {
+		Self {
+			x: 0.0,
+			y: 0.0
+		}
+	}
+
+	pub fn create(x: f64, y: f64) -> Self {
+		Self {
+			x,
+			y
+		}
+	}
+
+	pub fn length_squared(&self) -> f64 {
+		self.x.powi(2) + self.y.powi(2)
+	}
+
+	pub fn length(&self) -> f64 {
+		self.length_squared().sqrt()
+	}
+}
+
+impl Default for Vec2 {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Add<Vec2> for Vec2 {
+	type Output = Vec2;
+
+	fn add(self, rhs: Vec2) -> Self::Output {
+		Vec2 {
+			x: self.x + rhs.x,
+			y: self.y + rhs.y
+		}
+	}
+}
+
+impl Sub<Vec2> for Vec2 {
+	type Output = Vec2;
+
+	fn sub(self, rhs: Vec2) -> Self::Output {
+		Vec2 {
+			x: self.x - rhs.x,
+			y: self.y - rhs.y
+		}
+	}
+}
+
+impl Mul<f64> for Vec2 {
+	type Output = Vec2;
+
+	fn mul(self, rhs: f64) -> Self::Output {
+		Vec2 {
+			x: self.x * rhs,
+			y: self.y * rhs
+		}
+	}
+}
@@ -1,6 +1,6 @@
 use std::fmt::{Display, Formatter};
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
-use rand::{Rng, thread_rng};
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
+use crate::raytracing::sampler::Sampler;
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Vec3 {
@@ -52,6 +52,22 @@ impl Vec3 {
 		}
 	}
 
+	pub fn component_min(lhs: Vec3, rhs: Vec3) -> Vec3 {
+		Vec3 {
+			x: lhs.x.min(rhs.x),
+			y: lhs.y.min(rhs.y),
+			z: lhs.z.min(rhs.z),
+		}
+	}
+
+	pub fn component_max(lhs: Vec3, rhs: Vec3) -> Vec3 {
+		Vec3 {
+			x: lhs.x.max(rhs.x),
+			y: lhs.y.max(rhs.y),
+			z: lhs.z.max(rhs.z),
+		}
+	}
+
 	pub fn normalize(&mut self) {
 		*self /= self.length()
 	}
@@ -60,34 +76,43 @@ impl Vec3 {
 		self / self.length()
 	}
 
-	pub fn random_0_1() -> Self {
-		Self::random(0.0, 1.0)
+	pub fn random_0_1(sampler: &mut dyn Sampler) -> Self {
+		Self::random(sampler, 0.0, 1.0)
 	}
 
-	pub fn random(min: f64, max: f64) -> Self {
-		let mut rand = thread_rng();
-
+	pub fn random(sampler: &mut dyn Sampler, min: f64, max: f64) -> Self {
 		Self {
-			x: rand.gen_range(min..max),
-			y: rand.gen_range(min..max),
-			z: rand.gen_range(min..max)
+			x: sampler.next_range(min, max),
+			y: sampler.next_range(min, max),
+			z: sampler.next_range(min, max)
 		}
 	}
 
-	pub fn random_in_unit_sphere() -> Self {
-		let mut rand = thread_rng();
-		let direction = Self::random_0_1().normalized();
-		let t = rand.gen_range(-1.0..=1.0);
+	pub fn random_in_unit_sphere(sampler: &mut dyn Sampler) -> Self {
+		loop {
+			let point = Self::random(sampler, -1.0, 1.0);
 
-		direction * t
+			if point.length_squared() < 1.0 {
+				return point;
+			}
+		}
 	}
 
-	pub fn random_normalized() -> Self {
-		Self::random_in_unit_sphere().normalized()
+	pub fn random_normalized(sampler: &mut dyn Sampler) -> Self {
+		let (z, phi) = sampler.next_2d();
+		let z = 1.0 - 2.0 * z;
+		let phi = phi * std::f64::consts::TAU;
+		let radius = (1.0 - z * z).max(0.0).sqrt();
+
+		Vec3 {
+			x: radius * phi.cos(),
+			y: radius * phi.sin(),
+			z
+		}
 	}
 
-	pub fn random_in_hemisphere(normal: Vec3) -> Self {
-		let in_unit_sphere = Self::random_in_unit_sphere();
+	pub fn random_in_hemisphere(sampler: &mut dyn Sampler, normal: Vec3) -> Self {
+		let in_unit_sphere = Self::random_in_unit_sphere(sampler);
 		if Self::dot(in_unit_sphere, normal) > 0.0 {
 			in_unit_sphere
 		}
@@ -96,11 +121,9 @@ impl Vec3 {
 		}
 	}
 
-	pub fn random_in_unit_disk() -> Self {
-		let mut rand = thread_rng();
-
-		let angle: f64 = rand.gen_range(0.0..=360.0);
-		let distance: f64 = rand.gen_range(0.0..=1.0);
+	pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Self {
+		let (angle, distance) = sampler.next_2d();
+		let angle = angle * 360.0;
 
 		let x = angle.to_radians().cos();
 		let y = angle.to_radians().sin();
@@ -114,8 +137,47 @@ impl Vec3 {
 		vec * distance
 	}
 
+	pub fn orthonormal_basis(self) -> (Vec3, Vec3) {
+		let sign = 1.0_f64.copysign(self.z);
+		let a = -1.0 / (sign + self.z);
+		let b = self.x * self.y * a;
+
+		let tangent = Vec3::create(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x);
+		let bitangent = Vec3::create(b, sign + self.y * self.y * a, -self.y);
+
+		(tangent, bitangent)
+	}
+
 	pub fn near_zero(&self, threshold: f64) -> bool {
-		self.x < threshold && self.y < threshold && self.z < threshold
+		self.x.abs() < threshold && self.y.abs() < threshold && self.z.abs() < threshold
+	}
+
+	pub fn abs(self) -> Vec3 {
+		Vec3 {
+			x: self.x.abs(),
+			y: self.y.abs(),
+			z: self.z.abs()
+		}
+	}
+
+	pub fn max_dimension(&self) -> usize {
+		if self.x > self.y {
+			if self.x > self.z { 0 } else { 2 }
+		}
+		else if self.y > self.z { 1 } else { 2 }
+	}
+}
+
+impl Index<usize> for Vec3 {
+	type Output = f64;
+
+	fn index(&self, index: usize) -> &Self::Output {
+		match index {
+			0 => &self.x,
+			1 => &self.y,
+			2 => &self.z,
+			_ => panic!("Vec3 index out of range: {}", index)
+		}
 	}
 }
 
@@ -130,9 +192,9 @@ impl Neg for Vec3 {
 
 	fn neg(self) -> Self::Output {
 		Vec3 {
-			x: self.x * -1.0,
-			y: self.y * -1.0,
-			z: self.z * -1.0
+			x: -self.x,
+			y: -self.y,
+			z: -self.z
 		}
 	}
 }
@@ -0,0 +1,87 @@
+pub mod obj;
+pub mod mtl;
+
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::SplitWhitespace;
+use image::ImageError;
+
+#[derive(Debug)]
+pub enum WavefrontError {
+	Io {
+		path: PathBuf,
+		error: std::io::Error
+	},
+	Parse {
+		path: PathBuf,
+		line: usize,
+		message: String
+	},
+	Texture {
+		path: PathBuf,
+		error: ImageError
+	}
+}
+
+impl Display for WavefrontError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			WavefrontError::Io { path, error } => {
+				write!(f, "{}: {}", path.display(), error)
+			}
+			WavefrontError::Parse { path, line, message } => {
+				write!(f, "{}:{}: {}", path.display(), line, message)
+			}
+			WavefrontError::Texture { path, error } => {
+				write!(f, "{}: {}", path.display(), error)
+			}
+		}
+	}
+}
+
+impl std::error::Error for WavefrontError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			WavefrontError::Io { error, .. } => Some(error),
+			WavefrontError::Parse { .. } => None,
+			WavefrontError::Texture { error, .. } => Some(error)
+		}
+	}
+}
+
+pub(crate) struct LineContext<'a> {
+	pub path: &'a Path,
+	pub line: usize
+}
+
+impl<'a> LineContext<'a> {
+	pub fn error<S: Into<String>>(&self, message: S) -> WavefrontError {
+		WavefrontError::Parse {
+			path: self.path.to_path_buf(),
+			line: self.line,
+			message: message.into()
+		}
+	}
+
+	// Problems the importer can recover from, reported the same way as errors
+	pub fn warn<S: AsRef<str>>(&self, message: S) {
+		eprintln!("{}:{}: warning: {}", self.path.display(), self.line, message.as_ref());
+	}
+
+	pub fn parse_f64(&self, value: Option<&str>, name: &str) -> Result<f64, WavefrontError> {
+		let value = value.ok_or_else(|| self.error(format!("missing {}", name)))?;
+
+		value.parse::<f64>()
+			.map_err(|_| self.error(format!("invalid number '{}' for {}", value, name)))
+	}
+
+	pub fn parse_f64s<const N: usize>(&self, values: &mut SplitWhitespace, name: &str) -> Result<[f64; N], WavefrontError> {
+		let mut result = [0.0; N];
+
+		for value in result.iter_mut() {
+			*value = self.parse_f64(values.next(), name)?;
+		}
+
+		Ok(result)
+	}
+}
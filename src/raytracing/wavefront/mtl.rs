@@ -0,0 +1,252 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::image::load_texture;
+use crate::raytracing::color::Color;
+use crate::raytracing::materials::dielectric::Dielectric;
+use crate::raytracing::materials::lambertian::Lambertian;
+use crate::raytracing::materials::Material;
+use crate::raytracing::materials::metal::Metal;
+use crate::raytracing::texture::TextureWrapMode;
+use crate::raytracing::texture_sampling::{MipMap, TextureFilter};
+use crate::raytracing::wavefront::{LineContext, WavefrontError};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MtlMaterial {
+	pub name: String,
+	pub diffuse: Color,
+	pub specular: Color,
+	pub specular_exponent: f64,
+	pub index_of_refraction: f64,
+	pub dissolve: f64,
+	pub illumination_model: Option<u32>,
+	pub diffuse_map: Option<PathBuf>
+}
+
+impl MtlMaterial {
+	pub fn new(name: String) -> Self {
+		Self {
+			name,
+			diffuse: Color::create(0.8, 0.8, 0.8, 1.0),
+			specular: Color::create(0.0, 0.0, 0.0, 1.0),
+			specular_exponent: 0.0,
+			index_of_refraction: 1.0,
+			dissolve: 1.0,
+			illumination_model: None,
+			diffuse_map: None
+		}
+	}
+
+	pub fn is_transparent(&self) -> bool {
+		self.dissolve < 1.0 || matches!(self.illumination_model, Some(4) | Some(6) | Some(7) | Some(9))
+	}
+
+	pub fn is_metallic(&self) -> bool {
+		let specular = self.specular.r.max(self.specular.g).max(self.specular.b);
+		let diffuse = self.diffuse.r.max(self.diffuse.g).max(self.diffuse.b);
+
+		specular > 0.0 && (specular >= diffuse || self.illumination_model == Some(3))
+	}
+
+	// Loads the diffuse map, it replaces the diffuse color instead of tinting it
+	pub fn to_material(&self) -> Result<Arc<dyn Material + Send + Sync>, WavefrontError> {
+		if self.is_transparent() {
+			let index_of_refraction = if self.index_of_refraction > 1.0 {
+				self.index_of_refraction
+			}
+			else {
+				1.5
+			};
+
+			return Ok(Arc::new(Dielectric::create(index_of_refraction)));
+		}
+
+		if self.is_metallic() {
+			// Blinn-Phong exponent to roughness, used as the metal's fuzz radius
+			let fuzz = (2.0 / (self.specular_exponent.max(0.0) + 2.0)).sqrt();
+
+			return Ok(Arc::new(Metal::create(self.specular, fuzz.clamp(0.0, 1.0))));
+		}
+
+		match &self.diffuse_map {
+			None => Ok(Arc::new(Lambertian::create(self.diffuse))),
+			Some(path) => {
+				let texture = load_texture(path, TextureWrapMode::Repeat).map_err(|error| WavefrontError::Texture {
+					path: path.clone(),
+					error
+				})?;
+
+				Ok(Arc::new(Lambertian::create_textured(Arc::new(MipMap::create(texture)), TextureFilter::Trilinear)))
+			}
+		}
+	}
+}
+
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<Vec<MtlMaterial>, WavefrontError> {
+	let path = path.as_ref().to_path_buf();
+
+	let source = std::fs::read_to_string(&path).map_err(|error| WavefrontError::Io {
+		path: path.clone(),
+		error
+	})?;
+
+	parse_mtl(&source, &path)
+}
+
+pub fn parse_mtl(source: &str, path: &Path) -> Result<Vec<MtlMaterial>, WavefrontError> {
+	let base_directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+	let mut materials = vec![];
+	let mut current: Option<MtlMaterial> = None;
+
+	for (index, line) in source.lines().enumerate() {
+		let context = LineContext {
+			path,
+			line: index + 1
+		};
+
+		let line = match line.find('#') {
+			None => line,
+			Some(comment_start) => &line[..comment_start]
+		};
+
+		let mut values = line.split_whitespace();
+
+		let keyword = match values.next() {
+			None => continue,
+			Some(keyword) => keyword
+		};
+
+		if keyword == "newmtl" {
+			let name = values.collect::<Vec<&str>>().join(" ");
+			if name.is_empty() {
+				return Err(context.error("newmtl without a material name"));
+			}
+
+			if let Some(material) = current.take() {
+				materials.push(material);
+			}
+			current = Some(MtlMaterial::new(name));
+			continue;
+		}
+
+		let material = match current.as_mut() {
+			None => {
+				return Err(context.error(format!("'{}' before the first newmtl", keyword)));
+			}
+			Some(material) => material
+		};
+
+		match keyword {
+			"Kd" => material.diffuse = parse_color(&context, values, keyword)?,
+			"Ks" => material.specular = parse_color(&context, values, keyword)?,
+			"Ns" => material.specular_exponent = context.parse_f64(values.next(), keyword)?,
+			"Ni" => material.index_of_refraction = context.parse_f64(values.next(), keyword)?,
+			"d" => {
+				let mut value = values.next();
+				if value == Some("-halo") {
+					value = values.next();
+				}
+				material.dissolve = context.parse_f64(value, keyword)?;
+			}
+			"Tr" => material.dissolve = 1.0 - context.parse_f64(values.next(), keyword)?,
+			"illum" => {
+				let value = values.next().ok_or_else(|| context.error("missing illum"))?;
+				let value = value.parse::<u32>()
+					.map_err(|_| context.error(format!("invalid illumination model '{}'", value)))?;
+
+				material.illumination_model = Some(value);
+			}
+			"map_Kd" => {
+				let file_name = parse_map_file_name(values);
+				if file_name.is_empty() {
+					return Err(context.error("map_Kd without a file name"));
+				}
+				material.diffuse_map = Some(base_directory.join(file_name));
+			}
+			_ => {}
+		}
+	}
+
+	if let Some(material) = current.take() {
+		materials.push(material);
+	}
+
+	Ok(materials)
+}
+
+fn parse_color(context: &LineContext, values: std::str::SplitWhitespace, name: &str) -> Result<Color, WavefrontError> {
+	let values: Vec<&str> = values.collect();
+
+	match values.first() {
+		Some(&"spectral") | Some(&"xyz") => {
+			return Err(context.error(format!("unsupported {} color format '{}'", name, values[0])));
+		}
+		_ => {}
+	}
+
+	let r = context.parse_f64(values.first().copied(), name)?;
+	let g = match values.get(1) {
+		None => r,
+		Some(value) => context.parse_f64(Some(value), name)?
+	};
+	let b = match values.get(2) {
+		None => r,
+		Some(value) => context.parse_f64(Some(value), name)?
+	};
+
+	Ok(Color::create(r as f32, g as f32, b as f32, 1.0))
+}
+
+
+fn parse_map_file_name(values: std::str::SplitWhitespace) -> String {
+	let values: Vec<&str> = values.collect();
+
+	// Options like "-s 1 1 1" or "-clamp on" come before the file name
+	let mut index = 0;
+	while index < values.len() && values[index].starts_with('-') {
+		index += 1;
+		while index < values.len() && (values[index].parse::<f64>().is_ok() || values[index] == "on" || values[index] == "off") {
+			index += 1;
+		}
+	}
+
+	values[index..].join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn error_line(source: &str) -> usize {
+		match parse_mtl(source, Path::new("test.mtl")) {
+			Err(WavefrontError::Parse { line, .. }) => line,
+			Err(error) => panic!("expected a parse error, got {}", error),
+			Ok(_) => panic!("expected a parse error")
+		}
+	}
+
+	#[test]
+	fn parses_materials() {
+		let materials = parse_mtl("newmtl red\nKd 1 0 0\n\nnewmtl glass\nNi 1.45\nd 0.5\n", Path::new("test.mtl")).unwrap();
+
+		assert_eq!(materials.len(), 2);
+		assert_eq!(materials[0].diffuse, Color::create(1.0, 0.0, 0.0, 1.0));
+		assert!(materials[1].is_transparent());
+		assert_eq!(materials[1].index_of_refraction, 1.45);
+	}
+
+	#[test]
+	fn reports_the_line_of_invalid_colors() {
+		assert_eq!(error_line("newmtl red\nNs 10\nKd 1 red 0\n"), 3);
+	}
+
+	#[test]
+	fn reports_the_line_of_statements_before_newmtl() {
+		assert_eq!(error_line("# header\nKd 1 0 0\n"), 2);
+	}
+
+	#[test]
+	fn reports_the_line_of_invalid_illumination_models() {
+		assert_eq!(error_line("newmtl red\nillum -1\n"), 2);
+	}
+}
@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use crate::raytracing::color::Color;
+use crate::raytracing::hittable::triangle_mesh::{MeshTriangle, TriangleMesh};
+use crate::raytracing::materials::lambertian::Lambertian;
+use crate::raytracing::materials::Material;
+use crate::raytracing::vector_2d::Vec2;
+use crate::raytracing::vector_3d::Vec3;
+use crate::raytracing::wavefront::mtl::{load_mtl, MtlMaterial};
+use crate::raytracing::wavefront::{LineContext, WavefrontError};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct FaceVertex {
+	vertex: usize,
+	uv: Option<usize>,
+	normal: Option<usize>
+}
+
+#[derive(Default)]
+struct FaceGroup {
+	object_name: String,
+	material_name: Option<String>,
+	faces: Vec<[FaceVertex; 3]>
+}
+
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Vec<TriangleMesh>, WavefrontError> {
+	let path = path.as_ref().to_path_buf();
+
+	let source = std::fs::read_to_string(&path).map_err(|error| WavefrontError::Io {
+		path: path.clone(),
+		error
+	})?;
+
+	parse_obj(&source, &path)
+}
+
+pub fn parse_obj(source: &str, path: &Path) -> Result<Vec<TriangleMesh>, WavefrontError> {
+	let base_directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+	let mut vertices: Vec<Vec3> = vec![];
+	let mut normals: Vec<Vec3> = vec![];
+	let mut uvs: Vec<Vec2> = vec![];
+
+	let mut mtl_materials: HashMap<String, MtlMaterial> = HashMap::new();
+
+	let mut groups: Vec<FaceGroup> = vec![];
+	let mut current_object = String::new();
+	let mut current_material: Option<String> = None;
+
+	for (index, line) in source.lines().enumerate() {
+		let context = LineContext {
+			path,
+			line: index + 1
+		};
+
+		let line = match line.find('#') {
+			None => line,
+			Some(comment_start) => &line[..comment_start]
+		};
+
+		let mut values = line.split_whitespace();
+
+		let keyword = match values.next() {
+			None => continue,
+			Some(keyword) => keyword
+		};
+
+		match keyword {
+			"v" => {
+				let [x, y, z] = context.parse_f64s::<3>(&mut values, "vertex position")?;
+				vertices.push(Vec3::create(x, y, z));
+			}
+			"vn" => {
+				let [x, y, z] = context.parse_f64s::<3>(&mut values, "vertex normal")?;
+				normals.push(Vec3::create(x, y, z));
+			}
+			"vt" => {
+				let u = context.parse_f64(values.next(), "texture coordinate")?;
+				let v = match values.next() {
+					None => 0.0,
+					Some(value) => context.parse_f64(Some(value), "texture coordinate")?
+				};
+				uvs.push(Vec2::create(u, v));
+			}
+			"f" => {
+				let face: Vec<FaceVertex> = values
+					.map(|value| parse_face_vertex(&context, value, vertices.len(), uvs.len(), normals.len()))
+					.collect::<Result<_, _>>()?;
+
+				if face.len() < 3 {
+					return Err(context.error(format!("face with {} vertices, at least 3 are required", face.len())));
+				}
+
+				let group = current_group(&mut groups, &current_object, &current_material);
+
+				for i in 1..face.len() - 1 {
+					group.faces.push([face[0], face[i], face[i + 1]]);
+				}
+			}
+			"o" | "g" => {
+				current_object = values.collect::<Vec<&str>>().join(" ");
+			}
+			"usemtl" => {
+				let name = values.collect::<Vec<&str>>().join(" ");
+
+				// Exporters often reference materials that never made it into the MTL file
+				if mtl_materials.contains_key(&name) {
+					current_material = Some(name);
+				}
+				else {
+					context.warn(format!("unknown material '{}', using the default material", name));
+					current_material = None;
+				}
+			}
+			"mtllib" => {
+				let mut found = false;
+				for file_name in values {
+					found = true;
+
+					let materials = match load_mtl(base_directory.join(file_name)) {
+						Ok(materials) => materials,
+						Err(WavefrontError::Io { path, error }) => {
+							context.warn(format!("can't read material library {}: {}", path.display(), error));
+							continue;
+						}
+						Err(error) => return Err(error)
+					};
+
+					for material in materials {
+						mtl_materials.insert(material.name.clone(), material);
+					}
+				}
+				if !found {
+					return Err(context.error("mtllib without a file name"));
+				}
+			}
+			_ => {}
+		}
+	}
+
+	let default_material: Arc<dyn Material + Send + Sync> = Arc::new(Lambertian::create(Color::create(0.8, 0.8, 0.8, 1.0)));
+	let mut materials: HashMap<String, Arc<dyn Material + Send + Sync>> = HashMap::new();
+
+	let mut meshes = vec![];
+
+	for group in groups {
+		if group.faces.is_empty() {
+			continue;
+		}
+
+		let material = match &group.material_name {
+			None => default_material.clone(),
+			Some(name) => match materials.get(name) {
+				Some(material) => material.clone(),
+				None => {
+					let material = mtl_materials[name].to_material()?;
+					materials.insert(name.clone(), material.clone());
+					material
+				}
+			}
+		};
+
+		meshes.push(build_mesh(&group, &vertices, &normals, &uvs, material));
+	}
+
+	Ok(meshes)
+}
+
+fn current_group<'a>(groups: &'a mut Vec<FaceGroup>, object_name: &str, material_name: &Option<String>) -> &'a mut FaceGroup {
+	let position = groups.iter()
+		.position(|group| group.object_name == object_name && &group.material_name == material_name);
+
+	match position {
+		Some(position) => &mut groups[position],
+		None => {
+			groups.push(FaceGroup {
+				object_name: object_name.to_string(),
+				material_name: material_name.clone(),
+				faces: vec![]
+			});
+			groups.last_mut().unwrap()
+		}
+	}
+}
+
+fn parse_face_vertex(context: &LineContext, value: &str, vertex_count: usize, uv_count: usize, normal_count: usize) -> Result<FaceVertex, WavefrontError> {
+	let mut parts = value.split('/');
+
+	let vertex = parse_index(context, parts.next(), vertex_count, "vertex")?
+		.ok_or_else(|| context.error(format!("face vertex '{}' has no position index", value)))?;
+	let uv = parse_index(context, parts.next(), uv_count, "texture coordinate")?;
+	let normal = parse_index(context, parts.next(), normal_count, "normal")?;
+
+	if parts.next().is_some() {
+		return Err(context.error(format!("malformed face vertex '{}'", value)));
+	}
+
+	Ok(FaceVertex {
+		vertex,
+		uv,
+		normal
+	})
+}
+
+fn parse_index(context: &LineContext, value: Option<&str>, count: usize, name: &str) -> Result<Option<usize>, WavefrontError> {
+	let value = match value {
+		None | Some("") => return Ok(None),
+		Some(value) => value
+	};
+
+	let index = value.parse::<i64>()
+		.map_err(|_| context.error(format!("invalid {} index '{}'", name, value)))?;
+
+	let resolved = if index > 0 {
+		index - 1
+	}
+	else {
+		count as i64 + index
+	};
+
+	if index == 0 || resolved < 0 || resolved >= count as i64 {
+		return Err(context.error(format!("{} index {} out of range, {} defined", name, index, count)));
+	}
+
+	Ok(Some(resolved as usize))
+}
+
+fn build_mesh(group: &FaceGroup, vertices: &[Vec3], normals: &[Vec3], uvs: &[Vec2], material: Arc<dyn Material + Send + Sync>) -> TriangleMesh {
+	let mut vertex_remap = HashMap::new();
+	let mut normal_remap = HashMap::new();
+	let mut uv_remap = HashMap::new();
+
+	let mut mesh_vertices = vec![];
+	let mut mesh_normals = vec![];
+	let mut mesh_uvs = vec![];
+
+	let mut triangles = Vec::with_capacity(group.faces.len());
+
+	for face in &group.faces {
+		let triangle_vertices = face.map(|corner| {
+			remap(corner.vertex, &mut vertex_remap, &mut mesh_vertices, vertices)
+		});
+
+		let triangle_normals = if face.iter().all(|corner| corner.normal.is_some()) {
+			Some(face.map(|corner| {
+				remap(corner.normal.unwrap(), &mut normal_remap, &mut mesh_normals, normals)
+			}))
+		}
+		else {
+			None
+		};
+
+		let triangle_uvs = if face.iter().all(|corner| corner.uv.is_some()) {
+			Some(face.map(|corner| {
+				remap(corner.uv.unwrap(), &mut uv_remap, &mut mesh_uvs, uvs)
+			}))
+		}
+		else {
+			None
+		};
+
+		triangles.push(MeshTriangle {
+			vertices: triangle_vertices,
+			normals: triangle_normals,
+			uvs: triangle_uvs
+		});
+	}
+
+	TriangleMesh::create(mesh_vertices, mesh_normals, mesh_uvs, triangles, material)
+}
+
+fn remap<T: Copy>(index: usize, remap: &mut HashMap<usize, usize>, target: &mut Vec<T>, source: &[T]) -> usize {
+	*remap.entry(index).or_insert_with(|| {
+		target.push(source[index]);
+		target.len() - 1
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(source: &str) -> Result<Vec<TriangleMesh>, WavefrontError> {
+		parse_obj(source, Path::new("test.obj"))
+	}
+
+	fn error_line(result: Result<Vec<TriangleMesh>, WavefrontError>) -> usize {
+		match result {
+			Err(WavefrontError::Parse { line, .. }) => line,
+			Err(error) => panic!("expected a parse error, got {}", error),
+			Ok(_) => panic!("expected a parse error")
+		}
+	}
+
+	#[test]
+	fn triangulates_polygons() {
+		let meshes = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+
+		assert_eq!(meshes.len(), 1);
+		assert_eq!(meshes[0].triangle_count(), 2);
+	}
+
+	#[test]
+	fn resolves_negative_indices() {
+		let meshes = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nf -3 -2 -1\n").unwrap();
+
+		assert_eq!(meshes[0].triangle_vertices(0), [Vec3::create(0.0, 0.0, 0.0), Vec3::create(1.0, 0.0, 0.0), Vec3::create(1.0, 1.0, 0.0)]);
+	}
+
+	#[test]
+	fn reports_the_line_of_invalid_numbers() {
+		assert_eq!(error_line(parse("v 0 0 0\n# comment\n\nv 1 x 0\n")), 4);
+	}
+
+	#[test]
+	fn reports_the_line_of_out_of_range_indices() {
+		assert_eq!(error_line(parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3\nf 1 2 4\n")), 5);
+	}
+
+	#[test]
+	fn reports_the_line_of_degenerate_faces() {
+		assert_eq!(error_line(parse("v 0 0 0\nv 1 0 0\nf 1 2\n")), 3);
+	}
+
+	#[test]
+	fn unknown_materials_fall_back_to_the_default() {
+		let meshes = parse("mtllib missing.mtl\nusemtl missing\nv 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3\n").unwrap();
+
+		assert_eq!(meshes.len(), 1);
+	}
+}
@@ -1,7 +1,8 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use rand::prelude::SliceRandom;
 use rand::thread_rng;
 use workers_pool::WorkersPool;
-use crate::gui::{GUIModeSettings, GUIModeTree};
 use crate::raytracing::RaytracingWorker;
 use crate::raytracing::work::RaytracingWork;
 
@@ -25,7 +26,7 @@ pub enum TileDimensionOrder {
 	Random
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GenerationMode {
 	PixelRandom,
 	Line {
@@ -118,11 +119,11 @@ impl GenerationMode {
 				let mut horizontal_count = width / tile_width;
 				let mut vertical_count = height / tile_height;
 
-				let right_border = width % tile_width != 0;
-				let bottom_border = height % tile_height != 0;
+				let right_border = !width.is_multiple_of(*tile_width);
+				let bottom_border = !height.is_multiple_of(*tile_height);
 
-				let last_tile_width = tile_width + (width % tile_width);
-				let last_tile_height = tile_height + (height % tile_height);
+				let last_tile_width = width % tile_width;
+				let last_tile_height = height % tile_height;
 
 				if right_border {
 					horizontal_count += 1;
@@ -200,94 +201,212 @@ impl GenerationMode {
 		work
 	}
 
-	pub fn from_gui_mode_tree(tree: &GUIModeTree) -> Self {
-		match &tree.settings {
-			GUIModeSettings::PixelRandom => {
-				Self::PixelRandom
+	fn axis_order_range_to_values(start: u32, end: u32, order: TileAxisOrder) -> Vec<u32> {
+		let mut rand = thread_rng();
+
+		match order {
+			TileAxisOrder::Forward => {
+				(start..end).collect()
 			}
-			GUIModeSettings::Line { reverse_order_horizontal, random_order_vertical, transparent } => {
-				Self::Line {
-					reverse_order_horizontal: *reverse_order_horizontal,
-					random_order_vertical: *random_order_vertical,
-					transparent: *transparent
+			TileAxisOrder::Reverse => {
+				(start..end).rev().collect()
+			}
+			TileAxisOrder::Random => {
+				let mut values: Vec<u32> = (start..end).collect();
+				values.shuffle(&mut rand);
+				values
+			}
+		}
+	}
+}
+
+// Textual form used by the command line renderer:
+// "pixel", "line[,reverse][,random][,transparent]", "stripe[,random][,reverse][,transparent]" and
+// "tile,<width>x<height>[,line-first|stripe-first|random][,reverse-x|random-x][,reverse-y|random-y][,transparent][/<sub mode>]"
+impl FromStr for GenerationMode {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let (mode, sub_mode) = match value.split_once('/') {
+			None => (value, None),
+			Some((mode, sub_mode)) => (mode, Some(sub_mode))
+		};
+
+		let mut parts = mode.split(',').map(str::trim);
+		let kind = parts.next().unwrap_or_default();
+		let options: Vec<&str> = parts.collect();
+
+		if kind != "tile" && sub_mode.is_some() {
+			return Err(format!("generation mode '{}' does not take a sub mode", kind));
+		}
+
+		match kind {
+			"pixel" => {
+				match options.first() {
+					None => Ok(Self::PixelRandom),
+					Some(option) => Err(unknown_option(kind, option))
 				}
 			}
-			GUIModeSettings::Stripe { random_order_horizontal, reverse_order_vertical, transparent } => {
-				Self::Stripe {
-					random_order_horizontal: *random_order_horizontal,
-					reverse_order_vertical: *reverse_order_vertical,
-					transparent: *transparent
+			"line" => {
+				let mut reverse_order_horizontal = false;
+				let mut random_order_vertical = false;
+				let mut transparent = false;
+
+				for option in options {
+					match option {
+						"reverse" => reverse_order_horizontal = true,
+						"random" => random_order_vertical = true,
+						"transparent" => transparent = true,
+						_ => return Err(unknown_option(kind, option))
+					}
 				}
+
+				Ok(Self::Line {
+					reverse_order_horizontal,
+					random_order_vertical,
+					transparent
+				})
 			}
-			GUIModeSettings::Tile { tile_width, tile_height, dimension_order, horizontal_order, vertical_order, transparent } => {
-				let dimension_order = Self::indices_to_tile_dimension_order(*horizontal_order, *vertical_order, *dimension_order);
+			"stripe" => {
+				let mut random_order_horizontal = false;
+				let mut reverse_order_vertical = false;
+				let mut transparent = false;
+
+				for option in options {
+					match option {
+						"random" => random_order_horizontal = true,
+						"reverse" => reverse_order_vertical = true,
+						"transparent" => transparent = true,
+						_ => return Err(unknown_option(kind, option))
+					}
+				}
 
-				let sub_tree = match &tree.sub_tree {
-					None => panic!(),
-					Some(sub_tree) => sub_tree
+				Ok(Self::Stripe {
+					random_order_horizontal,
+					reverse_order_vertical,
+					transparent
+				})
+			}
+			"tile" => {
+				let (size, options) = match options.split_first() {
+					None => return Err("tile mode requires a tile size, e.g. 'tile,32x32'".to_string()),
+					Some(split) => split
+				};
+
+				let (tile_width, tile_height) = parse_tile_size(size)?;
+
+				let mut transparent = false;
+				let mut stripe_first = false;
+				let mut random_order = false;
+				let mut horizontal_order = TileAxisOrder::Forward;
+				let mut vertical_order = TileAxisOrder::Forward;
+
+				for option in options {
+					match *option {
+						"transparent" => transparent = true,
+						"line-first" => stripe_first = false,
+						"stripe-first" => stripe_first = true,
+						"random" => random_order = true,
+						"reverse-x" => horizontal_order = TileAxisOrder::Reverse,
+						"random-x" => horizontal_order = TileAxisOrder::Random,
+						"reverse-y" => vertical_order = TileAxisOrder::Reverse,
+						"random-y" => vertical_order = TileAxisOrder::Random,
+						_ => return Err(unknown_option(kind, option))
+					}
+				}
+
+				let dimension_order = if random_order {
+					TileDimensionOrder::Random
+				}
+				else if stripe_first {
+					TileDimensionOrder::StripeFirst {
+						horizontal_order,
+						vertical_order
+					}
+				}
+				else {
+					TileDimensionOrder::LineFirst {
+						horizontal_order,
+						vertical_order
+					}
 				};
 
-				let sub_generation_mode = Self::from_gui_mode_tree(sub_tree);
+				let sub_generation_mode = match sub_mode {
+					None => Self::PixelRandom,
+					Some(sub_mode) => sub_mode.parse()?
+				};
 
-				Self::Tile {
-					tile_width: *tile_width,
-					tile_height: *tile_height,
+				Ok(Self::Tile {
+					tile_width,
+					tile_height,
+					transparent,
 					dimension_order,
-					transparent: *transparent,
 					sub_generation_mode: Box::new(sub_generation_mode)
-				}
+				})
 			}
+			_ => Err(format!("unknown generation mode '{}', expected pixel, line, stripe or tile", kind))
 		}
 	}
+}
 
-	fn indices_to_tile_dimension_order(horizontal_order: usize, vertical_order: usize, dimension_order: usize) -> TileDimensionOrder {
-		let horizontal_order = Self::index_to_tile_axis_order(horizontal_order);
-		let vertical_order = Self::index_to_tile_axis_order(vertical_order);
+impl Display for GenerationMode {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let flag = |enabled: bool, name: &'static str| if enabled { name } else { "" };
 
-		match dimension_order {
-			0 => {
-				TileDimensionOrder::LineFirst {
-					horizontal_order,
-					vertical_order
-				}
+		match self {
+			GenerationMode::PixelRandom => write!(f, "pixel"),
+			GenerationMode::Line { reverse_order_horizontal, random_order_vertical, transparent } => {
+				write!(f, "line{}{}{}", flag(*reverse_order_horizontal, ",reverse"), flag(*random_order_vertical, ",random"), flag(*transparent, ",transparent"))
 			}
-			1 => {
-				TileDimensionOrder::StripeFirst {
-					horizontal_order,
-					vertical_order
-				}
+			GenerationMode::Stripe { random_order_horizontal, reverse_order_vertical, transparent } => {
+				write!(f, "stripe{}{}{}", flag(*random_order_horizontal, ",random"), flag(*reverse_order_vertical, ",reverse"), flag(*transparent, ",transparent"))
 			}
-			_ => {
-				TileDimensionOrder::Random
+			GenerationMode::Tile { tile_width, tile_height, transparent, dimension_order, sub_generation_mode } => {
+				write!(f, "tile,{}x{}", tile_width, tile_height)?;
+
+				let axis_order = |order: TileAxisOrder, axis: &str| match order {
+					TileAxisOrder::Forward => String::new(),
+					TileAxisOrder::Reverse => format!(",reverse-{}", axis),
+					TileAxisOrder::Random => format!(",random-{}", axis)
+				};
+
+				match dimension_order {
+					TileDimensionOrder::LineFirst { horizontal_order, vertical_order } => {
+						write!(f, "{}{}", axis_order(*horizontal_order, "x"), axis_order(*vertical_order, "y"))?;
+					}
+					TileDimensionOrder::StripeFirst { horizontal_order, vertical_order } => {
+						write!(f, ",stripe-first{}{}", axis_order(*horizontal_order, "x"), axis_order(*vertical_order, "y"))?;
+					}
+					TileDimensionOrder::Random => write!(f, ",random")?
+				}
+
+				write!(f, "{}", flag(*transparent, ",transparent"))?;
+
+				match sub_generation_mode.as_ref() {
+					GenerationMode::PixelRandom => Ok(()),
+					sub_generation_mode => write!(f, "/{}", sub_generation_mode)
+				}
 			}
 		}
 	}
+}
 
-	fn index_to_tile_axis_order(index: usize) -> TileAxisOrder {
-		match index {
-			1 => TileAxisOrder::Reverse,
-			2 => TileAxisOrder::Random,
-			_ => TileAxisOrder::Forward
-		}
-	}
+fn unknown_option(kind: &str, option: &str) -> String {
+	format!("unknown option '{}' for generation mode '{}'", option, kind)
+}
 
-	fn axis_order_range_to_values(start: u32, end: u32, order: TileAxisOrder) -> Vec<u32> {
-		let mut rand = thread_rng();
+fn parse_tile_size(value: &str) -> Result<(u32, u32), String> {
+	let invalid = || format!("invalid tile size '{}', expected <width>x<height>", value);
 
-		match order {
-			TileAxisOrder::Forward => {
-				(start..end).collect()
-			}
-			TileAxisOrder::Reverse => {
-				(start..end).rev().collect()
-			}
-			TileAxisOrder::Random => {
-				let mut values: Vec<u32> = (start..end).collect();
-				values.shuffle(&mut rand);
-				values
-			}
-		}
+	let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+	let width = width.parse::<u32>().map_err(|_| invalid())?;
+	let height = height.parse::<u32>().map_err(|_| invalid())?;
+
+	if width == 0 || height == 0 {
+		return Err(format!("tile size '{}' must be at least 1x1", value));
 	}
+
+	Ok((width, height))
 }
 
 pub struct RaytracingWorkGenerator {
@@ -297,11 +416,60 @@ pub struct RaytracingWorkGenerator {
 }
 
 impl RaytracingWorkGenerator {
-	pub fn generate(&self, pool: &mut WorkersPool<RaytracingWorker>) -> Result<(),()> {
+	pub(crate) fn generate(&self, pool: &mut WorkersPool<RaytracingWorker>) -> Result<(),()> {
 		let work = self.generation_mode.to_work(self.width, self.height);
 		for work in work {
 			pool.add_work(work)?;
 		}
 		Ok(())
 	}
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn round_trips_through_strings() {
+		let values = [
+			"pixel",
+			"line",
+			"line,reverse,random,transparent",
+			"stripe,random,transparent",
+			"tile,32x16",
+			"tile,8x8,stripe-first,reverse-x,random-y,transparent",
+			"tile,64x64,random/line,reverse",
+			"tile,64x64,reverse-y/tile,8x8,stripe-first/stripe,reverse"
+		];
+
+		for value in values {
+			let mode: GenerationMode = value.parse().unwrap();
+
+			assert_eq!(mode.to_string(), value);
+			assert_eq!(mode.to_string().parse::<GenerationMode>().unwrap(), mode);
+		}
+	}
+
+	#[test]
+	fn rejects_invalid_modes() {
+		assert!("spiral".parse::<GenerationMode>().is_err());
+		assert!("pixel/line".parse::<GenerationMode>().is_err());
+		assert!("line,sideways".parse::<GenerationMode>().is_err());
+		assert!("tile".parse::<GenerationMode>().is_err());
+		assert!("tile,0x8".parse::<GenerationMode>().is_err());
+	}
+
+	#[test]
+	fn tiles_cover_every_pixel_once() {
+		let mode: GenerationMode = "tile,16x16,random/line".parse().unwrap();
+		let (width, height) = (37, 23);
+
+		let mut covered = vec![0; (width * height) as usize];
+		for mut work in mode.to_work(width, height) {
+			while let Some((x, y)) = work.get_next_work_pixel() {
+				covered[(y * width + x) as usize] += 1;
+			}
+		}
+
+		assert!(covered.iter().all(|count| *count == 1));
+	}
+}
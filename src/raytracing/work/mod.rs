@@ -126,14 +126,14 @@ impl RaytracingWork {
 					if work.current_x == 0 {
 						return None;
 					}
-					work.current_x = work.current_x - 1;
+					work.current_x -= 1;
 					work.current_x
 				}
 				else {
 					if work.current_x > work.width {
 						return None;
 					}
-					work.current_x = work.current_x + 1;
+					work.current_x += 1;
 					work.current_x - 2
 				};
 
@@ -145,14 +145,14 @@ impl RaytracingWork {
 					if work.current_y == 0 {
 						return None;
 					}
-					work.current_y = work.current_y - 1;
+					work.current_y -= 1;
 					work.current_y
 				}
 				else {
 					if work.current_y > work.height {
 						return None;
 					}
-					work.current_y = work.current_y + 1;
+					work.current_y += 1;
 					work.current_y - 2
 				};
 
@@ -181,7 +181,7 @@ impl RaytracingWork {
 					}
 				}
 
-				return None;
+				None
 			}
 		}
 	}
@@ -219,11 +219,7 @@ impl RaytracingWork {
 				}
 			}
 			RaytracingWork::Tile(tile) => {
-				let new_work = tile.sub_generation_work;
-				let new_work = match new_work {
-					None => vec![],
-					Some(work) => work,
-				};
+				let new_work = tile.sub_generation_work.unwrap_or_default();
 				let new_work: Vec<RaytracingWork> = new_work.into_iter().flatten().collect();
 
 				for mut new_work in new_work {
@@ -239,20 +235,20 @@ impl RaytracingWork {
 	fn adjust_for_transparency(&mut self, x: u32, y: u32) {
 		match self {
 			RaytracingWork::Pixel(pixel) => {
-				pixel.x = pixel.x + x;
-				pixel.y = pixel.y + y;
+				pixel.x += x;
+				pixel.y += y;
 			}
 			RaytracingWork::Line(line) => {
-				line.x = line.x + x;
-				line.y = line.y + y;
+				line.x += x;
+				line.y += y;
 			}
 			RaytracingWork::Stripe(stripe) => {
-				stripe.x = stripe.x + x;
-				stripe.y = stripe.y + y;
+				stripe.x += x;
+				stripe.y += y;
 			}
 			RaytracingWork::Tile(tile) => {
-				tile.x = tile.x + x;
-				tile.y = tile.y + y;
+				tile.x += x;
+				tile.y += y;
 			}
 		}
 	}